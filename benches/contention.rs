@@ -0,0 +1,81 @@
+//! 多数のスレッドが`MakeWriter`でログを書き込むときの、ローテーションとのロックの競合を計測する。
+//!
+//! `tracing`の`fmt`レイヤーは、`make_writer`でライターを取得してからログを書式化して書き込むため、
+//! 書式化している間もライターが保持される。このベンチマークでは、書式化の代わりに短い時間待機
+//! しながら、サイズによるローテーションが頻繁に発生する状況で、`make_writer`の呼び出しにかかる
+//! 時間(ローテーションを待つ時間を含む)と、すべてのログを書き込むまでの時間を計測する。
+//!
+//! `cargo bench --bench contention`で実行する。
+
+use std::{
+    io::Write,
+    sync::{Arc, Barrier},
+    thread,
+    time::{Duration, Instant},
+};
+
+use restricted_rolling_file_appender::appenders::DailyRollingFileAppender;
+use tracing_subscriber::fmt::writer::MakeWriter;
+
+/// ログを書き込むスレッドの数。
+const THREADS: usize = 16;
+
+/// 1つのスレッドが書き込むログの数。
+const LINES_PER_THREAD: usize = 1_000;
+
+/// ライターを取得してから書き込むまでの、書式化の代わりに待機する時間。
+const FORMAT_WORK: Duration = Duration::from_micros(100);
+
+fn main() {
+    let directory = tempfile::tempdir().expect("failed to create temp dir");
+    let appender = Arc::new(
+        DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("bench")
+            .max_count(2)
+            .max_bytes(16 * 1024)
+            .build()
+            .expect("failed to build appender"),
+    );
+
+    let barrier = Arc::new(Barrier::new(THREADS + 1));
+    let handles: Vec<_> = (0..THREADS)
+        .map(|id| {
+            let appender = Arc::clone(&appender);
+            let barrier = Arc::clone(&barrier);
+            thread::spawn(move || {
+                let mut latencies = Vec::with_capacity(LINES_PER_THREAD);
+                barrier.wait();
+                for i in 0..LINES_PER_THREAD {
+                    let started = Instant::now();
+                    let mut writer = appender.make_writer();
+                    latencies.push(started.elapsed());
+                    thread::sleep(FORMAT_WORK);
+                    let line = format!("thread={} line={} message=benchmark\n", id, i);
+                    writer.write_all(line.as_bytes()).unwrap();
+                }
+                latencies
+            })
+        })
+        .collect();
+
+    barrier.wait();
+    let started = Instant::now();
+    let mut latencies: Vec<Duration> = handles
+        .into_iter()
+        .flat_map(|handle| handle.join().unwrap())
+        .collect();
+    let elapsed = started.elapsed();
+
+    latencies.sort();
+    let percentile = |p: usize| latencies[(latencies.len() - 1) * p / 100];
+    println!(
+        "{} threads, {} lines: total {:?}, make_writer p50 {:?}, p99 {:?}, max {:?}",
+        THREADS,
+        latencies.len(),
+        elapsed,
+        percentile(50),
+        percentile(99),
+        latencies[latencies.len() - 1]
+    );
+}
@@ -1,452 +1,9705 @@
 use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::{HashMap, HashSet, VecDeque},
+    env,
     fmt::Debug,
-    fs::{self, File, OpenOptions},
-    io::{self, Write},
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
     path::{Path, PathBuf},
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::{
+        atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, LazyLock, Mutex, PoisonError, TryLockError, Weak,
+    },
+    thread::{self, JoinHandle},
+    time::Duration as StdDuration,
 };
 
+#[cfg(feature = "gzip")]
+use flate2::write::GzEncoder;
 use regex::Regex;
-use time::{Duration, OffsetDateTime, Time};
+use time::{
+    format_description::{self, well_known::Rfc3339, OwnedFormatItem},
+    parsing::Parsed,
+    Date, Duration, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset,
+};
+use tracing_subscriber::{
+    fmt::format::{DefaultFields, Format},
+    registry::LookupSpan,
+};
 
-use crate::sync::{RwLock, RwLockReadGuard};
+use crate::{
+    byte_size::ByteSize,
+    error::AppenderError,
+    file_system::{FileSystem, OsFileSystem, SinkFactory, SinkFileSystem},
+    sync::RwLock,
+};
 
 /// `DailyFileAppender`
 ///
 /// `DailyFileAppender`は、ログをファイルに記録するとともに、日をまたいだとき、ログを記録する
-/// ファイルを別のファイルに切り替える。
+/// ファイルを別のファイルに切り替える。`Rotation`を設定した場合は、1時間または1分ごとに
+/// ファイルを切り替えることもできる。
+/// また、ファイルの最大サイズが設定されている場合、ファイルのサイズがその最大サイズに達したとき、
+/// ログを記録するファイルを同じ日付の別のファイルに切り替える。
 /// また、別のファイルに切り替えたとき、ログファイルの数が保存するファイルの数より多くなった場合、
 /// 最も古いファイルから削除する。
+/// ログを記録しているファイルが、ディレクトリとともに削除された場合は、ディレクトリとファイルを
+/// 作成し直して、ログを記録し続ける。
+/// 構築したとき、現在の日時より新しい日時のログファイルが存在する場合は、最も新しいファイルに
+/// 追記して、その日時を過ぎたときにローテーションする。
+///
+/// NTPによる補正などで時計が戻った場合も、ログを記録するファイルの日時を戻さない。ローテーション
+/// した後に時計が戻った場合は、時計が再び次にローテーションする日時を過ぎるまで、現在のファイルに
+/// 記録し続ける。時計が戻った状態で構築した場合も、上記のとおり最も新しいファイルから記録を再開する
+/// ため、古い日時のファイルに追記することはない。
 pub struct DailyRollingFileAppender {
-    state: Inner,
-    writer: RwLock<File>,
+    state: Arc<Inner>,
+    writer: Arc<RwLock<LogWriter>>,
+    _flusher: Option<Flusher>,
+    _scheduler: Option<Scheduler>,
 }
 
-#[derive(Debug)]
-pub struct RollingWriter<'a>(RwLockReadGuard<'a, File>);
+/// `RollingWriter`
+///
+/// `DailyRollingFileAppender`の`MakeWriter`が返却するライター。
+/// ローテーションを妨げないように、ロックは`write`や`flush`を呼び出している間のみ獲得する。
+/// そのため、`write`を呼び出すたびに、その時点でログを記録しているファイルに書き込む。
+/// レコードをまとめて書き込む設定の場合は、`write`で書き込まれたバイト列をライターが保持して、
+/// `flush`を呼び出したとき、または破棄したときに、ロックを獲得して1度に書き込む。
+///
+/// 書き込むたびにロックを獲得するため、`&RollingWriter`からも書き込める。保持しているレコードは
+/// `RefCell`で管理するため、`RollingWriter`は`Send`であるが`Sync`ではなく、共有参照を通じて
+/// 書き込めるのは同じスレッドからのみとなる。
+pub struct RollingWriter<'a>(&'a RwLock<LogWriter>, &'a Inner, RefCell<Vec<u8>>);
+
+/// ログを記録するファイルへのライター。
+///
+/// `MakeWriter`が返却する複数の`RollingWriter`から、バッファを共有して書き込めるように`Mutex`で
+/// 保護する。バッファリングしない場合は、容量0のバッファを使用する。
+type LogWriter = Mutex<BufWriter<LogFile>>;
+
+/// `FileSystem`が開いた、ログを記録するファイル。
+type LogFile = Box<dyn Write + Send>;
 
 struct Inner {
     next_date: AtomicUsize,
-    max_count: usize,
+    current_date: AtomicUsize,
+    segment: AtomicUsize,
+    rollover_pending: AtomicBool,
+    fallback: AtomicBool,
+    next_existence_check: AtomicUsize,
+    bytes_written: AtomicU64,
+    total_bytes_written: AtomicU64,
+    rollover_count: AtomicU64,
+    rolled_over: AtomicBool,
+    files_deleted: AtomicU64,
+    retention: Mutex<Retention>,
+    retention_order: RetentionOrder,
+    removal_strategy: RemovalStrategy,
+    dry_run: bool,
+    max_age_days: Option<u32>,
+    max_bytes: AtomicU64,
+    min_free_bytes: Option<u64>,
+    max_deletions_per_pass: Option<usize>,
+    keep_minimum: usize,
+    protected: Vec<String>,
+    size_suffix: SizeSuffix,
+    unique_segments: bool,
+    open_mode: OpenMode,
+    buffer_capacity: usize,
+    flush_on_write: bool,
+    fsync_on_flush: bool,
+    atomic_records: bool,
+    whole_records: bool,
     directory: PathBuf,
+    archive_directory: Option<PathBuf>,
     filename_prefix: String,
+    separator: char,
+    extension: String,
+    date_format: DateFormat,
+    log_file_regex: Regex,
+    filename_predicate: Option<FilenamePredicate>,
+    file_namer: Box<dyn FileNamer>,
+    header: Option<HeaderFn>,
+    footer: Option<Vec<u8>>,
+    record_terminator: Option<Vec<u8>>,
+    timestamp_prefix: bool,
+    compression: Compression,
+    current_link: bool,
+    part_files: bool,
+    manifest_name: Option<String>,
+    first_record: AtomicI64,
+    last_record: AtomicI64,
+    rotation: Rotation,
+    time_zone: TimeZone,
+    clock: Box<dyn Clock>,
+    file_system: Box<dyn FileSystem>,
+    open_attempts: usize,
+    on_rollover: Mutex<Option<RolloverHook>>,
+    on_planned_removal: Mutex<Option<PlannedRemovalHook>>,
+    on_error: Mutex<Option<ErrorHook>>,
+    #[cfg(feature = "process_lock")]
+    lock_path: Option<PathBuf>,
+    #[cfg(feature = "encoding")]
+    encoding: Option<&'static encoding_rs::Encoding>,
+    _directory_owner: Option<DirectoryOwner>,
 }
 
-impl DailyRollingFileAppender {
-    /// `DailyRollingFileAppender`を作成する。
+/// ファイルをローテーションしたときに呼び出す関数。
+type RolloverHook = Box<dyn FnMut(&Path) + Send>;
+
+/// 削除する代わりに報告する設定で、古いファイルを削除するときに呼び出す関数。
+type PlannedRemovalHook = Box<dyn FnMut(&Path) + Send>;
+
+/// アペンダーの内部でエラーが発生したときに呼び出す関数。
+type ErrorHook = Box<dyn FnMut(&AppenderError) + Send>;
+
+/// ファイルの日時を引数に、新しく作成したファイルの先頭に書き込むヘッダーを返却する関数。
+type HeaderFn = Box<dyn Fn(&OffsetDateTime) -> Vec<u8> + Send + Sync>;
+
+/// ファイル名を引数に、アペンダーが管理するログファイルであるかを判定する関数。
+pub type FilenamePredicate = Box<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// `FileNamer`
+///
+/// ログファイルの名前を作成して、アペンダーが管理するログファイルであるかを判定する。
+/// 日付とプロセスIDやホスト名を組み合わせるなど、独自の規則でファイルに名前を付ける場合は、
+/// このトレイトを実装して`DailyRollingFileAppenderBuilder::file_namer`に設定する。
+///
+/// `DefaultNamer`以外が作成したファイル名からは日時を解析できないため、古いファイルはファイル名の
+/// 順に削除され、ファイルを保存する日数は適用されない。ファイル名の順序が記録した順序と一致しない
+/// 場合は、`RetentionOrder::ByMtime`を設定する。
+pub trait FileNamer: Send + Sync {
+    /// ログを記録するファイルの名前を返却する。
     ///
     /// # Arguments
     ///
-    /// * directory: ファイルを作成するディレクトリ。
-    /// * file_name_prefix: ファイル名の接頭語。
-    /// * max_count: 現在ログを出力しているファイルを除いて、保存するファイルの最大数。
+    /// * date: ログファイルの日付。
     ///
     /// # Returns
     ///
-    /// `DailyRollingFileAppender`インスタンス。
-    pub fn new(
-        max_count: usize,
-        directory: impl AsRef<Path>,
-        filename_prefix: impl AsRef<Path>,
-    ) -> Self {
-        let today = today();
-        let (state, writer) = Inner::new(today, max_count, directory, filename_prefix);
-
-        Self { state, writer }
-    }
+    /// ログファイル名。
+    fn current_name(&self, date: &Date) -> String;
 
-    /// 単体テスト用に、`DailyRollingFileAppender`を作成する。
+    /// ファイルが、アペンダーが管理するログファイルであるか確認する。
+    ///
+    /// 現在ログを記録しているファイルに加えて、ローテーションしたファイルや圧縮したファイルにも
+    /// 一致させる。一致したファイルは、古いファイルを削除する対象となる。
     ///
     /// # Arguments
     ///
-    /// * directory: ファイルを作成するディレクトリ。
-    /// * file_name_prefix: ファイル名の接頭語。
-    /// * max_count: 現在ログを出力しているファイルを除いて、保存するファイルの最大数。
-    /// * date: 日付(0時0分0秒に設定された`OffsetDateTime`)。
+    /// * filename: ファイル名。
     ///
     /// # Returns
     ///
-    /// `DailyRollingFileAppender`インスタンス。
-    #[cfg(test)]
-    fn new_test(
-        max_count: usize,
-        directory: impl AsRef<Path>,
-        filename_prefix: impl AsRef<Path>,
-        date: OffsetDateTime,
-    ) -> Self {
-        let (state, writer) = Inner::new(date, max_count, directory, filename_prefix);
-
-        Self { state, writer }
-    }
-
-    #[cfg(test)]
-    fn inner(&self) -> &Inner {
-        &self.state
-    }
-}
-
-impl io::Write for DailyRollingFileAppender {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        let today = today();
-        let writer = self.writer.get_mut();
-        if let Some(current) = self.state.should_rollover() {
-            let _did_cas = self.state.advance_date(today, current);
-            debug_assert!(
-                _did_cas,
-                "if we have &mut access to the appender, \
-                no other thread can have advanced the timestamp..."
-            );
-            self.state.refresh_writer(&today, writer);
-        }
-
-        writer.write(buf)
-    }
-
-    fn flush(&mut self) -> io::Result<()> {
-        self.writer.get_mut().flush()
-    }
-}
-
-impl<'a> tracing_subscriber::fmt::writer::MakeWriter<'a> for DailyRollingFileAppender {
-    type Writer = RollingWriter<'a>;
+    /// アペンダーが管理するログファイルの場合はtrue。
+    fn is_owned(&self, filename: &str) -> bool;
 
-    fn make_writer(&'a self) -> Self::Writer {
-        let today = today();
-        if let Some(current) = self.state.should_rollover() {
-            if self.state.advance_date(today, current) {
-                self.state.refresh_writer(&today, &mut *self.writer.write());
-            }
+    /// ローテーションする間隔で切り捨てた日時と、同じ日時のファイルの連番から、ログファイルの名前を
+    /// 返却する。
+    ///
+    /// 既定の実装は、連番が0の場合は`current_name`の名前を、それ以外の場合は、その名前の後に
+    /// `.{index}`を付けた名前を返却する。
+    ///
+    /// # Arguments
+    ///
+    /// * date: ローテーションする間隔で切り捨てた日時。
+    /// * index: 同じ日時のファイルの連番。ローテーションした時刻を接尾辞とする場合は、その時刻を
+    ///   示すUnixタイムスタンプ。
+    ///
+    /// # Returns
+    ///
+    /// ログファイル名。
+    fn segment_name(&self, date: &OffsetDateTime, index: usize) -> String {
+        let name = self.current_name(&date.date());
+        match index {
+            0 => name,
+            _ => format!("{}.{}", name, index),
         }
-
-        RollingWriter(self.writer.read())
     }
 }
 
-impl io::Write for RollingWriter<'_> {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        (&*self.0).write(buf)
-    }
-
-    fn flush(&mut self) -> io::Result<()> {
-        (&*self.0).flush()
-    }
+/// `DefaultNamer`
+///
+/// `DailyRollingFileAppender`の既定の規則で、ログファイルに名前を付ける`FileNamer`。
+///
+/// ログファイル名は`{prefix}-<yyyymmdd>.{extension}`、同じ日付の2つ目以降のファイルは
+/// `{prefix}-<yyyymmdd>.<index>.{extension}`となり、圧縮されたファイル(`.gz`、`.zst`)も管理する。
+/// ビルダーに設定した区切り文字、日時の書式及び接尾辞は、`file_namer`を設定しなかった場合に
+/// このファイル名の規則に適用される。
+pub struct DefaultNamer {
+    filename_prefix: String,
+    separator: char,
+    extension: String,
+    date_format: DateFormat,
+    size_suffix: SizeSuffix,
+    tag: Option<String>,
+    log_file_regex: Regex,
 }
 
-impl Inner {
-    fn new(
-        today: OffsetDateTime,
-        max_count: usize,
-        directory: impl AsRef<Path>,
-        filename_prefix: impl AsRef<Path>,
-    ) -> (Self, RwLock<File>) {
-        let next_date = today + Duration::days(1);
-        let next_date = AtomicUsize::new(next_date.unix_timestamp() as usize);
-
-        let directory = directory.as_ref().to_owned();
-        let filename_prefix = filename_prefix.as_ref().to_str().unwrap().to_string();
-
-        let writer = RwLock::new(
-            create_writer(&directory, &filename_prefix, &today).expect("failed to create appender"),
-        );
-
-        let inner = Inner {
-            next_date,
-            max_count,
-            directory,
-            filename_prefix,
-        };
-
-        (inner, writer)
-    }
-
-    /// ファイルをローテーションする必要があるか確認する。
+impl DefaultNamer {
+    /// 既定の設定の`DefaultNamer`を作成する。
     ///
-    /// # 戻り値
+    /// # Arguments
     ///
-    /// ファイルをローテーションする必要がある場合は、現在設定されているファイルをローテーションする
-    /// 日付を示すUnixタイムスタンプ。ローテーションする必要がない場合はNone。
-    fn should_rollover(&self) -> Option<usize> {
-        let next_date = self.next_date.load(Ordering::Acquire);
-        let today = today();
-
-        if next_date <= today.unix_timestamp() as usize {
-            Some(next_date)
-        } else {
-            None
-        }
+    /// * filename_prefix: ファイル名の接頭語。
+    /// * extension: ファイルの拡張子。
+    ///
+    /// # Returns
+    ///
+    /// `DefaultNamer`インスタンス。
+    pub fn new(filename_prefix: &str, extension: &str) -> Self {
+        Self::with_settings(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            extension,
+            DateFormat::from(Rotation::Daily),
+            SizeSuffix::Counter,
+            None,
+        )
     }
 
-    /// 次にファイルをローテーションする日付を示すUnixタイムスタンプを設定する。
-    ///
-    /// 現在持っている次にファイルをローテーションする日付を示すUnixタイムスタンプが、
-    /// 引数`current`と等しい場合、次にファイルをローテーションする時を示す
-    /// Unixタイムスタンプを設定できる。この場合、この関数は`true`を返却する。
-    /// そのUnixタイムスタンプを設定できなかった場合は、falseを返却する。
+    /// ビルダーの設定から`DefaultNamer`を作成する。
     ///
     /// # 引数
     ///
-    /// - today: 次にファイルをローテーションする日付。
-    /// - current: 現在設定されていると考えられるファイルをローテーションする日付を示す
-    ///            Unixタイムスタンプ。
+    /// - filename_prefix: ファイル名の接頭語。
+    /// - separator: 接頭語と日時の間の区切り文字。
+    /// - extension: ファイルの拡張子。
+    /// - date_format: ファイル名に含める日時の書式。
+    /// - size_suffix: サイズによってローテーションしたファイルの接尾辞。
+    /// - tag: 日時の後に含めるタグ。
     ///
     /// # 戻り値
     ///
-    /// 設定できた場合はtrue。設定に失敗した場合はfalse。
-    fn advance_date(&self, today: OffsetDateTime, current: usize) -> bool {
-        let next_date = today.unix_timestamp() as usize;
+    /// `DefaultNamer`インスタンス。
+    fn with_settings(
+        filename_prefix: &str,
+        separator: char,
+        extension: &str,
+        date_format: DateFormat,
+        size_suffix: SizeSuffix,
+        tag: Option<String>,
+    ) -> Self {
+        let log_file_regex = tagged_log_file_regex(
+            filename_prefix,
+            separator,
+            extension,
+            &date_format,
+            &exact_tag_pattern(tag.as_deref(), separator),
+        );
 
-        self.next_date
-            .compare_exchange(current, next_date, Ordering::AcqRel, Ordering::Acquire)
-            .is_ok()
+        Self {
+            filename_prefix: filename_prefix.to_owned(),
+            separator,
+            extension: extension.to_owned(),
+            date_format,
+            size_suffix,
+            tag,
+            log_file_regex,
+        }
     }
+}
 
-    /// ログファイルを更新する。
-    ///
-    /// # 引数
-    ///
-    /// - today: ファイルの日付。
-    /// - file: ファイル。
-    fn refresh_writer(&self, today: &OffsetDateTime, file: &mut File) {
-        if let Err(err) = file.flush() {
-            eprintln!("Couldn't flush previous writer: {}", err);
-        }
-        let result = create_writer(&self.directory, &self.filename_prefix, today);
-        match result {
-            Ok(new_file) => {
-                *file = new_file;
-            }
-            Err(err) => {
-                eprintln!("Couldn't create writer for logs: {}", err);
-            }
-        }
-        // 古いログファイルを削除
-        self.remove_old_files();
+impl Debug for DefaultNamer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DefaultNamer")
+            .field("filename_prefix", &self.filename_prefix)
+            .field("separator", &self.separator)
+            .field("extension", &self.extension)
+            .field("size_suffix", &self.size_suffix)
+            .field("tag", &self.tag)
+            .finish_non_exhaustive()
     }
+}
 
-    /// 古いファイルを削除する。
-    ///
-    /// 正規表現を使用して、ディレクトリに存在するログファイルを取得する。
-    /// 取得したログファイルのファイル名をベクタに格納する。
-    /// その後、ベクタの要素をファイル名の昇順で並べ替える。
-    /// ログファイルの書式から、過去のログファイルの順にログファイル名が並んでいるため、
-    /// ベクタの先頭から保管するログファイルの数になるまで、ログファイルを削除する。
-    fn remove_old_files(&self) {
-        let targets = fs::read_dir(&self.directory);
-        if let Err(err) = targets {
-            eprintln!("Couldn't find log files: {}", err);
-            return;
-        }
+impl FileNamer for DefaultNamer {
+    fn current_name(&self, date: &Date) -> String {
+        self.segment_name(&start_of_day(*date), 0)
+    }
 
-        let mut targets: Vec<String> = targets
-            .unwrap()
-            .filter_map(|entry| match entry {
-                Ok(entry) => {
-                    is_log_file(&entry.file_name().to_string_lossy(), &self.filename_prefix)
-                }
-                Err(_) => None,
-            })
-            .collect();
+    fn is_owned(&self, filename: &str) -> bool {
+        match_log_file(filename, &self.log_file_regex).is_some()
+    }
 
-        if self.max_count < targets.len() - 1 {
-            targets.sort();
-            for target in &targets[..(targets.len() - (self.max_count + 1))] {
-                if let Err(err) = std::fs::remove_file(self.directory.join(target)) {
-                    eprintln!("Couldn't remove log file: {}", err);
-                }
+    fn segment_name(&self, date: &OffsetDateTime, index: usize) -> String {
+        let name = match (self.size_suffix, index) {
+            (SizeSuffix::Time, 1..) => create_time_suffixed_log_filename(
+                &self.filename_prefix,
+                self.separator,
+                &self.extension,
+                &self.date_format,
+                date,
+                &OffsetDateTime::from_unix_timestamp(index as i64)
+                    .expect("Invalid timestamp; this is a bug in restricted-rolling-file-appender"),
+            ),
+            _ => create_log_filename(
+                &self.filename_prefix,
+                self.separator,
+                &self.extension,
+                &self.date_format,
+                date,
+                index,
+            ),
+        };
+        // タグは、接頭語と日時に続けて挿入する
+        match &self.tag {
+            Some(tag) => {
+                let head = format!(
+                    "{}{}",
+                    filename_head(&self.filename_prefix, self.separator),
+                    self.date_format.format(date)
+                );
+                format!("{}{}{}{}", head, self.separator, tag, &name[head.len()..])
             }
+            None => name,
         }
     }
 }
 
-/// ディレクトリエントリがログファイルであるか確認する。
-///
-/// # 引数
-///
-/// - entry: ディレクトリエントリ。
-/// - prefix: ログファイルの接頭語。
+/// `Retention`
 ///
-/// # 戻り値
+/// 古いログファイルを削除する基準。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retention {
+    /// 現在ログを出力しているファイルを除いて、保存するファイルの最大数。
+    ///
+    /// 0の場合は無制限で、ファイルの数による削除はしない。
+    Count(usize),
+    /// 現在ログを出力しているファイルを含めて、保存するファイルの合計サイズの上限(バイト)。
+    ///
+    /// 合計サイズが上限以下になるまで、最も古いファイルから削除する。
+    /// ただし、現在ログを出力しているファイルは削除しない。
+    TotalBytes(u64),
+}
+
+/// `RetentionOrder`
 ///
-/// ログファイルの場合はそのディレクトリエントリ。ログファイルでない場合はNone。
-fn is_log_file(filename: &str, prefix: &str) -> Option<String> {
-    let pattern = format!(r"^{}-\d{{8}}.log$", prefix);
-    let re = Regex::new(&pattern).unwrap();
+/// 古いログファイルを削除するときに、ログファイルを古い順に並べる方法。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RetentionOrder {
+    /// ファイル名に含まれる日時と連番の順に並べる。
+    ///
+    /// 既定の設定のファイル名に含まれる日付は、`parse_log_date`で取り出せる。
+    #[default]
+    ByName,
+    /// ファイルの更新日時の順に並べる。
+    ///
+    /// 更新日時が同じファイル、または更新日時を取得できないファイルは、ファイル名に含まれる日時と
+    /// 連番の順に並べる。更新日時を取得できないファイルは、更新日時を取得できたファイルより古い
+    /// ファイルとして扱う。
+    ByMtime,
+}
 
-    match re.is_match(filename) {
-        true => Some(filename.to_owned()),
-        false => None,
-    }
+/// `RemovalStrategy`
+///
+/// 保存する基準を超えた古いログファイルを、取り除く方法。
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum RemovalStrategy {
+    /// ファイルを削除する。
+    #[default]
+    Delete,
+    /// ファイルを削除せずに、ディレクトリに移動する。
+    ///
+    /// 運用者が確認してから削除できるように、ファイルを移動する。ディレクトリが存在しない場合は、
+    /// 移動するときに作成する。相対パスの場合は、古いファイルを削除するディレクトリからの相対パス
+    /// として扱うため、`MoveTo(".trash".into())`はそのディレクトリの`.trash`サブディレクトリとなる。
+    /// 移動先に同じ名前のファイルが存在する場合は、`foo-20220527.log.1`のように、重複しない連番を
+    /// 付ける。移動したファイルは、保存するファイルの数や合計サイズに含めず、自動的には削除しない。
+    MoveTo(PathBuf),
 }
 
-/// 現在日時を取得して、その日のUnixタイムスタンプを返却する。
+/// `AppenderStats`
 ///
-/// # 戻り値
+/// `DailyRollingFileAppender`を構築してからの、書き込みやローテーションの累計。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AppenderStats {
+    /// ファイルに書き込んだバイト数。ヘッダーは含まない。
+    pub bytes_written: u64,
+    /// ローテーションした回数。新しいファイルを作成できなかったローテーションと、削除された
+    /// ファイルを作成し直した場合など、同じファイルを開き直したローテーションは含まない。
+    pub rollover_count: u64,
+    /// 古いファイルとして削除したファイルの数。
+    pub files_deleted: u64,
+}
+
+/// `Clock`
 ///
-/// 今日の0時のUnixタイムスタンプ。
-fn today() -> OffsetDateTime {
-    let time = Time::from_hms(0, 0, 0)
-        .expect("Invalid time; this is a bug in restricted-rolling-file-appender");
+/// ファイルをローテーションする日付を決めるために、今日の日付を返却する時計。
+/// 単体テストなどで日付を任意に進めたい場合は、このトレイトを実装した時計を
+/// `DailyRollingFileAppenderBuilder::clock`に設定する。
+pub trait Clock: Send + Sync {
+    /// 今日の日付を返却する。
+    fn now_date(&self) -> Date;
+
+    /// 指定されたタイムゾーンにおける今日の日付を返却する。
+    ///
+    /// 既定の実装は、タイムゾーンに関わらず`now_date`が返却する日付を返却する。
+    fn now_date_in(&self, time_zone: TimeZone) -> Date {
+        let _ = time_zone;
+        self.now_date()
+    }
 
-    OffsetDateTime::now_utc().replace_time(time)
+    /// 指定されたタイムゾーンにおける現在日時を返却する。
+    ///
+    /// 返却する日時のオフセットは問わない。アペンダーは、返却された日時を設定された
+    /// タイムゾーンのオフセットに変換してから、日付の境界を判定する。
+    ///
+    /// 既定の実装は、`now_date_in`が返却する日付の0時0分0秒(タイムゾーンにおける時刻)を
+    /// 返却する。1日より短い間隔でファイルをローテーションする場合は、この関数を実装する
+    /// 必要がある。
+    fn now_in(&self, time_zone: TimeZone) -> OffsetDateTime {
+        let midnight = self.now_date_in(time_zone).midnight();
+        midnight.assume_offset(time_zone.offset_at(midnight.assume_utc()))
+    }
 }
 
-/// 日毎にローテーションするログファイルの名前を作成して、返却する。
-///
-/// ログファイル名は、`{filename_prefix}-<yyyymmdd>.log`となる。
+/// `SystemClock`
 ///
-/// # 引数
+/// システムの現在日時から今日の日付を返却する時計。
+/// `now_date`はUTCにおける今日の日付を返却する。
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+/// `Rotation`
 ///
-/// - filename_prefix: ファイル名の接頭語。
-/// - date: ファイルの日付。
+/// ファイルをローテーションする間隔。
 ///
-/// # 戻り値
+/// ログファイル名に含まれる日時の書式は、ローテーションする間隔によって次の通りとなる。
 ///
-/// ログファイル名。
-fn create_daily_log_filename(filename_prefix: &str, date: &OffsetDateTime) -> String {
-    let month: u8 = date.month().into();
-
-    format!(
-        "{}-{:04}{:02}{:02}.log",
-        filename_prefix,
-        date.year(),
-        month,
-        date.day()
-    )
+/// * `Daily`: `yyyymmdd`
+/// * `Hourly`: `yyyymmddHH`
+/// * `Minutely`: `yyyymmddHHMM`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Rotation {
+    /// 1日ごと。
+    #[default]
+    Daily,
+    /// 1時間ごと。
+    Hourly,
+    /// 1分ごと。
+    Minutely,
 }
 
-/// ログファイルのパスを生成して、返却する。
+/// `SizeSuffix`
 ///
-/// # 引数
+/// ファイルのサイズによってローテーションしたときに、同じ日時のファイルを区別するために
+/// ファイル名に付ける接尾辞。いずれの接尾辞も、古いファイルから順に並べ替えられる。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SizeSuffix {
+    /// 連番(`foo-20220527.1.log`、`foo-20220527.2.log`)。
+    #[default]
+    Counter,
+    /// ローテーションした時刻(`foo-20220527T153000.log`)。
+    ///
+    /// 時刻は、設定されたタイムゾーンにおける時分秒となる。1秒以内に複数回ローテーションした
+    /// 場合は、同じファイルに追記する。`DailyRollingFileAppenderBuilder::unique_segments`を
+    /// 設定した場合は、直前のファイルの時刻に1秒を加えた時刻を接尾辞とする。
+    Time,
+}
+
+/// `OpenMode`
 ///
-/// - directory: ログファイルディレクトリ。
-/// - filename: ログファイル名。
+/// ログを記録するファイルを開くときに、既に存在するファイルを扱う方法。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OpenMode {
+    /// 既に存在するファイルに追記する。
+    #[default]
+    Append,
+    /// 既に存在するファイルを空にしてから書き込む。
+    ///
+    /// プロセスを再起動したとき、同じ日時のファイルを空にする。ただし、ローテーションで
+    /// 現在ログを記録しているファイルと同じファイルを開く場合は、空にせずに追記する。
+    Truncate,
+}
+
+/// `Compression`
 ///
-/// # 戻り値
+/// ローテーションしたファイルを圧縮する方式。
 ///
-/// ログファイルパスを返却する。
-fn create_daily_log_path(directory: &Path, filename: &str) -> String {
-    directory.join(filename).to_str().unwrap().to_string()
+/// gzipは`gzip`フィーチャー(既定で有効)、Zstandardは`zstd`フィーチャーを有効にした場合に
+/// 選択できる。ログファイルの判定と古いファイルの削除は、フィーチャーに関わらず、いずれの方式で
+/// 圧縮したファイル(`.gz`、`.zst`)も対象とする。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// 圧縮しない。
+    #[default]
+    None,
+    /// gzip(`.gz`)。
+    #[cfg(feature = "gzip")]
+    Gzip {
+        /// 圧縮レベル(0から9)。9を超える値は9として扱う。
+        level: u32,
+    },
+    /// Zstandard(`.zst`)。
+    #[cfg(feature = "zstd")]
+    Zstd {
+        /// 圧縮レベル(1から22)。0は既定の圧縮レベルを表す。
+        level: i32,
+    },
 }
 
-/// ライターを作成する。
-///
-/// # 引数
+/// `TimeZone`
 ///
-/// - path: ログファイルディレクトリのパス。
-/// - filename_prefix: ログファイルの接頭語。
-/// - date: ログファイルの日付。
+/// 日付の境界を決めるタイムゾーン。
 ///
-/// # 戻り値
+/// `Local`を選択した場合、ローカルのオフセットを取得できないとき(例えば、マルチスレッドで
+/// 動作しているUnix系のプラットフォーム)は、UTCにおける日付を使用する。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum TimeZone {
+    /// UTC。
+    #[default]
+    Utc,
+    /// ローカルのタイムゾーン。
+    Local,
+    /// UTCからの固定されたオフセット。
+    Fixed(UtcOffset),
+}
+
+/// `DailyRollingFileAppenderBuilder`
 ///
-/// `File`インスタンス。
-fn create_writer(
-    directory: &Path,
-    filename_prefix: &str,
-    date: &OffsetDateTime,
-) -> io::Result<File> {
-    let filename = create_daily_log_filename(filename_prefix, date);
-    let path = create_daily_log_path(directory, &filename);
-    let path = Path::new(&path);
-    let mut open_options = OpenOptions::new();
-    open_options.append(true).create(true);
+/// `DailyRollingFileAppender`を構築するビルダー。
+/// ファイルを作成するディレクトリとファイル名の接頭語は、必ず設定しなければならない。
+pub struct DailyRollingFileAppenderBuilder {
+    retention: Retention,
+    retention_order: RetentionOrder,
+    removal_strategy: RemovalStrategy,
+    dry_run: bool,
+    max_age_days: Option<u32>,
+    directory: Option<PathBuf>,
+    directory_lock: bool,
+    include_pid: bool,
+    host_tag: Option<String>,
+    archive_directory: Option<PathBuf>,
+    filename_prefix: Option<String>,
+    separator: char,
+    max_bytes: Option<u64>,
+    min_free_bytes: Option<u64>,
+    max_deletions_per_pass: Option<usize>,
+    keep_minimum: usize,
+    protected: Vec<String>,
+    size_suffix: SizeSuffix,
+    unique_segments: bool,
+    roll_on_start: bool,
+    open_mode: OpenMode,
+    buffer_capacity: usize,
+    flush_on_write: bool,
+    fsync_on_flush: bool,
+    atomic_records: bool,
+    whole_records: bool,
+    stderr_fallback: bool,
+    open_attempts: usize,
+    flush_interval: Option<StdDuration>,
+    scheduled_rollover: bool,
+    extension: String,
+    date_format: Option<String>,
+    filename_predicate: Option<FilenamePredicate>,
+    file_namer: Option<Box<dyn FileNamer>>,
+    header: Option<HeaderFn>,
+    footer: Option<Vec<u8>>,
+    record_terminator: Option<Vec<u8>>,
+    timestamp_prefix: bool,
+    compression: Compression,
+    current_link: bool,
+    part_files: bool,
+    manifest: bool,
+    rotation: Rotation,
+    time_zone: TimeZone,
+    clock: Box<dyn Clock>,
+    file_system: Box<dyn FileSystem>,
+    on_rollover: Option<RolloverHook>,
+    on_planned_removal: Option<PlannedRemovalHook>,
+    on_error: Option<ErrorHook>,
+    #[cfg(feature = "process_lock")]
+    process_lock: bool,
+    #[cfg(feature = "encoding")]
+    encoding: Option<&'static encoding_rs::Encoding>,
+}
 
-    let new_file = open_options.open(path);
-    if new_file.is_err() {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-            return open_options.open(path);
-        }
-    }
+/// 保存するファイルの最大数の既定値。
+const DEFAULT_MAX_COUNT: usize = 7;
 
-    new_file
-}
+/// ログファイルの拡張子の既定値。
+const DEFAULT_EXTENSION: &str = "log";
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs::DirEntry;
+/// ファイル名の接頭語と日時の間の区切り文字の既定値。
+const DEFAULT_SEPARATOR: char = '-';
 
-    #[test]
-    fn test_is_log_file() {
-        let prefix = "foo";
+/// 一時的なエラーでファイルを開けなかった場合に、開くことを試みる既定の回数。
+const DEFAULT_OPEN_ATTEMPTS: usize = 3;
 
-        let log_filenames = vec!["foo-00000000.log", "foo-20220527.log"];
-        for filename in log_filenames {
-            assert!(
-                is_log_file(filename, prefix).is_some(),
-                "filename={}",
-                filename
-            );
-        }
+/// ファイルを開くことを再試行するまでの最初の待ち時間。再試行するたびに2倍にする。
+const OPEN_RETRY_BACKOFF: StdDuration = StdDuration::from_millis(10);
 
-        let not_log_filenames = vec![
-            "foo.log",
-            "20220527.log",
-            "foo-2022052a.log",
-            "foo-20220527.txt",
-        ];
-        for filename in not_log_filenames {
-            assert!(
-                is_log_file(filename, prefix).is_none(),
-                "filename={}",
-                filename
-            );
-        }
-    }
+/// ログを記録しているファイルが存在するか確認する間隔(秒)。
+const EXISTENCE_CHECK_INTERVAL: usize = 1;
 
-    #[test]
-    fn test_create_daily_log_filename() {
-        let filename_prefix = "foo";
-        let date = today();
-        let month: u8 = date.month().into();
-        let expected = format!(
-            "{}-{:04}{:02}{:02}.log",
-            filename_prefix,
+/// 多くのファイルシステムが許容する、ファイル名の最大の長さ(バイト)。
+const MAX_FILENAME_LEN: usize = 255;
+
+/// 連番または時刻の接尾辞と、圧縮したファイルの拡張子のために残しておくファイル名の長さ(バイト)。
+const FILENAME_SUFFIX_RESERVE: usize = 32;
+
+/// 書き込んでいる途中のファイルの名前に付ける拡張子。
+const PART_EXTENSION: &str = "part";
+
+/// 閉じたファイルを記録するマニフェストの、接頭語と区切り文字に続くファイル名。
+const MANIFEST_NAME: &str = "manifest.jsonl";
+
+/// 現在のファイルに、まだレコードを書き込んでいないことを表す時刻。
+const NO_RECORD: i64 = i64::MIN;
+
+/// ファイルの最大サイズが設定されていないことを表すサイズ。
+const NO_MAX_BYTES: u64 = u64::MAX;
+
+impl DailyRollingFileAppender {
+    /// `DailyRollingFileAppender`を作成する。
+    ///
+    /// # Arguments
+    ///
+    /// * directory: ファイルを作成するディレクトリ。
+    /// * file_name_prefix: ファイル名の接頭語。
+    /// * max_count: 現在ログを出力しているファイルを除いて、保存するファイルの最大数。
+    ///   0の場合は無制限。
+    ///
+    /// # Returns
+    ///
+    /// `DailyRollingFileAppender`インスタンス。
+    ///
+    /// # Panics
+    ///
+    /// ファイル名の接頭語がUTF-8でない場合、またはファイルを作成できなかった場合。
+    pub fn new(
+        max_count: usize,
+        directory: impl AsRef<Path>,
+        filename_prefix: impl AsRef<Path>,
+    ) -> Self {
+        Self::try_new(max_count, directory, filename_prefix).expect("failed to create appender")
+    }
+
+    /// `DailyRollingFileAppender`を作成する。
+    ///
+    /// # Arguments
+    ///
+    /// * directory: ファイルを作成するディレクトリ。
+    /// * file_name_prefix: ファイル名の接頭語。
+    /// * max_count: 現在ログを出力しているファイルを除いて、保存するファイルの最大数。
+    ///   0の場合は無制限。
+    ///
+    /// # Returns
+    ///
+    /// `DailyRollingFileAppender`インスタンス。ファイル名の接頭語がUTF-8でない場合、パスの
+    /// 区切り文字や制御文字を含む場合、またはファイル名が長すぎる場合は、`InvalidInput`エラーを
+    /// 保持する`AppenderError::Other`。ファイルを作成できなかった場合は
+    /// `AppenderError::CreateWriter`。
+    pub fn try_new(
+        max_count: usize,
+        directory: impl AsRef<Path>,
+        filename_prefix: impl AsRef<Path>,
+    ) -> Result<Self, AppenderError> {
+        let filename_prefix =
+            filename_prefix
+                .as_ref()
+                .to_str()
+                .ok_or_else(|| AppenderError::Other {
+                    message: "Couldn't build appender",
+                    source: io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "filename prefix of the appender is not valid UTF-8",
+                    ),
+                })?;
+
+        Self::builder()
+            .max_count(max_count)
+            .directory(directory)
+            .filename_prefix(filename_prefix)
+            .build()
+            .map_err(|err| AppenderError::from_io("Couldn't build appender", err))
+    }
+
+    /// `DailyRollingFileAppender`を構築するビルダーを作成する。
+    ///
+    /// # Returns
+    ///
+    /// `DailyRollingFileAppenderBuilder`インスタンス。
+    pub fn builder() -> DailyRollingFileAppenderBuilder {
+        DailyRollingFileAppenderBuilder::default()
+    }
+
+    /// ファイルの最大サイズを設定する。
+    ///
+    /// ログを記録しているファイルのサイズが最大サイズに達したとき、同じ日付で連番を付けた
+    /// ファイル(例: `foo-20220527.1.log`)に切り替える。
+    ///
+    /// # Arguments
+    ///
+    /// * max_bytes: ファイルの最大サイズ(バイト)。
+    ///
+    /// # Returns
+    ///
+    /// `DailyRollingFileAppender`インスタンス。
+    pub fn with_max_bytes(self, max_bytes: u64) -> Self {
+        // バックグラウンドのスレッドが状態を共有していても変更できるように、原子的に更新する
+        self.state.max_bytes.store(max_bytes, Ordering::Release);
+
+        self
+    }
+
+    /// 現在ログを記録しているファイルのパスを返却する。
+    ///
+    /// 他のスレッドがローテーションした直後であっても、そのローテーションで切り替えたファイルの
+    /// パスを返却する。
+    ///
+    /// # Returns
+    ///
+    /// 現在ログを記録しているファイルのパス。
+    pub fn current_path(&self) -> PathBuf {
+        // ファイルの日付と連番は書き込みロックを獲得して更新されるため、読み込みロックを獲得して
+        // いる間は、日付と連番の組み合わせが変わらない
+        let _writer = self.writer.read();
+
+        self.state.current_path()
+    }
+
+    /// 次に日時によってファイルをローテーションする日時を返却する。
+    ///
+    /// 現在ログを記録しているファイルの日時に、ローテーションする間隔を加えた日時であり、
+    /// 日ごとにローテーションする場合は翌日の0時、時間や分ごとにローテーションする場合は次の
+    /// 時間や分の始まりとなる。既にその日時を過ぎていても、まだログを書き込んでいない場合は、
+    /// 過ぎた日時を返却する。
+    ///
+    /// # Returns
+    ///
+    /// 次にファイルをローテーションする、設定したタイムゾーンにおける日時。
+    pub fn next_rollover(&self) -> OffsetDateTime {
+        self.state.next_rollover()
+    }
+
+    /// 現在ログを記録しているファイルの末尾から、最大で指定された行数の行を読み込む。
+    ///
+    /// 読み込みを開始する時点のファイルの長さまでを読み込むため、読み込んでいる間に書き込まれた
+    /// 行は含まれない。また、書き込みの途中で改行で終わっていない最後の行と、バッファに残っていて
+    /// ファイルに書き込まれていない行も含まれない。ファイルを読み込んでいる間はロックを獲得しない
+    /// ため、他のスレッドの書き込みを妨げない。
+    ///
+    /// ファイルシステムの抽象化はシークに対応していないため、ファイルは先頭から読み込むが、保持する
+    /// 行は最大で指定された行数に限られるため、大きなファイルでもファイル全体をメモリに読み込まない。
+    /// ローテーションする前のファイルは読み込まない。
+    ///
+    /// # Arguments
+    ///
+    /// * max_lines: 読み込む最大の行数。
+    ///
+    /// # Returns
+    ///
+    /// 古い順に並べた行。行末の改行は含まない。ファイルを読み込めなかった場合は、そのエラー。
+    pub fn read_recent(&self, max_lines: usize) -> io::Result<Vec<String>> {
+        if max_lines == 0 {
+            return Ok(Vec::new());
+        }
+        // ローテーションしている途中のファイルを読み込まないように、読み込みロックを獲得して
+        // ファイルのパスと長さを取得する
+        let (path, len) = {
+            let _writer = self.writer.read();
+            let path = self.state.current_path();
+            let len = self.state.file_system.file_len(&path)?;
+            (path, len)
+        };
+        let mut reader = BufReader::new(self.state.file_system.open(&path)?.take(len));
+        let mut lines = VecDeque::with_capacity(max_lines.min(1024));
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 || !line.ends_with(b"\n") {
+                break;
+            }
+            line.pop();
+            if line.ends_with(b"\r") {
+                line.pop();
+            }
+            if lines.len() == max_lines {
+                lines.pop_front();
+            }
+            lines.push_back(String::from_utf8_lossy(&line).into_owned());
+        }
+
+        Ok(lines.into())
+    }
+
+    /// 保存する基準に従って、古いファイルを直ちに削除する。
+    ///
+    /// 古いファイルは、ローテーションしたときと`DailyRollingFileAppender`を構築したときに
+    /// 削除されるが、このメソッドを呼び出すことで、任意の時点で削除できる。
+    /// 現在ログを記録しているファイルは削除しない。
+    ///
+    /// # Returns
+    ///
+    /// 古いファイルを検索できなかった場合は`AppenderError::ReadDir`、削除できなかったファイルが
+    /// ある場合は、最初に削除できなかったファイルの`AppenderError::RemoveFile`。他のエラーは、
+    /// `DailyRollingFileAppenderBuilder::on_error`に設定した関数に渡される。削除する代わりに報告する
+    /// 設定の場合、削除するファイルは`DailyRollingFileAppenderBuilder::on_planned_removal`に設定した
+    /// 関数に渡される。
+    pub fn enforce_retention_now(&self) -> Result<(), AppenderError> {
+        // 削除している間に、他のスレッドがローテーションしないように読み込みロックを獲得する
+        let _writer = self.writer.read();
+        let _lock = self.state.lock_processes();
+
+        let mut first_error = None;
+        self.state.prune_old_files(|err| match first_error {
+            None => first_error = Some(err),
+            Some(_) => self.state.report_error(err),
+        });
+
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// 保存するファイルの最大数を変更して、新しい最大数に従って古いファイルを直ちに削除する。
+    ///
+    /// 設定を再読み込みしたときなどに、再起動せずに保存する基準を変更するために使用する。
+    /// 保存する基準は`Retention::Count`に置き換えられ、以降のローテーションでも新しい最大数に
+    /// 従って古いファイルを削除する。
+    ///
+    /// # Arguments
+    ///
+    /// * max_count: 保存するファイルの最大数。0を指定した場合は、ファイルの数で削除しない。
+    pub fn set_max_count(&self, max_count: usize) {
+        // 変更している間に、他のスレッドがローテーションしないように書き込みロックを獲得する
+        let _writer = self.writer.write();
+        *self
+            .state
+            .retention
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Retention::Count(max_count);
+        let _lock = self.state.lock_processes();
+
+        self.state.remove_old_files();
+    }
+
+    /// 現在ログを記録しているファイルを閉じて、同じパスのファイルを開き直す。
+    ///
+    /// logrotateなどの外部のツールがファイルの名前を変更した後に呼び出すことで、以降のログを
+    /// 同じパスに作成し直したファイルに記録する。SIGHUPを受け取ったときに呼び出す場合は、
+    /// シグナルハンドラの中ではなく、シグナルを待つスレッドから呼び出す。
+    /// ファイルの日付と連番は変更せず、古いファイルも削除しない。
+    ///
+    /// # Returns
+    ///
+    /// バッファに残っている内容を書き込めなかった場合は`AppenderError::Flush`、ファイルを
+    /// 開けなかった場合は`AppenderError::CreateWriter`。その場合は、開いていたファイルにログを
+    /// 記録し続ける。
+    pub fn reopen(&self) -> Result<(), AppenderError> {
+        let mut writer = self.writer.write();
+        let writer = writer.get_mut().unwrap_or_else(PoisonError::into_inner);
+        let path = self.state.current_path();
+        if let Err(err) = writer.flush() {
+            return Err(AppenderError::Flush { path, source: err });
+        }
+
+        let date = self.state.current_date();
+        let (file, len) = create_writer(
+            self.state.file_system.as_ref(),
+            &path,
+            OpenMode::Append,
+            self.state.header.as_ref(),
+            &date,
+            self.state.open_attempts,
+        )
+        .map_err(|err| AppenderError::CreateWriter {
+            path: path.clone(),
+            source: err,
+        })?;
+        *writer = BufWriter::with_capacity(self.state.buffer_capacity, file);
+        // 名前を変更されずに同じファイルを開き直した場合に備えて、ファイルのサイズを引き継ぐ
+        self.state.bytes_written.store(len, Ordering::Release);
+        self.state.fallback.store(false, Ordering::Release);
+
+        Ok(())
+    }
+
+    /// 日時やファイルのサイズに関わらず、直ちにファイルをローテーションする。
+    ///
+    /// サイズでローテーションしたときと同様に、同じ日時でファイル名の接尾辞が異なる新しい
+    /// ファイルを作成する。バッファに残っている内容を前のファイルに書き込んで閉じた後、
+    /// 保存する基準に従って古いファイルを削除する。同じパスのファイルを開き直す`reopen`と
+    /// 異なり、以降のログは新しいファイル名のファイルに記録する。
+    ///
+    /// 接尾辞に`SizeSuffix::Time`を設定している場合、1秒以内に続けて呼び出すと同じファイルに
+    /// 追記する。
+    ///
+    /// # Returns
+    ///
+    /// バッファに残っている内容を書き込めなかった場合は、そのエラー。新しいファイルを作成
+    /// できなかった場合はエラー。その場合は、開いていたファイルにログを記録し続け、次に
+    /// 書き込むときにローテーションを再試行する。
+    pub fn force_rollover(&self) -> io::Result<()> {
+        let mut writer = self.writer.write();
+        let writer = writer.get_mut().unwrap_or_else(PoisonError::into_inner);
+        writer.flush()?;
+
+        self.state.roll_by_size(writer);
+        if self.state.rollover_pending.load(Ordering::Acquire) {
+            return Err(io::Error::other("Couldn't create writer for logs"));
+        }
+
+        Ok(())
+    }
+
+    /// このアペンダーにログを記録する`tracing_subscriber`のレイヤーを作成する。
+    ///
+    /// ファイルに記録するため、ANSIエスケープシーケンスによる装飾は無効にする。
+    /// `tracing_subscriber::registry().with(appender.layer()).init()`のように使用する。
+    ///
+    /// # Returns
+    ///
+    /// このアペンダーを書き込み先とする`tracing_subscriber::fmt::Layer`インスタンス。
+    pub fn layer<S>(self) -> tracing_subscriber::fmt::Layer<S, DefaultFields, Format, Self>
+    where
+        S: tracing_core::Subscriber + for<'a> LookupSpan<'a>,
+    {
+        tracing_subscriber::fmt::layer()
+            .with_ansi(false)
+            .with_writer(self)
+    }
+
+    /// アペンダーが管理するログファイルのパスを、新しい順に返却する。
+    ///
+    /// 古いファイルを削除するときと同じ規則でログファイルを判定して並べ替えるため、現在ログを
+    /// 記録しているファイルが常に先頭となる。ログファイルでないファイルは含まない。
+    /// ファイルを移動するディレクトリが設定されている場合、または`.part`ファイルに書き込む設定の
+    /// 場合は、現在ログを記録しているファイルに続けて、ローテーションしたログファイルを返却する。
+    ///
+    /// # Returns
+    ///
+    /// 新しい順に並べたログファイルのパス。ディレクトリを読み込めなかった場合は、そのエラー。
+    pub fn list_files(&self) -> io::Result<Vec<PathBuf>> {
+        // 検索している間に、他のスレッドがローテーションしないように読み込みロックを獲得する
+        let _writer = self.writer.read();
+
+        let (targets, _) = self.state.pruner().log_files()?;
+        let directory = self.state.retained_directory();
+        let mut files: Vec<PathBuf> = targets
+            .iter()
+            .rev()
+            .map(|name| directory.join(name))
+            .collect();
+        let current = self.state.current_path();
+        if !files.contains(&current) {
+            files.insert(0, current);
+        }
+
+        Ok(files)
+    }
+
+    /// アペンダーが管理するログファイルの数を返却する。
+    ///
+    /// `list_files`と同じ規則でログファイルを数えるため、現在ログを記録しているファイルを含み、
+    /// ログファイルでないファイルは含まない。
+    ///
+    /// # Returns
+    ///
+    /// ログファイルの数。ディレクトリを読み込めなかった場合は、そのエラー。
+    pub fn managed_file_count(&self) -> io::Result<usize> {
+        Ok(self.list_files()?.len())
+    }
+
+    /// アペンダーが管理するログファイルのサイズの合計を返却する。
+    ///
+    /// `list_files`と同じ規則でログファイルを判定する。検索した後に削除されたファイルは、
+    /// 合計に含めない。
+    ///
+    /// # Returns
+    ///
+    /// ログファイルのサイズ(バイト)の合計。ディレクトリを読み込めなかった場合、またはファイルの
+    /// サイズを取得できなかった場合は、そのエラー。
+    pub fn managed_total_bytes(&self) -> io::Result<u64> {
+        let mut total = 0;
+        for path in self.list_files()? {
+            match self.state.file_system.file_len(&path) {
+                Ok(len) => total += len,
+                // 検索した後に、ローテーションや他のプロセスが削除したファイルは無視する
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(total)
+    }
+
+    /// 保存する基準に従って、次に古いファイルを削除するときに削除するファイルのパスを返却する。
+    ///
+    /// ファイルは削除しない。確保する空き容量が設定されている場合、空き容量を回復するために削除する
+    /// ファイルは、ファイルのサイズから見積もる。
+    ///
+    /// # Returns
+    ///
+    /// 削除するファイルのパス。ディレクトリを読み込めなかった場合、または空き容量を取得できなかった
+    /// 場合は、そのエラー。
+    pub fn plan_removals(&self) -> io::Result<Vec<PathBuf>> {
+        // 検索している間に、他のスレッドがローテーションしないように読み込みロックを獲得する
+        let _writer = self.writer.read();
+
+        self.state.pruner().plan()
+    }
+
+    /// 書き込みやローテーションの累計を返却する。
+    ///
+    /// 累計は書き込みを妨げないように個別に更新されるため、他のスレッドが書き込んでいる間は、
+    /// 各値が同じ時点の値であるとは限らない。
+    ///
+    /// # Returns
+    ///
+    /// `AppenderStats`インスタンス。
+    pub fn stats(&self) -> AppenderStats {
+        AppenderStats {
+            bytes_written: self.state.total_bytes_written.load(Ordering::Relaxed),
+            rollover_count: self.state.rollover_count.load(Ordering::Relaxed),
+            files_deleted: self.state.files_deleted.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 前回呼び出してから、ローテーションしたかを返却して、その記録を消去する。
+    ///
+    /// `io::Write::write`は書き込んだバイト数しか返却できないため、書き込んだ直後にこのメソッドを
+    /// 呼び出すことで、その書き込みでローテーションしたかを確認できる。ただし、記録は
+    /// `DailyRollingFileAppender`ごとに1つで、どのスレッドの書き込みによるローテーションかを
+    /// 区別しないため、複数のスレッドが書き込んでいる場合は、他のスレッドの書き込みによる
+    /// ローテーションでも`true`を返却し、複数のスレッドが呼び出した場合は、最初に呼び出した
+    /// スレッドだけが`true`を受け取る。ローテーションしたファイルのパスが必要な場合は、
+    /// `DailyRollingFileAppenderBuilder::on_rollover`を使用する。
+    ///
+    /// # Returns
+    ///
+    /// 前回呼び出してから、または構築してから、ローテーションした場合は`true`。
+    pub fn took_rollover(&self) -> bool {
+        self.state.rolled_over.swap(false, Ordering::AcqRel)
+    }
+
+    /// 単体テスト用に、`DailyRollingFileAppender`を作成する。
+    ///
+    /// # Arguments
+    ///
+    /// * directory: ファイルを作成するディレクトリ。
+    /// * file_name_prefix: ファイル名の接頭語。
+    /// * max_count: 現在ログを出力しているファイルを除いて、保存するファイルの最大数。
+    ///   0の場合は無制限。
+    /// * date: 日付(0時0分0秒に設定された`OffsetDateTime`)。
+    ///
+    /// # Returns
+    ///
+    /// `DailyRollingFileAppender`インスタンス。
+    #[cfg(test)]
+    fn new_test(
+        max_count: usize,
+        directory: impl AsRef<Path>,
+        filename_prefix: impl AsRef<Path>,
+        date: OffsetDateTime,
+    ) -> Self {
+        Self::builder()
+            .max_count(max_count)
+            .directory(directory)
+            .filename_prefix(filename_prefix.as_ref().to_str().unwrap())
+            .build_at(date)
+            .expect("failed to create appender")
+    }
+
+    #[cfg(test)]
+    fn inner(&self) -> &Inner {
+        &self.state
+    }
+}
+
+impl Default for DailyRollingFileAppenderBuilder {
+    fn default() -> Self {
+        Self {
+            retention: Retention::Count(DEFAULT_MAX_COUNT),
+            retention_order: RetentionOrder::default(),
+            removal_strategy: RemovalStrategy::default(),
+            dry_run: false,
+            directory_lock: true,
+            include_pid: false,
+            host_tag: None,
+            max_age_days: None,
+            directory: None,
+            archive_directory: None,
+            filename_prefix: None,
+            separator: DEFAULT_SEPARATOR,
+            max_bytes: None,
+            min_free_bytes: None,
+            max_deletions_per_pass: None,
+            keep_minimum: 1,
+            protected: Vec::new(),
+            size_suffix: SizeSuffix::default(),
+            unique_segments: false,
+            roll_on_start: false,
+            open_mode: OpenMode::default(),
+            buffer_capacity: 0,
+            flush_on_write: false,
+            fsync_on_flush: false,
+            atomic_records: false,
+            whole_records: false,
+            stderr_fallback: false,
+            open_attempts: DEFAULT_OPEN_ATTEMPTS,
+            flush_interval: None,
+            scheduled_rollover: false,
+            extension: DEFAULT_EXTENSION.to_owned(),
+            date_format: None,
+            filename_predicate: None,
+            file_namer: None,
+            header: None,
+            footer: None,
+            record_terminator: None,
+            timestamp_prefix: false,
+            compression: Compression::None,
+            current_link: false,
+            part_files: false,
+            manifest: false,
+            rotation: Rotation::default(),
+            time_zone: TimeZone::default(),
+            clock: Box::new(SystemClock),
+            file_system: Box::new(OsFileSystem::new()),
+            on_rollover: None,
+            on_planned_removal: None,
+            on_error: None,
+            #[cfg(feature = "process_lock")]
+            process_lock: false,
+            #[cfg(feature = "encoding")]
+            encoding: None,
+        }
+    }
+}
+
+impl Debug for DailyRollingFileAppender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DailyRollingFileAppender")
+            .field("directory", &self.state.directory)
+            .field("filename_prefix", &self.state.filename_prefix)
+            .field("retention", &self.state.retention())
+            .field("current_date", &self.state.current_date())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Debug for DailyRollingFileAppenderBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DailyRollingFileAppenderBuilder")
+            .field("retention", &self.retention)
+            .field("retention_order", &self.retention_order)
+            .field("removal_strategy", &self.removal_strategy)
+            .field("dry_run", &self.dry_run)
+            .field("max_age_days", &self.max_age_days)
+            .field("directory", &self.directory)
+            .field("archive_directory", &self.archive_directory)
+            .field("filename_prefix", &self.filename_prefix)
+            .field("separator", &self.separator)
+            .field("max_bytes", &self.max_bytes)
+            .field("min_free_bytes", &self.min_free_bytes)
+            .field("max_deletions_per_pass", &self.max_deletions_per_pass)
+            .field("keep_minimum", &self.keep_minimum)
+            .field("protected", &self.protected)
+            .field("size_suffix", &self.size_suffix)
+            .field("unique_segments", &self.unique_segments)
+            .field("roll_on_start", &self.roll_on_start)
+            .field("open_mode", &self.open_mode)
+            .field("buffer_capacity", &self.buffer_capacity)
+            .field("flush_on_write", &self.flush_on_write)
+            .field("fsync_on_flush", &self.fsync_on_flush)
+            .field("atomic_records", &self.atomic_records)
+            .field("whole_records", &self.whole_records)
+            .field("timestamp_prefix", &self.timestamp_prefix)
+            .field("stderr_fallback", &self.stderr_fallback)
+            .field("open_attempts", &self.open_attempts)
+            .field("flush_interval", &self.flush_interval)
+            .field("scheduled_rollover", &self.scheduled_rollover)
+            .field("extension", &self.extension)
+            .field("date_format", &self.date_format)
+            .field("compression", &self.compression)
+            .field("current_link", &self.current_link)
+            .field("part_files", &self.part_files)
+            .field("manifest", &self.manifest)
+            .field("rotation", &self.rotation)
+            .field("time_zone", &self.time_zone)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DailyRollingFileAppenderBuilder {
+    /// 現在ログを出力しているファイルを除いて、保存するファイルの最大数を設定する。
+    ///
+    /// 設定しなかった場合は7となる。0を設定した場合は無制限となり、ファイルの数による削除はしない。
+    pub fn max_count(mut self, max_count: usize) -> Self {
+        self.retention = Retention::Count(max_count);
+
+        self
+    }
+
+    /// 現在ログを出力しているファイルを含めて、保存するファイルの合計サイズの上限(バイト)を設定する。
+    ///
+    /// 設定した場合、保存するファイルの最大数による削除はしない。
+    pub fn max_total_bytes(mut self, max_total_bytes: u64) -> Self {
+        self.retention = Retention::TotalBytes(max_total_bytes);
+
+        self
+    }
+
+    /// 古いログファイルを削除するときに、ログファイルを古い順に並べる方法を設定する。
+    ///
+    /// 独自の日時の書式を設定した場合や、外部のツールがファイルの名前を変更する場合など、
+    /// ファイル名の順序が記録した順序と一致しない場合は、`RetentionOrder::ByMtime`を設定する。
+    /// 設定しなかった場合は`RetentionOrder::ByName`となる。
+    pub fn retention_order(mut self, retention_order: RetentionOrder) -> Self {
+        self.retention_order = retention_order;
+
+        self
+    }
+
+    /// 保存する基準を超えた古いログファイルを、取り除く方法を設定する。
+    ///
+    /// `RemovalStrategy::MoveTo`を設定した場合は、古いファイルを削除せずにディレクトリへ移動する。
+    /// 設定しなかった場合は`RemovalStrategy::Delete`となり、古いファイルを削除する。
+    pub fn removal_strategy(mut self, removal_strategy: RemovalStrategy) -> Self {
+        self.removal_strategy = removal_strategy;
+
+        self
+    }
+
+    /// 古いファイルを削除する代わりに、削除するファイルを報告するかを設定する。
+    ///
+    /// 有効にした場合、古いファイルを削除するときに、削除するファイルのパスを`on_planned_removal`に
+    /// 設定した関数に渡して、ファイルを削除しない。
+    /// 保存する基準を実際のディレクトリで確認してから、削除を有効にするために使用する。
+    /// 設定しなかった場合は`false`となり、古いファイルを削除する。
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+
+        self
+    }
+
+    /// 同じプロセスの他のアペンダーと、ディレクトリ、ファイル名の接頭語及び拡張子の組み合わせが
+    /// 重複していないか確認するかを設定する。
+    ///
+    /// 有効にした場合、同じディレクトリ、ファイル名の接頭語及び拡張子でログを記録しているアペンダーが
+    /// 既に存在するときは、構築に失敗する。意図的に複数のアペンダーで同じファイルを共有する場合は、
+    /// 無効にする。設定しなかった場合は`true`となる。
+    pub fn directory_lock(mut self, directory_lock: bool) -> Self {
+        self.directory_lock = directory_lock;
+
+        self
+    }
+
+    /// ログファイルを保存する日数を設定する。
+    ///
+    /// ファイル名に含まれる日付が、今日から保存する日数より前のファイルを削除する。
+    /// この設定は古いログファイルを削除する基準と併用され、どちらかに該当したファイルを削除する。
+    /// ファイル名に含まれる日付を解析できないファイルは削除しない。
+    /// 設定しなかった場合は、日数によるファイルの削除をしない。
+    pub fn max_age_days(mut self, max_age_days: u32) -> Self {
+        self.max_age_days = Some(max_age_days);
+
+        self
+    }
+
+    /// 古いログファイルを削除する基準を設定する。
+    ///
+    /// 設定しなかった場合は`Retention::Count(7)`となる。
+    pub fn retention(mut self, retention: Retention) -> Self {
+        self.retention = retention;
+
+        self
+    }
+
+    /// ファイルを作成するディレクトリを設定する。
+    ///
+    /// 相対パスを設定した場合は、`build`したときのカレントディレクトリを基準とした絶対パスに変換する。
+    /// ディレクトリが存在しない場合は、`build`したときに作成する。
+    pub fn directory(mut self, directory: impl AsRef<Path>) -> Self {
+        self.directory = Some(directory.as_ref().to_owned());
+
+        self
+    }
+
+    /// ローテーションによって閉じたファイルを移動するディレクトリを設定する。
+    ///
+    /// 設定した場合、ローテーションによって閉じたファイルは、圧縮した後にこのディレクトリへ移動され、
+    /// ファイルを作成するディレクトリには現在ログを記録しているファイルのみが残る。ファイルの名前を
+    /// 変更できない場合(異なるファイルシステムのディレクトリなど)は、ファイルを複製した後に削除する。
+    /// 古いファイルの削除はこのディレクトリのファイルを対象とするため、保存するファイルの合計サイズと
+    /// 空き容量には、現在ログを記録しているファイルを含めない。
+    ///
+    /// 相対パスを設定した場合やディレクトリが存在しない場合は、ファイルを作成するディレクトリと
+    /// 同様に扱う。設定しなかった場合は、ファイルを移動しない。
+    pub fn archive_directory(mut self, archive_directory: impl AsRef<Path>) -> Self {
+        self.archive_directory = Some(archive_directory.as_ref().to_owned());
+
+        self
+    }
+
+    /// ファイル名の接頭語を設定する。
+    ///
+    /// パスの区切り文字や制御文字など、ファイル名に使用できない文字を含む場合、または生成する
+    /// ファイル名が255バイトに収まらない場合、構築するときに`InvalidInput`エラーとなる。
+    /// 空の接頭語を設定した場合は、区切り文字を付けずに、日時から始まるファイル名
+    /// (`20220527.log`)となる。その場合、同じディレクトリの日時から始まる同じ拡張子のファイルは、
+    /// すべて古いファイルを削除する対象となる。
+    pub fn filename_prefix(mut self, filename_prefix: impl Into<String>) -> Self {
+        self.filename_prefix = Some(filename_prefix.into());
+
+        self
+    }
+
+    /// ファイル名の日時の後に、プロセスIDを含めるか設定する。
+    ///
+    /// 含める場合、ファイル名は`{prefix}-<yyyymmdd>-<pid>.{extension}`となる。ホスト名などの
+    /// タグも設定した場合は、`{prefix}-<yyyymmdd>-{host_tag}-<pid>.{extension}`となる。
+    /// 古いファイルを削除するときは、同じタグを含むファイルのみを対象とするため、同じディレクトリを
+    /// 共有する複数のインスタンスが、互いのファイルを削除しない。ただし、プロセスIDは起動するたびに
+    /// 変わるため、以前のプロセスが作成したファイルは削除されない。設定しなかった場合は`false`となる。
+    pub fn include_pid(mut self, include_pid: bool) -> Self {
+        self.include_pid = include_pid;
+
+        self
+    }
+
+    /// ファイル名の日時の後に含める、ホスト名などのタグを設定する。
+    ///
+    /// 設定した場合、ファイル名は`{prefix}-<yyyymmdd>-{host_tag}.{extension}`となり、古いファイルを
+    /// 削除するときは、同じタグを含むファイルのみを対象とする。タグが空の場合と、ピリオドまたは
+    /// ファイル名に使用できない文字を含む場合は、`build`で`InvalidInput`エラーとなる。
+    pub fn host_tag(mut self, host_tag: impl Into<String>) -> Self {
+        self.host_tag = Some(host_tag.into());
+
+        self
+    }
+
+    /// ファイル名の接頭語と日時の間の区切り文字を設定する。
+    ///
+    /// 区切り文字は、ファイル名の作成と、古いファイルを削除するときのファイル名の判定の両方に使用する。
+    /// パスの区切り文字などファイル名に使用できない文字と、日時と区別できない数字は、`build`で
+    /// `InvalidInput`エラーとなる。設定しなかった場合は`-`となる。
+    pub fn separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+
+        self
+    }
+
+    /// ファイルの最大サイズ(バイト)を設定する。
+    ///
+    /// ファイルのサイズには、ヘッダーと、再起動する前に同じファイルに記録した内容を含む。
+    /// 設定しなかった場合は、ファイルのサイズによるローテーションをしない。
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = Some(max_bytes);
+
+        self
+    }
+
+    /// ファイルの最大サイズを`ByteSize`で設定する。
+    ///
+    /// 設定ファイルなどの`"10MiB"`のような文字列は、`str::parse`で`ByteSize`に変換できる。
+    /// `max_bytes`と同様に、最後に設定した値を使用する。
+    pub fn max_size(self, max_size: ByteSize) -> Self {
+        self.max_bytes(max_size.as_u64())
+    }
+
+    /// ファイルの最大サイズをキビバイト(1024バイト)単位で設定する。
+    pub fn max_size_kib(self, max_size_kib: u64) -> Self {
+        self.max_size(ByteSize::kib(max_size_kib))
+    }
+
+    /// ファイルの最大サイズをメビバイト(1024 * 1024バイト)単位で設定する。
+    pub fn max_size_mib(self, max_size_mib: u64) -> Self {
+        self.max_size(ByteSize::mib(max_size_mib))
+    }
+
+    /// ログファイルを作成するファイルシステムに確保する空き容量を設定する。
+    ///
+    /// 古いファイルを削除した後、空き容量がこの値より少ない場合は、空き容量が回復するか、
+    /// 残すファイルの最小数(`keep_minimum`)になるまで、保存する基準を超えて古い順にファイルを削除する。
+    /// 空き容量を取得できない場合は、エラーを報告して、追加で削除しない。
+    /// 設定しなかった場合は、空き容量を確認しない。
+    pub fn min_free_bytes(mut self, min_free_bytes: u64) -> Self {
+        self.min_free_bytes = Some(min_free_bytes);
+
+        self
+    }
+
+    /// 1回の削除で削除するファイルの最大数を設定する。
+    ///
+    /// 保存する基準を超えるファイルが大量に残っている場合、1回の削除に時間がかかり、ローテーションの
+    /// 契機となった書き込みを長く待たせる可能性がある。最大数を設定した場合は、古い順に最大数まで
+    /// 削除して、残りのファイルは以降のローテーションなどで削除する。確保する空き容量のために
+    /// 削除するファイルも、この最大数に含める。設定しなかった場合は、基準を超えるファイルを
+    /// すべて削除する。
+    pub fn max_deletions_per_pass(mut self, max_deletions_per_pass: usize) -> Self {
+        self.max_deletions_per_pass = Some(max_deletions_per_pass);
+
+        self
+    }
+
+    /// 現在ログを記録しているファイルを除いて、削除せずに残すファイルの最小数を設定する。
+    ///
+    /// 保存する日数や合計サイズ、確保する空き容量など、いずれの基準で削除する場合も、新しい順に
+    /// この数のファイルを残す。ログがほとんど記録されない期間に、直前の記録を含むファイルまで
+    /// 削除しないようにする。保存するファイルの最大数がこの値より少ない場合は、この値まで残す。
+    /// 設定しなかった場合は1となる。0を設定した場合は、各基準のとおりに削除する。
+    pub fn keep_minimum(mut self, keep_minimum: usize) -> Self {
+        self.keep_minimum = keep_minimum;
+
+        self
+    }
+
+    /// 古いファイルを削除するときに、決して削除しないファイルの名前を設定する。
+    ///
+    /// ファイル名は、ディレクトリを含まない名前と完全に一致する場合に限り、削除しない。
+    /// 既定のパターンや`filename_predicate`で設定した関数に一致する場合でも、これらのファイルは
+    /// ログファイルとして扱わないため、削除する対象にも、保存するファイルの数や合計サイズにも
+    /// 含めない。運用者がディレクトリに置いたREADMEなどを、誤った設定で削除しないように使用する。
+    /// 設定しなかった場合は空となる。
+    pub fn protected(mut self, protected: Vec<String>) -> Self {
+        self.protected = protected;
+
+        self
+    }
+
+    /// ファイルのサイズによってローテーションしたときに、ファイル名に付ける接尾辞を設定する。
+    ///
+    /// 設定しなかった場合は`SizeSuffix::Counter`となる。
+    pub fn size_suffix(mut self, size_suffix: SizeSuffix) -> Self {
+        self.size_suffix = size_suffix;
+
+        self
+    }
+
+    /// ファイルのサイズによってローテーションしたファイルの名前が、重複しないようにするかを設定する。
+    ///
+    /// 設定した場合、`SizeSuffix::Time`で1秒以内に複数回ローテーションしたときは、同じファイルに
+    /// 追記せずに、直前のファイルの時刻に1秒を加えた時刻を接尾辞とする。また、構築するときに
+    /// ディレクトリから同じ日時のファイルを検索して、最も新しいファイルから記録を再開するため、
+    /// 再起動した後にローテーションしても、既に存在するファイルに記録しない。`OpenMode::Truncate`を
+    /// 設定している場合は、既に存在するファイルを空にしないように、次のファイルから記録を再開する。
+    /// 設定しなかった場合は`false`となり、同じ日時の最初のファイルから記録を再開する。
+    pub fn unique_segments(mut self, unique_segments: bool) -> Self {
+        self.unique_segments = unique_segments;
+
+        self
+    }
+
+    /// 構築するたびに、新しいファイルから記録を開始するかを設定する。
+    ///
+    /// 設定した場合、構築するときにディレクトリから同じ日時のファイルを検索して、既に存在する場合は、
+    /// 最も新しいファイルの次の、サイズによってローテーションしたときと同じ接尾辞を付けたファイルから
+    /// 記録を開始する。`OpenMode`に関わらず、前回の実行で記録したファイルに追記したり、空にしたりしない
+    /// ため、実行ごとにログを分けられる。前回の実行で記録したファイルは、他のファイルと同じ基準で
+    /// 削除し、`keep_minimum`を1以上に設定していれば、直前の実行のファイルは残る。
+    /// 設定しなかった場合は`false`となる。
+    pub fn roll_on_start(mut self, roll_on_start: bool) -> Self {
+        self.roll_on_start = roll_on_start;
+
+        self
+    }
+
+    /// ログを記録するファイルを開くときに、既に存在するファイルを扱う方法を設定する。
+    ///
+    /// 設定しなかった場合は`OpenMode::Append`となる。
+    pub fn open_mode(mut self, open_mode: OpenMode) -> Self {
+        self.open_mode = open_mode;
+
+        self
+    }
+
+    /// ファイルに書き込むときに使用するバッファの容量(バイト)を設定する。
+    ///
+    /// バッファリングすることで、ファイルへの書き込みのシステムコールを減らすことができる。
+    /// バッファの内容は、バッファが一杯になったとき、ローテーションしたとき、明示的に`flush`を
+    /// 呼び出したときにファイルに書き込まれる。
+    /// ただし、プロセスが異常終了した場合など、ファイルに書き込まれていないバッファの内容は
+    /// 失われる可能性がある。設定しなかった場合は0となり、バッファリングしない。
+    pub fn buffer_capacity(mut self, buffer_capacity: usize) -> Self {
+        self.buffer_capacity = buffer_capacity;
+
+        self
+    }
+
+    /// 書き込むたびにバッファの内容をファイルに書き込むかを設定する。
+    ///
+    /// 設定しなかった場合は`false`となる。
+    pub fn flush_on_write(mut self, flush_on_write: bool) -> Self {
+        self.flush_on_write = flush_on_write;
+
+        self
+    }
+
+    /// フラッシュするたびに、ファイルの内容をストレージに同期するかを設定する。
+    ///
+    /// `flush`は、バッファの内容をオペレーティングシステムに渡すだけで、ストレージへの書き込みは
+    /// オペレーティングシステムに任せるため、電源が失われた場合などに、フラッシュしたログが失われる
+    /// 可能性がある。有効にした場合、`flush`は`FileSystem::sync_all`でファイルの内容をストレージに
+    /// 書き込むまで待機し、同期できなかった場合はそのエラーを返却する。
+    ///
+    /// ストレージへの同期は、ストレージによっては数ミリ秒から数十ミリ秒かかるため、頻繁に
+    /// フラッシュする場合、特に`flush_on_write`と組み合わせた場合は、書き込みの性能が大きく
+    /// 低下する。設定しなかった場合は`false`となる。
+    pub fn fsync_on_flush(mut self, fsync_on_flush: bool) -> Self {
+        self.fsync_on_flush = fsync_on_flush;
+
+        self
+    }
+
+    /// `MakeWriter`が返却するライターごとに、レコードをまとめて書き込むかを設定する。
+    ///
+    /// 有効にした場合、ライターは`write`で書き込まれたバイト列を保持して、`flush`を呼び出したとき、
+    /// または破棄したときに、ロックを獲得して1度に書き込む。そのため、複数のスレッドが同時に
+    /// ログを出力しても、レコードの途中に他のレコードが混ざらない。
+    /// ただし、ライターは1つのレコード全体をメモリに保持するため、同時に出力するスレッドの数と
+    /// レコードの大きさに応じてメモリを消費する。
+    /// 設定しなかった場合は`false`となり、`write`を呼び出すたびにファイルに書き込む。
+    pub fn atomic_records(mut self, atomic_records: bool) -> Self {
+        self.atomic_records = atomic_records;
+
+        self
+    }
+
+    /// ファイルのサイズによるローテーションを、レコードの間でのみ行うかを設定する。
+    ///
+    /// 有効にした場合、`write`の1回の呼び出しを1つのレコードとして扱い、レコードを書き込むと最大サイズを
+    /// 超える場合は、書き込む前に連番を付けたファイルに切り替える。そのため、ファイルのサイズは
+    /// 最大サイズを超えず、JSON Linesなどの1つのレコードが2つのファイルに分かれない。ただし、空の
+    /// ファイルには、最大サイズを超えるレコードも書き込む。`MakeWriter`が返却するライターに、
+    /// 1つのレコードを複数回に分けて書き込む場合は、`atomic_records`も有効にする。
+    /// 設定しなかった場合は`false`となり、最大サイズに達した後に書き込むときにローテーションする。
+    pub fn whole_records(mut self, whole_records: bool) -> Self {
+        self.whole_records = whole_records;
+
+        self
+    }
+
+    /// 構築したときにファイルを作成できなかった場合に、標準エラー出力にログを記録するかを設定する。
+    ///
+    /// 有効にした場合、ディレクトリに書き込めないなどの理由でファイルを作成できなくても構築に
+    /// 成功し、ファイルを作成できるまで標準エラー出力にログを記録する。その間、書き込むときに
+    /// 1秒ごとにファイルの作成を再試行し、作成できた後のログはファイルに記録する。標準エラー
+    /// 出力にはフッターを書き込まない。標準エラー出力に切り替えるときは、ファイルを作成できなかった
+    /// `AppenderError::CreateWriter`を`on_error`に設定した関数に渡す。
+    /// 設定しなかった場合は`false`となり、ファイルを作成できなかったときは構築に失敗する。
+    pub fn stderr_fallback(mut self, stderr_fallback: bool) -> Self {
+        self.stderr_fallback = stderr_fallback;
+
+        self
+    }
+
+    /// ログを記録するファイルを開くときに、試みる最大の回数を設定する。
+    ///
+    /// ネットワークファイルシステムなどで、`Interrupted`や`WouldBlock`、`TimedOut`などの一時的な
+    /// エラーによってファイルを開けなかった場合は、10ミリ秒から始めて2倍ずつ待ち時間を延ばしながら、
+    /// この回数まで開き直す。`PermissionDenied`など、再試行しても解消しないエラーの場合は、
+    /// 再試行せずにエラーとする。再試行している間は、ログを書き込むスレッドが待機する。
+    /// 0を指定した場合は1となり、再試行しない。設定しなかった場合は3となる。
+    pub fn open_attempts(mut self, open_attempts: usize) -> Self {
+        self.open_attempts = open_attempts.max(1);
+
+        self
+    }
+
+    /// バッファの内容を定期的にファイルに書き込む間隔を設定する。
+    ///
+    /// 設定した場合、`build`したときにバックグラウンドのスレッドを開始して、間隔ごとにフラッシュする。
+    /// スレッドは`DailyRollingFileAppender`を破棄したときに終了する。
+    /// ログを書き込んでいるスレッドを待たせないように、ロックを獲得できなかった場合は、次の間隔まで
+    /// フラッシュしない。フラッシュできなかった場合は、`AppenderError::Flush`を`on_error`に設定した
+    /// 関数に渡す。設定しなかった場合は、定期的にフラッシュしない。
+    pub fn flush_interval(mut self, flush_interval: StdDuration) -> Self {
+        self.flush_interval = Some(flush_interval);
+
+        self
+    }
+
+    /// 書き込みの有無に関わらず、日時の境界でファイルをローテーションするかを設定する。
+    ///
+    /// ローテーションは書き込むときに確認するため、ログがほとんど書き込まれない場合は、日時の境界を
+    /// 過ぎても、前の日時のファイルを開いたままになる。設定した場合、`build`したときにバックグラウンドの
+    /// スレッドを開始して、次の日時の境界でファイルをローテーションする。時計の変更に追従するため、
+    /// スレッドは最長で`SCHEDULER_MAX_WAIT`ごとに日時を確認するので、ローテーションは境界から
+    /// 最大でその時間だけ遅れる。スレッドは`DailyRollingFileAppender`を破棄したときに終了する。
+    /// 設定しなかった場合は`false`となり、日時の境界の後に最初に書き込むときにローテーションする。
+    pub fn scheduled_rollover(mut self, scheduled_rollover: bool) -> Self {
+        self.scheduled_rollover = scheduled_rollover;
+
+        self
+    }
+
+    /// ログファイルの拡張子を設定する。
+    ///
+    /// 拡張子の先頭のピリオドは不要で、付けた場合は取り除かれる。設定しなかった場合は`log`となる。
+    pub fn extension(mut self, extension: impl Into<String>) -> Self {
+        self.extension = extension.into().trim_start_matches('.').to_owned();
+
+        self
+    }
+
+    /// ローテーションしたファイルを、既定の圧縮レベルのgzipで圧縮するか設定する。
+    ///
+    /// 圧縮する場合、ローテーションしたファイルは`{ファイル名}.gz`に圧縮され、元のファイルは削除される。
+    /// 設定しなかった場合は圧縮しない。`compression(Compression::Gzip { level: 6 })`と同じ。
+    #[cfg(feature = "gzip")]
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compression = match compress {
+            true => Compression::Gzip { level: 6 },
+            false => Compression::None,
+        };
+
+        self
+    }
+
+    /// ローテーションしたファイルを圧縮する方式を設定する。
+    ///
+    /// 圧縮する場合、ローテーションしたファイルは`{ファイル名}.{圧縮方式の拡張子}`に圧縮され、
+    /// 元のファイルは削除される。設定しなかった場合は`Compression::None`となり、圧縮しない。
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+
+        self
+    }
+
+    /// 現在ログを記録しているファイルを指すシンボリックリンクを作成するか設定する。
+    ///
+    /// 作成する場合、`{filename_prefix}{separator}current.{extension}`という名前のシンボリックリンクを、
+    /// ファイルを作成するディレクトリに作成して、ローテーションするたびに更新する。
+    /// シンボリックリンクは一時的な名前で作成した後に名前を変更するため、ローテーションしている
+    /// 間もシンボリックリンクが存在しない状態にはならない。
+    /// Unix系以外のプラットフォームでは、警告を出力してシンボリックリンクを作成しない。
+    /// 設定しなかった場合は作成しない。
+    pub fn current_link(mut self, current_link: bool) -> Self {
+        self.current_link = current_link;
+
+        self
+    }
+
+    /// ログを記録しているファイルを、`.part`を付けた名前で書き込むかを設定する。
+    ///
+    /// 有効にした場合、ログを記録しているファイルは`{name}.{extension}.part`という名前で書き込み、
+    /// ローテーションによって閉じたときに`{name}.{extension}`に名前を変更する。ディレクトリを
+    /// 監視してログを収集するプログラムが、書き込んでいる途中のファイルを収集しないようにするために
+    /// 使用する。`.part`ファイルはログファイルとして扱わないため、保存するファイルの数に含めず、
+    /// 削除することもない。名前を変更する先にファイルが存在する場合は、上書きせずに追記する。
+    ///
+    /// `DailyRollingFileAppender`を破棄したときには名前を変更しないため、再起動した後は、
+    /// 同じ日時の`.part`ファイルに追記を再開する。設定しなかった場合は`false`となる。
+    pub fn part_files(mut self, part_files: bool) -> Self {
+        self.part_files = part_files;
+
+        self
+    }
+
+    /// ローテーションによって閉じたファイルを、マニフェストに記録するかを設定する。
+    ///
+    /// 有効にした場合、ファイルを作成するディレクトリの`{filename_prefix}{separator}manifest.jsonl`に、
+    /// 閉じたファイルごとに1行のJSONを追記する。各行は、ファイル名(`file`)、圧縮や移動をした後の
+    /// ファイルのサイズ(`bytes`)、このプロセスがそのファイルに最初と最後にレコードを書き込んだ
+    /// 時刻(`first_record`、`last_record`)、及びローテーションした理由(`reason`)を含む。
+    /// 時刻はRFC 3339形式で、レコードを書き込まなかった場合は`null`となる。理由は、日時の境界を
+    /// 過ぎた場合は`"date"`、同じ日時のまま切り替えた場合は`"size"`となる。マニフェストは
+    /// ログファイルとして扱わないため、古いファイルとして削除することはない。
+    /// 設定しなかった場合は`false`となる。
+    pub fn manifest(mut self, manifest: bool) -> Self {
+        self.manifest = manifest;
+
+        self
+    }
+
+    /// ファイルをローテーションする間隔を設定する。
+    ///
+    /// 設定しなかった場合は`Rotation::Daily`となる。
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+
+        self
+    }
+
+    /// 日付の境界を決めるタイムゾーンを設定する。
+    ///
+    /// 最初に作成するファイルの日付と、ファイルをローテーションするか確認するときの日付の
+    /// 両方に、このタイムゾーンを使用する。設定しなかった場合は`TimeZone::Utc`となる。
+    pub fn time_zone(mut self, time_zone: TimeZone) -> Self {
+        self.time_zone = time_zone;
+
+        self
+    }
+
+    /// 今日の日付を返却する時計を設定する。
+    ///
+    /// 設定しなかった場合は`SystemClock`となる。
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+
+        self
+    }
+
+    /// ログファイルを作成、検索及び削除するファイルシステムを設定する。
+    ///
+    /// 設定しなかった場合は`OsFileSystem`となる。単体テストでは、`MemoryFileSystem`を設定すると、
+    /// ディスクに書き込まずに、書き込まれたログやローテーションしたファイルを確認できる。
+    /// Unix系のプラットフォームで、作成するファイルやディレクトリの許可モードを指定する場合は、
+    /// 許可モードを設定した`OsFileSystem`を設定する。
+    pub fn file_system(mut self, file_system: impl FileSystem + 'static) -> Self {
+        self.file_system = Box::new(file_system);
+
+        self
+    }
+
+    /// ファイルの代わりに、ローテーションするたびに作成する出力先にログを書き込むように設定する。
+    ///
+    /// ファイルシステムに`SinkFileSystem`を設定する。出力先は読み込みや削除ができないため、
+    /// 古いファイルの削除や圧縮などは行わない。`file_system`と同時に設定した場合は、最後に設定した
+    /// ものを使用する。
+    pub fn sink_factory(self, sink_factory: impl SinkFactory + 'static) -> Self {
+        self.file_system(SinkFileSystem::new(sink_factory))
+    }
+
+    /// ファイルをローテーションしたときに呼び出す関数を設定する。
+    ///
+    /// 関数は、ローテーションによって閉じたファイルのパスを引数に、古いファイルを削除する前に
+    /// 呼び出される。ファイルを圧縮する場合、引数は圧縮したファイルのパスとなる。ファイルを移動する
+    /// ディレクトリが設定されている場合、引数は移動した後のファイルのパスとなる。
+    /// 関数は、`DailyRollingFileAppender`を構築したときに作成するファイルに対しては呼び出されない。
+    pub fn on_rollover(mut self, on_rollover: impl FnMut(&Path) + Send + 'static) -> Self {
+        self.on_rollover = Some(Box::new(on_rollover));
+
+        self
+    }
+
+    /// 古いファイルを削除する代わりに報告する設定で、削除するファイルを報告する関数を設定する。
+    ///
+    /// 関数は、`dry_run`を有効にした場合に、古いファイルを削除するたびに、削除するファイルごとに
+    /// そのパスを引数に呼び出される。設定しなかった場合は、削除するファイルを標準エラー出力に出力する。
+    pub fn on_planned_removal(
+        mut self,
+        on_planned_removal: impl FnMut(&Path) + Send + 'static,
+    ) -> Self {
+        self.on_planned_removal = Some(Box::new(on_planned_removal));
+
+        self
+    }
+
+    /// ログファイル名に含める日時の書式を設定する。
+    ///
+    /// 書式は`time`クレートの書式記述(例えば`[year]-[month]-[day]`)で指定する。
+    /// 書式は、固定長の数字で年月日と、ローテーションする間隔に応じて時や分を含まなければならず、
+    /// ファイル名に使用できない文字を含んではならない。書式は`build`で検証され、条件を満たさない
+    /// 場合は`InvalidInput`エラーとなる。
+    /// 設定しなかった場合は、`yyyymmdd`に、ローテーションする間隔に応じて時や分が続く書式となる。
+    pub fn date_format(mut self, date_format: impl Into<String>) -> Self {
+        self.date_format = Some(date_format.into());
+
+        self
+    }
+
+    /// 新しく作成したファイルの先頭に書き込むヘッダーを返却する関数を設定する。
+    ///
+    /// 関数は、ローテーションする間隔で切り捨てたファイルの日時を引数に、ファイルを開いた直後に
+    /// 呼び出される。ヘッダーは空のファイルにのみ書き込まれ、プロセスを再起動して同じファイルに
+    /// 追記する場合は書き込まれない。ヘッダーに改行が必要な場合は、関数が返却するバイト列に含める。
+    pub fn header(
+        mut self,
+        header: impl Fn(&OffsetDateTime) -> Vec<u8> + Send + Sync + 'static,
+    ) -> Self {
+        self.header = Some(Box::new(header));
+
+        self
+    }
+
+    /// ファイルを閉じる直前に、ファイルの末尾に書き込むフッターを設定する。
+    ///
+    /// フッターは、ローテーションしたときに前のファイルに書き込まれ、アペンダーを破棄したときに
+    /// ログを記録しているファイルに書き込まれる。同じファイルを開き直す場合
+    /// (`reopen`など)は書き込まれない。フッターに改行が必要な場合は、バイト列に含める。
+    pub fn footer(mut self, footer: impl Into<Vec<u8>>) -> Self {
+        self.footer = Some(footer.into());
+
+        self
+    }
+
+    /// 各レコードの末尾に付ける終端を設定する。
+    ///
+    /// `write`の1回の呼び出しを1つのレコードとして扱い、レコードが終端で終わっていない場合は、
+    /// 末尾に終端を付けて書き込む。レコードをまとめて書き込む設定の場合は、まとめたレコードに対して
+    /// 付ける。改行を出力しない書式で、レコードが連結されないように使用する。`write`は、終端を
+    /// 含まない、引数のバイト列のバイト数を返却する。
+    /// 設定しなかった場合、または空のバイト列を設定した場合は、終端を付けない。
+    pub fn record_terminator(mut self, record_terminator: impl Into<Vec<u8>>) -> Self {
+        self.record_terminator = Some(record_terminator.into());
+
+        self
+    }
+
+    /// 各レコードの先頭に、書き込んだ時刻を付けるかを設定する。
+    ///
+    /// 有効にした場合、`write`の1回の呼び出しを1つのレコードとして扱い、タイムゾーンにおける現在の
+    /// 時刻をRFC 3339形式(`2022-05-27T15:30:00+09:00`など)で、空白に続けてレコードの先頭に付ける。
+    /// 時刻とレコードは、終端を付けたレコードとともに1度に書き込む。レコードをまとめて書き込む設定の
+    /// 場合は、まとめたレコードを書き込むときの時刻を付ける。時刻を出力しない書式で使用する。
+    /// 設定しなかった場合は`false`となる。
+    pub fn timestamp_prefix(mut self, timestamp_prefix: bool) -> Self {
+        self.timestamp_prefix = timestamp_prefix;
+
+        self
+    }
+
+    /// アペンダーが管理するログファイルであるかを判定する関数を設定する。
+    ///
+    /// 古いファイルを削除するとき、ディレクトリに存在するファイルのうち、この関数が`true`を返却した
+    /// ファイルのみを削除する対象とする。設定しなかった場合は、ファイル名の接頭語、日時及び拡張子から
+    /// 構成される既定のパターンに一致するファイルを対象とする。
+    /// 複数のアペンダーが同じディレクトリにファイルを出力する場合に、他のアペンダーのファイルを
+    /// 削除しないように使用する。既定のパターンに一致しないファイルは、最も古いファイルとして扱う。
+    pub fn filename_predicate(
+        mut self,
+        filename_predicate: impl Fn(&str) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.filename_predicate = Some(Box::new(filename_predicate));
+
+        self
+    }
+
+    /// ログファイルに名前を付ける規則を設定する。
+    ///
+    /// 設定した場合、ログファイルの名前はこの規則で作成され、アペンダーが管理するログファイルは、
+    /// ファイル名を判定する関数が設定されていなければ、この規則で判定される。区切り文字、拡張子、
+    /// 日時の書式及び接尾辞の設定は、ファイル名に適用されない。ファイル名の接頭語は、
+    /// シンボリックリンクなどの名前に使用する。設定しなかった場合は、`DefaultNamer`の規則となる。
+    pub fn file_namer(mut self, file_namer: impl FileNamer + 'static) -> Self {
+        self.file_namer = Some(Box::new(file_namer));
+
+        self
+    }
+
+    /// アペンダーの内部でエラーが発生したときに呼び出す関数を設定する。
+    ///
+    /// ローテーションしたファイルの作成、古いファイルの検索や削除などで発生したエラーは、
+    /// 書き込みの呼び出し元に返却できないため、この関数に渡される。関数は、エラーが発生した
+    /// 操作と対象のパスを保持する`AppenderError`を受け取る。
+    /// 設定しなかった場合は、エラーを標準エラー出力に出力する。
+    pub fn on_error(mut self, on_error: impl FnMut(&AppenderError) + Send + 'static) -> Self {
+        self.on_error = Some(Box::new(on_error));
+
+        self
+    }
+
+    /// 同じディレクトリにログを記録する他のプロセスと、ローテーションと古いファイルの削除を
+    /// 調整するか設定する。
+    ///
+    /// 調整する場合、ファイルを作成するディレクトリに`{filename_prefix}.lock`という名前のファイルを
+    /// 作成してロックし、ロックを獲得したプロセスのみがローテーションと古いファイルの削除をする。
+    /// ローテーションした先のファイルを他のプロセスが既に作成している場合は、そのファイルを開くだけで、
+    /// 閉じたファイルの圧縮や移動、及び古いファイルの削除はしない。ロックを獲得できなかった場合は、
+    /// エラーを報告して、調整せずにローテーションする。設定しなかった場合は調整しない。
+    ///
+    /// 次の制限がある。
+    ///
+    /// * ロックはアドバイザリロックであるため、このアペンダー以外がファイルを変更することは防げない。
+    ///   また、NFSなどのネットワークファイルシステムでは、ロックが機能しない場合がある。
+    /// * 各プロセスは自身の時計でローテーションするため、時計が遅れているプロセスは、他のプロセスが
+    ///   閉じたファイルに書き込み続ける。
+    /// * 他のプロセスがローテーションしてから、自身がローテーションするまでに前のファイルに書き込んだ
+    ///   ログは、閉じたファイルを圧縮または移動する設定の場合は失われる。
+    /// * ファイルのサイズは、ファイルを開いたときのサイズと自身が書き込んだサイズから数えるため、
+    ///   サイズによるローテーションは、プロセスごとに異なる時点で行われる。
+    /// * ロックファイルはオペレーティングシステムのファイルシステムに作成するため、`MemoryFileSystem`
+    ///   などを設定した場合も、ディレクトリがディスクに存在しなければならない。
+    /// * 同じプロセスの複数のアペンダーで調整する場合は、`directory_lock`を無効にする必要がある。
+    #[cfg(feature = "process_lock")]
+    pub fn process_lock(mut self, process_lock: bool) -> Self {
+        self.process_lock = process_lock;
+
+        self
+    }
+
+    /// ログを記録するファイルの文字エンコーディングを設定する。
+    ///
+    /// `encoding`フィーチャーを有効にした場合に使用できる。`write`に渡されたUTF-8のバイト列を、
+    /// レコードごとにこのエンコーディングに変換して書き込む。ヘッダー、フッター及びレコードの終端も
+    /// 変換する。UTF-16LEとUTF-16BEの場合は、新しく作成したファイルの先頭に、ヘッダーより前に
+    /// バイト順マークを書き込む。UTF-8として不正なバイト列は置換文字に、変換先のエンコーディングで
+    /// 表現できない文字は数値文字参照(`&#12354;`など)に置き換える。1つの文字が複数の`write`に
+    /// 分かれて渡された場合は正しく変換できないため、レコードは`write`の1回の呼び出しで渡す。
+    /// ファイルのサイズは、変換した後のバイト数で数える。
+    /// 設定しなかった場合、またはUTF-8を設定した場合は、変換せずに書き込む。
+    #[cfg(feature = "encoding")]
+    pub fn encoding(mut self, encoding: &'static encoding_rs::Encoding) -> Self {
+        self.encoding = Some(encoding);
+
+        self
+    }
+
+    /// `DailyRollingFileAppender`を構築する。
+    ///
+    /// # Returns
+    ///
+    /// `DailyRollingFileAppender`インスタンス。ファイルを作成するディレクトリ、またはファイル名の
+    /// 接頭語が設定されていない場合は`InvalidInput`エラー。ファイルを作成できなかった場合は、
+    /// そのエラー。
+    pub fn build(self) -> io::Result<DailyRollingFileAppender> {
+        let now = self
+            .rotation
+            .truncate(self.time_zone.now(self.clock.as_ref()));
+        self.build_at(now)
+    }
+
+    /// 指定された日時のファイルにログを記録する`DailyRollingFileAppender`を構築する。
+    ///
+    /// # 引数
+    ///
+    /// - now: ローテーションする間隔で切り捨てた日時。
+    ///
+    /// # 戻り値
+    ///
+    /// `DailyRollingFileAppender`インスタンス。
+    fn build_at(self, now: OffsetDateTime) -> io::Result<DailyRollingFileAppender> {
+        let flush_interval = self.flush_interval;
+        let scheduled_rollover = self.scheduled_rollover;
+        let (state, writer) = Inner::new(now, self)?;
+        let state = Arc::new(state);
+        let writer = Arc::new(writer);
+        let flusher = match flush_interval {
+            Some(interval) => Some(Flusher::spawn(
+                Arc::downgrade(&state),
+                Arc::downgrade(&writer),
+                interval,
+            )?),
+            None => None,
+        };
+        let scheduler = match scheduled_rollover {
+            true => Some(Scheduler::spawn(
+                Arc::downgrade(&state),
+                Arc::downgrade(&writer),
+            )?),
+            false => None,
+        };
+
+        Ok(DailyRollingFileAppender {
+            state,
+            writer,
+            _flusher: flusher,
+            _scheduler: scheduler,
+        })
+    }
+}
+
+impl Drop for DailyRollingFileAppender {
+    fn drop(&mut self) {
+        // フッターとバッファに残っている内容を書き込み、書き込めなかった場合はエラーを報告する
+        let writer = self.writer.read();
+        let mut writer = writer.lock().unwrap_or_else(PoisonError::into_inner);
+        self.state.write_footer(&mut writer);
+        if let Err(err) = writer.flush() {
+            self.state.report_error(AppenderError::Flush {
+                path: self.state.current_path(),
+                source: err,
+            });
+        }
+    }
+}
+
+impl io::Write for DailyRollingFileAppender {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let now = self.state.now();
+        // フラッシュするスレッドと共有しているため、ロックを獲得する
+        let mut writer = self.writer.write();
+        let writer = writer.get_mut().unwrap_or_else(PoisonError::into_inner);
+        match self.state.should_rollover() {
+            Some(Rollover::Date(current)) => {
+                let _did_cas = self.state.advance_date(now, current);
+                debug_assert!(
+                    _did_cas,
+                    "if we have &mut access to the appender, \
+                    no other thread can have advanced the timestamp..."
+                );
+                self.state.refresh_writer(&now, 0, writer);
+            }
+            Some(Rollover::Pending) => self.state.retry_rollover(writer),
+            Some(Rollover::Size) => self.state.roll_by_size(writer),
+            Some(Rollover::Missing | Rollover::Fallback) => self.state.recreate_writer(writer),
+            None => {}
+        }
+        let record = self.state.prepare_record(buf);
+        if self.state.would_split_record(record.len()) {
+            self.state.roll_by_size(writer);
+        }
+
+        // ローテーションは書き込む前に済ませているため、書き込んだ内容は、一部しか書き込めなかった
+        // 場合も含めて、全て新しいファイルに記録される
+        let (written, len) = write_prepared(writer, buf, record)?;
+        self.state.record_written(len);
+        if self.state.flush_on_write {
+            writer.flush()?;
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let writer = self.writer.read();
+        writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .flush()?;
+
+        self.state.sync_current_file()
+    }
+}
+
+impl<'a> tracing_subscriber::fmt::writer::MakeWriter<'a> for DailyRollingFileAppender {
+    type Writer = RollingWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        let now = self.state.now();
+        match self.state.should_rollover() {
+            Some(Rollover::Date(current)) if self.state.advance_date(now, current) => {
+                let mut writer = self.writer.write();
+                let writer = writer.get_mut().unwrap_or_else(PoisonError::into_inner);
+                self.state.refresh_writer(&now, 0, writer);
+            }
+            Some(Rollover::Date(_)) => {}
+            Some(Rollover::Pending) => {
+                let mut writer = self.writer.write();
+                // 書き込みロックを獲得するまでに、他のスレッドがローテーションしている可能性があるため、
+                // 再度確認する
+                if self.state.rollover_pending.load(Ordering::Acquire) {
+                    let writer = writer.get_mut().unwrap_or_else(PoisonError::into_inner);
+                    self.state.retry_rollover(writer);
+                }
+            }
+            Some(Rollover::Size) => {
+                let mut writer = self.writer.write();
+                // 書き込みロックを獲得するまでに、他のスレッドがローテーションしている可能性があるため、
+                // 再度確認する
+                if self.state.exceeds_max_bytes() {
+                    let writer = writer.get_mut().unwrap_or_else(PoisonError::into_inner);
+                    self.state.roll_by_size(writer);
+                }
+            }
+            Some(Rollover::Missing) => {
+                let mut writer = self.writer.write();
+                // 書き込みロックを獲得するまでに、他のスレッドがファイルを作成し直している可能性が
+                // あるため、再度確認する
+                if self.state.is_current_file_missing() {
+                    let writer = writer.get_mut().unwrap_or_else(PoisonError::into_inner);
+                    self.state.recreate_writer(writer);
+                }
+            }
+            Some(Rollover::Fallback) => {
+                let mut writer = self.writer.write();
+                // 書き込みロックを獲得するまでに、他のスレッドがファイルを作成している可能性が
+                // あるため、再度確認する
+                if self.state.fallback.load(Ordering::Acquire) {
+                    let writer = writer.get_mut().unwrap_or_else(PoisonError::into_inner);
+                    self.state.recreate_writer(writer);
+                }
+            }
+            None => {}
+        }
+
+        RollingWriter(&self.writer, &self.state, RefCell::new(Vec::new()))
+    }
+}
+
+impl Debug for RollingWriter<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RollingWriter")
+            .field("bytes_written", &self.1.bytes_written)
+            .field("flush_on_write", &self.1.flush_on_write)
+            .field("pending", &self.2.borrow().len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RollingWriter<'_> {
+    /// 保持しているレコードを、ロックを獲得して1度に書き込む。
+    ///
+    /// # 戻り値
+    ///
+    /// 書き込めなかった場合は、そのエラー。その場合も、保持しているレコードは破棄する。
+    fn write_record(&self) -> io::Result<()> {
+        // 借用は取り出すまでに限り、エラーを報告する関数などから再び借用できるようにする
+        let record = std::mem::take(&mut *self.2.borrow_mut());
+        if record.is_empty() {
+            return Ok(());
+        }
+
+        let record = self.1.prepare_record(&record);
+        self.1.roll_before_record(self.0, record.len());
+        let writer = self.0.read();
+        let mut writer = writer.lock().unwrap_or_else(PoisonError::into_inner);
+        writer.write_all(&record)?;
+        self.1.record_written(record.len());
+        if self.1.flush_on_write {
+            writer.flush()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl io::Write for RollingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&*self).flush()
+    }
+}
+
+impl io::Write for &RollingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.1.atomic_records {
+            self.2.borrow_mut().extend_from_slice(buf);
+            return Ok(buf.len());
+        }
+
+        let record = self.1.prepare_record(buf);
+        self.1.roll_before_record(self.0, record.len());
+        // 書き込んでいる間のみロックを獲得して、ローテーションが書き込みロックを獲得できるようにする
+        let writer = self.0.read();
+        let mut writer = writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let (written, len) = write_prepared(&mut *writer, buf, record)?;
+        self.1.record_written(len);
+        if self.1.flush_on_write {
+            writer.flush()?;
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_record()?;
+        let writer = self.0.read();
+        writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .flush()?;
+
+        self.1.sync_current_file()
+    }
+}
+
+impl Drop for RollingWriter<'_> {
+    fn drop(&mut self) {
+        // `tracing_subscriber`の`fmt`レイヤーは、イベントごとにライターを破棄するため、
+        // 破棄したときに保持しているレコードを書き込む
+        if let Err(err) = self.write_record() {
+            self.1.report_error(AppenderError::Other {
+                message: "Couldn't write log record",
+                source: err,
+            });
+        }
+    }
+}
+
+/// 一定の間隔でライターをフラッシュするスレッド。
+///
+/// スレッドはライターへの弱い参照を保持する。破棄したとき、スレッドに終了を通知して、
+/// スレッドの終了を待つ。
+struct Flusher {
+    shutdown: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// 日時の境界でファイルをローテーションするスレッド。
+///
+/// スレッドはアペンダーの状態とライターへの弱い参照を保持する。破棄したとき、スレッドに終了を
+/// 通知して、スレッドの終了を待つ。
+struct Scheduler {
+    shutdown: Option<mpsc::Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// 日時の境界でローテーションするスレッドが、日時を確認するまでに待機する最長の時間。
+const SCHEDULER_MAX_WAIT: StdDuration = StdDuration::from_secs(1);
+
+/// 同じプロセスのアペンダーがログを記録している、ディレクトリ、ファイル名の接頭語及び拡張子の組み合わせ。
+static DIRECTORY_OWNERS: LazyLock<Mutex<HashSet<(PathBuf, String, String)>>> =
+    LazyLock::new(Default::default);
+
+/// `parse_log_date`が最後に解析した接頭語と、そのログファイル名に一致する正規表現。
+///
+/// 任意の接頭語で呼び出されても正規表現が増え続けないように、最後の接頭語の正規表現だけを保持する。
+static LOG_DATE_REGEX: Mutex<Option<(String, Regex)>> = Mutex::new(None);
+
+/// `parse_log_date`が解析する、既定の設定のログファイル名に含める日時の書式。
+static DAILY_DATE_FORMAT: LazyLock<DateFormat> =
+    LazyLock::new(|| DateFormat::from(Rotation::Daily));
+
+/// `parse_log_date`が解析するログファイル名の、任意の拡張子に一致する正規表現のパターン。
+const ANY_EXTENSION_PATTERN: &str = r"[^.]+";
+
+/// 同じプロセスのアペンダーが古いファイルを削除するときに、ディレクトリごとに獲得するロック。
+///
+/// ロックは削除している間のみ保持して、どのアペンダーも保持していないロックは、次にロックを
+/// 作成するときに取り除く。
+static RETENTION_LOCKS: LazyLock<Mutex<HashMap<PathBuf, Weak<Mutex<()>>>>> =
+    LazyLock::new(Default::default);
+
+/// アペンダーが、ディレクトリ、ファイル名の接頭語及び拡張子の組み合わせを所有していることを表す。
+///
+/// 破棄したとき、組み合わせの所有を解除する。
+struct DirectoryOwner {
+    key: (PathBuf, String, String),
+}
+
+/// ローテーションによって閉じたファイルについて、マニフェストに記録する情報。
+struct ClosedFile {
+    /// 最初にレコードを書き込んだ時刻(UNIX時間)。書き込まなかった場合は`NO_RECORD`。
+    first_record: i64,
+    /// 最後にレコードを書き込んだ時刻(UNIX時間)。書き込まなかった場合は`NO_RECORD`。
+    last_record: i64,
+    /// ローテーションした理由。
+    reason: &'static str,
+}
+
+/// ログファイル名に含める日時の書式。
+struct DateFormat {
+    /// `time`クレートの書式記述。
+    description: OwnedFormatItem,
+    /// 書式化した日時に一致する正規表現のパターン。
+    pattern: String,
+}
+
+/// ファイルをローテーションする理由。
+enum Rollover {
+    /// 日付が変わった。
+    ///
+    /// 現在設定されているファイルをローテーションする日付を示すUnixタイムスタンプを持つ。
+    Date(usize),
+    /// 前回のローテーションで新しいファイルを作成できなかった。
+    Pending,
+    /// ファイルのサイズが最大サイズに達した。
+    Size,
+    /// ログを記録しているファイルが削除された。
+    Missing,
+    /// ファイルを作成できずに、標準エラー出力にログを記録している。
+    Fallback,
+}
+
+impl Flusher {
+    /// ライターをフラッシュするスレッドを開始する。
+    ///
+    /// フラッシュできなかった場合は、アペンダーの内部で発生したエラーとして報告する。
+    ///
+    /// # 引数
+    ///
+    /// - state: アペンダーの状態への弱い参照。
+    /// - writer: フラッシュするライターへの弱い参照。
+    /// - interval: フラッシュする間隔。
+    ///
+    /// # 戻り値
+    ///
+    /// `Flusher`インスタンス。スレッドを開始できなかった場合は、そのエラー。
+    fn spawn(
+        state: Weak<Inner>,
+        writer: Weak<RwLock<LogWriter>>,
+        interval: StdDuration,
+    ) -> io::Result<Self> {
+        let (shutdown, receiver) = mpsc::channel::<()>();
+        let handle = thread::Builder::new()
+            .name("restricted-rolling-file-appender-flusher".to_owned())
+            .spawn(move || {
+                // 終了が通知されるか、アペンダーが破棄されるまで、間隔ごとにフラッシュする
+                while let Err(RecvTimeoutError::Timeout) = receiver.recv_timeout(interval) {
+                    let (state, writer) = match (state.upgrade(), writer.upgrade()) {
+                        (Some(state), Some(writer)) => (state, writer),
+                        _ => return,
+                    };
+                    // ローテーションや書き込みを待たせないように、ロックを獲得できない場合は
+                    // 次の間隔までフラッシュしない
+                    let writer = match writer.try_read() {
+                        Some(writer) => writer,
+                        None => continue,
+                    };
+                    let result = match writer.try_lock() {
+                        Ok(mut writer) => writer.flush(),
+                        Err(TryLockError::Poisoned(err)) => err.into_inner().flush(),
+                        Err(TryLockError::WouldBlock) => continue,
+                    };
+                    if let Err(err) = result {
+                        state.report_error(AppenderError::Flush {
+                            path: state.current_path(),
+                            source: err,
+                        });
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            shutdown: Some(shutdown),
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Scheduler {
+    /// 日時の境界でファイルをローテーションするスレッドを開始する。
+    ///
+    /// # 引数
+    ///
+    /// - state: アペンダーの状態への弱い参照。
+    /// - writer: ログを記録しているファイルへのライターへの弱い参照。
+    ///
+    /// # 戻り値
+    ///
+    /// `Scheduler`インスタンス。スレッドを開始できなかった場合は、そのエラー。
+    fn spawn(state: Weak<Inner>, writer: Weak<RwLock<LogWriter>>) -> io::Result<Self> {
+        let (shutdown, receiver) = mpsc::channel::<()>();
+        let handle = thread::Builder::new()
+            .name("restricted-rolling-file-appender-scheduler".to_owned())
+            .spawn(move || loop {
+                let wait = match state.upgrade() {
+                    Some(state) => state.until_next_date().min(SCHEDULER_MAX_WAIT),
+                    None => return,
+                };
+                // 終了が通知されるか、アペンダーが破棄されるまで、日時の境界ごとにローテーションする
+                if !matches!(receiver.recv_timeout(wait), Err(RecvTimeoutError::Timeout)) {
+                    return;
+                }
+                match (state.upgrade(), writer.upgrade()) {
+                    (Some(state), Some(writer)) => state.roll_on_schedule(&writer),
+                    _ => return,
+                }
+            })?;
+
+        Ok(Self {
+            shutdown: Some(shutdown),
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        // 送信側を破棄して、スレッドに終了を通知する
+        self.shutdown.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl DirectoryOwner {
+    /// ディレクトリ、ファイル名の接頭語及び拡張子の組み合わせを所有する。
+    ///
+    /// # 引数
+    ///
+    /// - directory: ファイルを作成するディレクトリ。
+    /// - filename_prefix: ファイル名の接頭語。
+    /// - tag: ファイル名に含めるタグ。タグが異なるアペンダーは、異なるファイルに記録する。
+    /// - extension: ファイルの拡張子。拡張子が異なるアペンダーは、異なるファイルに記録し、
+    ///   異なるファイルを削除する。
+    ///
+    /// # 戻り値
+    ///
+    /// 組み合わせの所有。他のアペンダーが既に所有している場合は`AlreadyExists`エラー。
+    fn acquire(
+        directory: &Path,
+        filename_prefix: &str,
+        tag: Option<&str>,
+        extension: &str,
+    ) -> io::Result<Self> {
+        // ファイル名に使用できない`/`で区切り、タグを含む接頭語と区別する
+        let filename_prefix = match tag {
+            Some(tag) => format!("{}/{}", filename_prefix, tag),
+            None => filename_prefix.to_owned(),
+        };
+        let key = (
+            directory.to_path_buf(),
+            filename_prefix.clone(),
+            extension.to_owned(),
+        );
+        let mut owners = DIRECTORY_OWNERS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if !owners.insert(key.clone()) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "another appender already writes {} files with extension {} in {}",
+                    filename_prefix,
+                    extension,
+                    directory.display()
+                ),
+            ));
+        }
+
+        Ok(Self { key })
+    }
+}
+
+impl Drop for DirectoryOwner {
+    fn drop(&mut self) {
+        DIRECTORY_OWNERS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.key);
+    }
+}
+
+impl Drop for Flusher {
+    fn drop(&mut self) {
+        // 送信側を破棄して、スレッドに終了を通知する
+        self.shutdown.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Debug for Inner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Inner")
+            .field("directory", &self.directory)
+            .field("archive_directory", &self.archive_directory)
+            .field("filename_prefix", &self.filename_prefix)
+            .field("extension", &self.extension)
+            .field("retention", &self.retention())
+            .field("current_date", &self.current_date())
+            .field("segment", &self.segment)
+            .field("rotation", &self.rotation)
+            .field("time_zone", &self.time_zone)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Inner {
+    fn new(
+        now: OffsetDateTime,
+        builder: DailyRollingFileAppenderBuilder,
+    ) -> io::Result<(Self, RwLock<LogWriter>)> {
+        #[cfg(feature = "encoding")]
+        let builder = encode_header_and_footer(builder);
+        let directory = builder.directory.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "directory of the appender is not set",
+            )
+        })?;
+        let filename_prefix = builder.filename_prefix.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "filename prefix of the appender is not set",
+            )
+        })?;
+        if filename_prefix.chars().any(is_illegal_filename_char) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "filename prefix of the appender is not valid",
+            ));
+        }
+        if is_illegal_filename_char(builder.separator) || builder.separator.is_ascii_digit() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "filename separator of the appender is not valid",
+            ));
+        }
+        let tag = instance_tag(
+            builder.host_tag.as_deref(),
+            builder.include_pid,
+            builder.separator,
+        )?;
+        // 標準エラー出力に記録する場合は、ファイルを作成するときにディレクトリの作成も再試行する
+        if let Err(err) = builder.file_system.create_dir_all(&directory) {
+            if !builder.stderr_fallback {
+                return Err(err);
+            }
+        }
+        // 後からカレントディレクトリが変更されても、同じディレクトリにファイルを作成するように、
+        // 絶対パスに変換する
+        let directory = match directory.is_absolute() {
+            true => directory,
+            false => env::current_dir()?.join(directory),
+        };
+        let directory_owner = match builder.directory_lock {
+            true => Some(DirectoryOwner::acquire(
+                &directory,
+                &filename_prefix,
+                tag.as_deref(),
+                &builder.extension,
+            )?),
+            false => None,
+        };
+        let archive_directory = match builder.archive_directory {
+            Some(archive_directory) => {
+                builder.file_system.create_dir_all(&archive_directory)?;
+                match archive_directory.is_absolute() {
+                    true => Some(archive_directory),
+                    false => Some(env::current_dir()?.join(archive_directory)),
+                }
+            }
+            None => None,
+        };
+        // 相対パスの移動先は、古いファイルを削除するディレクトリからの相対パスとする
+        let removal_strategy = match builder.removal_strategy {
+            RemovalStrategy::MoveTo(target) if target.is_relative() => RemovalStrategy::MoveTo(
+                archive_directory
+                    .as_deref()
+                    .unwrap_or(&directory)
+                    .join(target),
+            ),
+            removal_strategy => removal_strategy,
+        };
+        let new_date_format = || match &builder.date_format {
+            Some(date_format) => DateFormat::new(date_format, builder.rotation),
+            None => Ok(DateFormat::from(builder.rotation)),
+        };
+        let date_format = new_date_format()?;
+        let log_file_regex = tagged_log_file_regex(
+            &filename_prefix,
+            builder.separator,
+            &builder.extension,
+            &date_format,
+            &exact_tag_pattern(tag.as_deref(), builder.separator),
+        );
+        let file_namer = match builder.file_namer {
+            Some(file_namer) => file_namer,
+            None => Box::new(DefaultNamer::with_settings(
+                &filename_prefix,
+                builder.separator,
+                &builder.extension,
+                new_date_format()?,
+                builder.size_suffix,
+                tag,
+            )),
+        };
+        // 長すぎるファイル名は、ファイルを開くときに分かりにくいエラーとなるため、構築するときに
+        // 拒否する
+        if file_namer.segment_name(&now, 0).len() + FILENAME_SUFFIX_RESERVE > MAX_FILENAME_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "filename of the appender is too long",
+            ));
+        }
+
+        // 時計が遅れている場合などに、既に存在するより新しい日時のファイルを残して、古い日時の
+        // ファイルに記録しないように、最も新しいファイルの日時から記録を再開する
+        let now = match newest_log_date(
+            builder.file_system.as_ref(),
+            &directory,
+            &log_file_regex,
+            &date_format,
+        ) {
+            Some(newest) => now.max(newest.assume_offset(now.offset())),
+            None => now,
+        };
+        let next_date = now + builder.rotation.duration();
+        let next_date = AtomicUsize::new(next_date.unix_timestamp() as usize);
+        let current_date = AtomicUsize::new(now.unix_timestamp() as usize);
+
+        // 再起動する前に記録した同じ日時のファイルのうち、最も新しいファイルから記録を再開する。
+        // 構築するたびに新しいファイルから記録を開始する場合は、その次のファイルから記録を開始する
+        let segment = match builder.unique_segments || builder.roll_on_start {
+            true => match newest_segment_index(
+                builder.file_system.as_ref(),
+                &directory,
+                &log_file_regex,
+                &date_format,
+                &now,
+            ) {
+                Some(index) if builder.open_mode == OpenMode::Append && !builder.roll_on_start => {
+                    index
+                }
+                Some(index) => match builder.size_suffix {
+                    SizeSuffix::Counter => index + 1,
+                    SizeSuffix::Time => {
+                        time_suffix_index(builder.time_zone, builder.clock.as_ref()).max(index + 1)
+                    }
+                },
+                None => 0,
+            },
+            false => 0,
+        };
+        let mut path = directory.join(file_namer.segment_name(&now, segment));
+        if builder.part_files {
+            path.as_mut_os_string().push(format!(".{}", PART_EXTENSION));
+        }
+        let mut on_error = builder.on_error;
+        let result = create_writer(
+            builder.file_system.as_ref(),
+            &path,
+            builder.open_mode,
+            builder.header.as_ref(),
+            &now,
+            builder.open_attempts,
+        );
+        let (file, len, fallback) = match result {
+            Ok((file, len)) => (file, len, false),
+            Err(err) if builder.stderr_fallback => {
+                // 標準エラー出力に記録することを、エラーを報告する関数に通知してから切り替える
+                let err = AppenderError::CreateWriter {
+                    path: path.clone(),
+                    source: err,
+                };
+                match on_error.as_mut() {
+                    Some(on_error) => on_error(&err),
+                    None => eprintln!("{}, writing to stderr", err),
+                }
+                (Box::new(io::stderr()) as LogFile, 0, true)
+            }
+            Err(err) => return Err(AppenderError::CreateWriter { path, source: err }.into()),
+        };
+        let writer = RwLock::new(Mutex::new(BufWriter::with_capacity(
+            builder.buffer_capacity,
+            file,
+        )));
+
+        let manifest_name = builder.manifest.then(|| {
+            format!(
+                "{}{}",
+                filename_head(&filename_prefix, builder.separator),
+                MANIFEST_NAME
+            )
+        });
+        #[cfg(feature = "process_lock")]
+        let lock_path = builder
+            .process_lock
+            .then(|| directory.join(format!("{}.lock", filename_prefix)));
+        let inner = Inner {
+            next_date,
+            current_date,
+            segment: AtomicUsize::new(segment),
+            rollover_pending: AtomicBool::new(false),
+            fallback: AtomicBool::new(fallback),
+            next_existence_check: AtomicUsize::new(0),
+            // 再起動した後も最大サイズを超えないように、既に存在するファイルのサイズから数える
+            bytes_written: AtomicU64::new(len),
+            total_bytes_written: AtomicU64::new(0),
+            rollover_count: AtomicU64::new(0),
+            rolled_over: AtomicBool::new(false),
+            files_deleted: AtomicU64::new(0),
+            retention: Mutex::new(builder.retention),
+            retention_order: builder.retention_order,
+            removal_strategy,
+            dry_run: builder.dry_run,
+            max_age_days: builder.max_age_days,
+            max_bytes: AtomicU64::new(builder.max_bytes.unwrap_or(NO_MAX_BYTES)),
+            min_free_bytes: builder.min_free_bytes,
+            max_deletions_per_pass: builder.max_deletions_per_pass,
+            keep_minimum: builder.keep_minimum,
+            protected: builder.protected,
+            size_suffix: builder.size_suffix,
+            unique_segments: builder.unique_segments,
+            open_mode: builder.open_mode,
+            buffer_capacity: builder.buffer_capacity,
+            flush_on_write: builder.flush_on_write,
+            fsync_on_flush: builder.fsync_on_flush,
+            atomic_records: builder.atomic_records,
+            whole_records: builder.whole_records,
+            directory,
+            archive_directory,
+            log_file_regex,
+            date_format,
+            filename_prefix,
+            separator: builder.separator,
+            extension: builder.extension,
+            filename_predicate: builder.filename_predicate,
+            file_namer,
+            header: builder.header,
+            footer: builder.footer,
+            record_terminator: builder
+                .record_terminator
+                .filter(|terminator| !terminator.is_empty()),
+            timestamp_prefix: builder.timestamp_prefix,
+            compression: builder.compression,
+            current_link: builder.current_link,
+            part_files: builder.part_files,
+            manifest_name,
+            first_record: AtomicI64::new(NO_RECORD),
+            last_record: AtomicI64::new(NO_RECORD),
+            rotation: builder.rotation,
+            time_zone: builder.time_zone,
+            clock: builder.clock,
+            file_system: builder.file_system,
+            open_attempts: builder.open_attempts,
+            on_rollover: Mutex::new(builder.on_rollover),
+            on_planned_removal: Mutex::new(builder.on_planned_removal),
+            on_error: Mutex::new(on_error),
+            #[cfg(feature = "process_lock")]
+            lock_path,
+            #[cfg(feature = "encoding")]
+            encoding: builder.encoding,
+            _directory_owner: directory_owner,
+        };
+        let lock = inner.lock_processes();
+        inner.update_current_link(&now, segment);
+        // 前回停止するまでにローテーションしたファイルが残っている場合に備えて、構築したときにも移動する
+        inner.archive_stale_files();
+        // 長期間停止していた場合に備えて、構築したときにも古いファイルを削除する
+        inner.remove_old_files();
+        drop(lock);
+
+        Ok((inner, writer))
+    }
+
+    /// 時計から、設定されたタイムゾーンにおける現在日時を取得して返却する。
+    ///
+    /// # 戻り値
+    ///
+    /// ローテーションする間隔で切り捨てた現在日時。
+    fn now(&self) -> OffsetDateTime {
+        self.rotation
+            .truncate(self.time_zone.now(self.clock.as_ref()))
+    }
+
+    /// 次にファイルをローテーションする日時までの時間を返却する。
+    ///
+    /// # 戻り値
+    ///
+    /// 次にファイルをローテーションする日時までの時間。既に過ぎている場合は0。
+    fn until_next_date(&self) -> StdDuration {
+        let now = self.time_zone.now(self.clock.as_ref());
+
+        (self.next_rollover() - now)
+            .try_into()
+            .unwrap_or(StdDuration::ZERO)
+    }
+
+    /// 日時の境界を過ぎている場合は、書き込みを待たずにファイルをローテーションする。
+    ///
+    /// 書き込むときと同様に、他のスレッドが先にローテーションした場合はローテーションしない。
+    ///
+    /// # 引数
+    ///
+    /// - writer: ログを記録しているファイルへのライターのロック。
+    fn roll_on_schedule(&self, writer: &RwLock<LogWriter>) {
+        let now = self.now();
+        let next_date = self.next_date.load(Ordering::Acquire);
+        if next_date <= now.unix_timestamp() as usize && self.advance_date(now, next_date) {
+            let mut writer = writer.write();
+            let writer = writer.get_mut().unwrap_or_else(PoisonError::into_inner);
+            self.refresh_writer(&now, 0, writer);
+        }
+    }
+
+    /// ファイルに書き込んだバイト数を記録する。
+    ///
+    /// # 引数
+    ///
+    /// - written: 書き込んだバイト数。
+    fn record_written(&self, written: usize) {
+        self.bytes_written
+            .fetch_add(written as u64, Ordering::AcqRel);
+        self.total_bytes_written
+            .fetch_add(written as u64, Ordering::Relaxed);
+        // マニフェストに記録しない場合は、書き込むたびに時計を読まない
+        if self.manifest_name.is_some() {
+            let now = self.time_zone.now(self.clock.as_ref()).unix_timestamp();
+            let _ = self.first_record.compare_exchange(
+                NO_RECORD,
+                now,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            );
+            self.last_record.store(now, Ordering::Release);
+        }
+    }
+
+    /// ファイルをローテーションする必要があるか確認する。
+    ///
+    /// # 戻り値
+    ///
+    /// ファイルをローテーションする必要がある場合は、ローテーションする理由。
+    /// ローテーションする必要がない場合はNone。
+    fn should_rollover(&self) -> Option<Rollover> {
+        let next_date = self.next_date.load(Ordering::Acquire);
+        let now = self.now();
+
+        if next_date <= now.unix_timestamp() as usize {
+            Some(Rollover::Date(next_date))
+        } else if self.fallback.load(Ordering::Acquire) {
+            // 書き込むたびにファイルの作成を試みないように、確認する時期にのみ再試行する
+            self.should_check_existence().then_some(Rollover::Fallback)
+        } else if self.rollover_pending.load(Ordering::Acquire) {
+            Some(Rollover::Pending)
+        } else if self.exceeds_max_bytes() {
+            Some(Rollover::Size)
+        } else if self.should_check_existence() && self.is_current_file_missing() {
+            Some(Rollover::Missing)
+        } else {
+            None
+        }
+    }
+
+    /// ログを記録しているファイルが存在するか確認する時期であるか確認する。
+    ///
+    /// 書き込むたびにファイルシステムに問い合わせないように、`EXISTENCE_CHECK_INTERVAL`秒ごとに
+    /// 確認する。
+    ///
+    /// # 戻り値
+    ///
+    /// 確認する時期の場合はtrue。
+    fn should_check_existence(&self) -> bool {
+        let now = self.clock.now_in(self.time_zone).unix_timestamp() as usize;
+        let next_check = self.next_existence_check.load(Ordering::Acquire);
+        if now < next_check {
+            return false;
+        }
+
+        // 他のスレッドが先に更新した場合は、そのスレッドが確認する
+        self.next_existence_check
+            .compare_exchange(
+                next_check,
+                now + EXISTENCE_CHECK_INTERVAL,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            )
+            .is_ok()
+    }
+
+    /// ログを記録しているファイルが、ディレクトリやファイルの削除によって存在しなくなったか確認する。
+    ///
+    /// # 戻り値
+    ///
+    /// ファイルが存在しない場合はtrue。ファイルが存在する場合、または確認できなかった場合はfalse。
+    fn is_current_file_missing(&self) -> bool {
+        match self.file_system.file_len(&self.current_path()) {
+            Err(err) => err.kind() == io::ErrorKind::NotFound,
+            Ok(_) => false,
+        }
+    }
+
+    /// ファイルの最大サイズを返却する。
+    ///
+    /// # 戻り値
+    ///
+    /// ファイルの最大サイズ(バイト)。最大サイズが設定されていない場合はNone。
+    fn max_bytes(&self) -> Option<u64> {
+        match self.max_bytes.load(Ordering::Acquire) {
+            NO_MAX_BYTES => None,
+            max_bytes => Some(max_bytes),
+        }
+    }
+
+    /// 現在ログを記録しているファイルのサイズが、最大サイズに達しているか確認する。
+    ///
+    /// # 戻り値
+    ///
+    /// 最大サイズに達している場合はtrue。最大サイズに達していない場合、または最大サイズが
+    /// 設定されていない場合はfalse。
+    fn exceeds_max_bytes(&self) -> bool {
+        match self.max_bytes() {
+            Some(max_bytes) => max_bytes <= self.bytes_written.load(Ordering::Acquire),
+            None => false,
+        }
+    }
+
+    /// レコードを書き込むと、現在ログを記録しているファイルのサイズが最大サイズを超えるか確認する。
+    ///
+    /// # 引数
+    ///
+    /// - len: 書き込むレコードのサイズ(バイト)。
+    ///
+    /// # 戻り値
+    ///
+    /// ローテーションをレコードの間でのみ行う設定で、空でないファイルにレコードを書き込むと
+    /// 最大サイズを超える場合はtrue。そうでない場合はfalse。
+    fn would_split_record(&self, len: usize) -> bool {
+        if !self.whole_records {
+            return false;
+        }
+
+        match self.max_bytes() {
+            Some(max_bytes) => {
+                let written = self.bytes_written.load(Ordering::Acquire);
+                0 < written && max_bytes < written + len as u64
+            }
+            None => false,
+        }
+    }
+
+    /// レコードが終端で終わっていない場合に、末尾に終端を付けたレコードを返却する。
+    ///
+    /// # 引数
+    ///
+    /// - record: 書き込むレコード。
+    ///
+    /// # 戻り値
+    ///
+    /// 終端を付けたレコード。終端を設定していない場合、レコードが空の場合、または既に終端で
+    /// 終わっている場合は、元のレコード。
+    fn terminate<'b>(&self, record: &'b [u8]) -> Cow<'b, [u8]> {
+        match &self.record_terminator {
+            Some(terminator) if !record.is_empty() && !record.ends_with(terminator) => {
+                Cow::Owned([record, terminator].concat())
+            }
+            _ => Cow::Borrowed(record),
+        }
+    }
+
+    /// レコードに必要に応じて終端と時刻を付けて、ファイルの文字エンコーディングに変換する。
+    ///
+    /// # 引数
+    ///
+    /// - record: `write`に渡されたレコード。
+    ///
+    /// # 戻り値
+    ///
+    /// ファイルに書き込むレコード。終端や時刻を付けず、変換もしない場合は、元のレコード。
+    fn prepare_record<'b>(&self, record: &'b [u8]) -> Cow<'b, [u8]> {
+        let mut record = self.terminate(record);
+        if self.timestamp_prefix && !record.is_empty() {
+            // 時刻を書式化できない日時は`time`の範囲外であり、時計から取得することはない
+            let timestamp = self
+                .time_zone
+                .now(self.clock.as_ref())
+                .format(&Rfc3339)
+                .unwrap_or_default();
+            record = Cow::Owned([timestamp.as_bytes(), b" ", &record].concat());
+        }
+        #[cfg(feature = "encoding")]
+        if let Some(encoding) = self.encoding {
+            return Cow::Owned(encode_record(encoding, &record).into_owned());
+        }
+
+        record
+    }
+
+    /// レコードを書き込むと最大サイズを超える場合に、書き込む前にファイルをローテーションする。
+    ///
+    /// # 引数
+    ///
+    /// - writer: ログを記録しているファイルへのライターのロック。
+    /// - len: 書き込むレコードのサイズ(バイト)。
+    fn roll_before_record(&self, writer: &RwLock<LogWriter>, len: usize) {
+        if !self.would_split_record(len) {
+            return;
+        }
+
+        let mut writer = writer.write();
+        // 書き込みロックを獲得するまでに、他のスレッドがローテーションしている可能性があるため、
+        // 再度確認する
+        if self.would_split_record(len) {
+            let writer = writer.get_mut().unwrap_or_else(PoisonError::into_inner);
+            self.roll_by_size(writer);
+        }
+    }
+
+    /// 次にファイルをローテーションする日時を返却する。
+    ///
+    /// ファイルの日付はタイムゾーンにおける日時をUTCとして保持しているため、タイムゾーンの
+    /// オフセットに置き換える。
+    ///
+    /// # 戻り値
+    ///
+    /// 次にファイルをローテーションする、タイムゾーンにおける日時。
+    fn next_rollover(&self) -> OffsetDateTime {
+        let next_date = self.next_date.load(Ordering::Acquire) as i64;
+        let next_date = OffsetDateTime::from_unix_timestamp(next_date)
+            .expect("Invalid timestamp; this is a bug in restricted-rolling-file-appender");
+
+        next_date.replace_offset(self.time_zone.offset_at(next_date))
+    }
+
+    /// 現在ログを記録しているファイルの日付を返却する。
+    ///
+    /// # 戻り値
+    ///
+    /// 現在ログを記録しているファイルの日付(ローテーションする間隔で切り捨てた`OffsetDateTime`)。
+    fn current_date(&self) -> OffsetDateTime {
+        let current_date = self.current_date.load(Ordering::Acquire) as i64;
+
+        OffsetDateTime::from_unix_timestamp(current_date)
+            .expect("Invalid timestamp; this is a bug in restricted-rolling-file-appender")
+    }
+
+    /// ログファイルのパスを返却する。
+    ///
+    /// # 引数
+    ///
+    /// - date: ログファイルの日付。
+    /// - index: 同じ日付のファイルの連番。接尾辞がローテーションした時刻の場合は、その時刻を示す
+    ///   Unixタイムスタンプ。0の場合は、接尾辞を付けない。
+    ///
+    /// # 戻り値
+    ///
+    /// ログファイルのパス。
+    fn log_path(&self, date: &OffsetDateTime, index: usize) -> PathBuf {
+        self.directory
+            .join(self.file_namer.segment_name(date, index))
+    }
+
+    /// ログを記録している間のファイルのパスを返却する。
+    ///
+    /// # 引数
+    ///
+    /// - date: ログファイルの日付。
+    /// - index: 同じ日付のファイルの連番。
+    ///
+    /// # 戻り値
+    ///
+    /// `.part`ファイルに書き込む設定の場合は、ログファイルのパスに`.part`を付けたパス。そうでない
+    /// 場合は、ログファイルのパス。
+    fn active_path(&self, date: &OffsetDateTime, index: usize) -> PathBuf {
+        let mut path = self.log_path(date, index);
+        if self.part_files {
+            path.as_mut_os_string().push(format!(".{}", PART_EXTENSION));
+        }
+
+        path
+    }
+
+    /// 次にファイルをローテーションする日付を示すUnixタイムスタンプを設定する。
+    ///
+    /// 現在持っている次にファイルをローテーションする日付を示すUnixタイムスタンプが、
+    /// 引数`current`と等しい場合、次にファイルをローテーションする時を示す
+    /// Unixタイムスタンプを設定できる。この場合、この関数は`true`を返却する。
+    /// そのUnixタイムスタンプを設定できなかった場合は、falseを返却する。
+    ///
+    /// # 引数
+    ///
+    /// - now: 新しくログを記録するファイルの日付。この日付にローテーションする間隔を加えた日付が、
+    ///   次にファイルをローテーションする日付になる。
+    /// - current: 現在設定されていると考えられるファイルをローテーションする日付を示す
+    ///   Unixタイムスタンプ。
+    ///
+    /// # 戻り値
+    ///
+    /// 設定できた場合はtrue。設定に失敗した場合はfalse。
+    fn advance_date(&self, now: OffsetDateTime, current: usize) -> bool {
+        let next_date = (now + self.rotation.duration()).unix_timestamp() as usize;
+
+        self.next_date
+            .compare_exchange(current, next_date, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    }
+
+    /// ファイルのサイズが最大サイズに達したため、同じ日付の次の連番、または現在の時刻を接尾辞と
+    /// するファイルに切り替える。
+    ///
+    /// # 引数
+    ///
+    /// - writer: ログを記録しているファイルへのライター。
+    fn roll_by_size(&self, writer: &mut BufWriter<LogFile>) {
+        let date = self.current_date();
+        let segment = self.segment.load(Ordering::Acquire);
+        let index = match self.size_suffix {
+            SizeSuffix::Counter => segment + 1,
+            SizeSuffix::Time => {
+                let index = time_suffix_index(self.time_zone, self.clock.as_ref());
+                // 1秒以内にローテーションした場合や時計が戻った場合も、直前のファイルより後の時刻にする
+                match self.unique_segments {
+                    true => index.max(segment + 1),
+                    false => index,
+                }
+            }
+        };
+        self.refresh_writer(&date, index, writer);
+    }
+
+    /// 削除されたログファイルを作成し直す。
+    ///
+    /// ディレクトリが削除されている場合は、ディレクトリも作成し直す。作成し直したファイルには、
+    /// 現在の日時と連番を引き継ぐ。
+    ///
+    /// # 引数
+    ///
+    /// - writer: ログを記録しているファイルへのライター。
+    fn recreate_writer(&self, writer: &mut BufWriter<LogFile>) {
+        let date = self.current_date();
+        let index = self.segment.load(Ordering::Acquire);
+        self.refresh_writer(&date, index, writer);
+    }
+
+    /// 前回失敗したローテーションを再試行する。
+    ///
+    /// 現在ログを記録しているファイルと日時が異なる場合は、現在の日時の最初のファイルに、
+    /// 日時が同じ場合は、同じ日時の次の連番のファイルにローテーションする。
+    ///
+    /// # 引数
+    ///
+    /// - writer: ログを記録しているファイルへのライター。
+    fn retry_rollover(&self, writer: &mut BufWriter<LogFile>) {
+        let now = self.now();
+        if now == self.current_date() {
+            self.roll_by_size(writer);
+        } else {
+            self.refresh_writer(&now, 0, writer);
+        }
+    }
+
+    /// ログファイルを更新する。
+    ///
+    /// 新しいファイルを作成できなかった場合は、エラーを報告して、前のファイルにログを記録し続ける。
+    /// その場合、次に書き込むときにローテーションを再試行する。
+    ///
+    /// # 引数
+    ///
+    /// - date: ファイルの日付。
+    /// - index: 同じ日付のファイルの連番。
+    /// - writer: ログを記録しているファイルへのライター。
+    fn refresh_writer(&self, date: &OffsetDateTime, index: usize, writer: &mut BufWriter<LogFile>) {
+        // バッファに残っている内容を前のファイルに書き込み、ファイルシステムが返却したライターも
+        // フラッシュする。ライターを入れ替えた後に、前のファイルの内容が書き込まれないようにする
+        let previous_date = self.current_date();
+        let previous_index = self.segment.load(Ordering::Acquire);
+        let previous_path = self.active_path(&previous_date, previous_index);
+        if let Err(err) = writer.flush() {
+            self.report_error(AppenderError::Flush {
+                path: previous_path.clone(),
+                source: err,
+            });
+        }
+        let path = self.active_path(date, index);
+        // 他のプロセスが既にローテーションした場合は、そのプロセスが作成したファイルを開くだけにする
+        let lock = self.lock_processes();
+        let rolled_by_other =
+            lock.is_some() && previous_path != path && self.file_system.file_len(&path).is_ok();
+        // 現在ログを記録しているファイルと同じファイルを開く場合は、記録したログを消さないように追記する
+        let open_mode = match previous_path == path || rolled_by_other {
+            true => OpenMode::Append,
+            false => self.open_mode,
+        };
+        let result = create_writer(
+            self.file_system.as_ref(),
+            &path,
+            open_mode,
+            self.header.as_ref(),
+            date,
+            self.open_attempts,
+        );
+        // 標準エラー出力に記録していた場合は、閉じるファイルが存在しない
+        let fallback = self.fallback.load(Ordering::Acquire);
+        match result {
+            Ok((new_file, len)) => {
+                // 前のファイルを閉じる前に、フッターを書き込む
+                if previous_path != path && !rolled_by_other {
+                    self.write_footer(writer);
+                    if let Err(err) = writer.flush() {
+                        self.report_error(AppenderError::Flush {
+                            path: previous_path.clone(),
+                            source: err,
+                        });
+                    }
+                }
+                *writer = BufWriter::with_capacity(self.buffer_capacity, new_file);
+                self.current_date
+                    .store(date.unix_timestamp() as usize, Ordering::Release);
+                self.segment.store(index, Ordering::Release);
+                self.bytes_written.store(len, Ordering::Release);
+                self.rollover_pending.store(false, Ordering::Release);
+                self.fallback.store(false, Ordering::Release);
+                self.update_current_link(date, index);
+                if previous_path != path {
+                    self.rollover_count.fetch_add(1, Ordering::Relaxed);
+                    self.rolled_over.store(true, Ordering::Release);
+                    let closed = ClosedFile {
+                        first_record: self.first_record.swap(NO_RECORD, Ordering::AcqRel),
+                        last_record: self.last_record.swap(NO_RECORD, Ordering::AcqRel),
+                        reason: match previous_date == *date {
+                            true => "size",
+                            false => "date",
+                        },
+                    };
+                    if !rolled_by_other && !fallback {
+                        let previous_path = match self.part_files {
+                            true => self.complete_part_file(
+                                previous_path,
+                                self.log_path(&previous_date, previous_index),
+                            ),
+                            false => previous_path,
+                        };
+                        self.finalize_file(previous_path, &closed);
+                    }
+                }
+            }
+            Err(err) => {
+                // 標準エラー出力に記録している場合は、確認する時期に再試行する
+                if !fallback {
+                    self.rollover_pending.store(true, Ordering::Release);
+                }
+                self.report_error(AppenderError::CreateWriter { path, source: err });
+            }
+        }
+        // 古いログファイルを削除
+        if !rolled_by_other {
+            self.remove_old_files();
+        }
+    }
+
+    /// フッターが設定されている場合は、ログを記録しているファイルにフッターを書き込む。
+    ///
+    /// 書き込めなかった場合は、エラーを報告する。
+    ///
+    /// # 引数
+    ///
+    /// - writer: ログを記録しているファイルへのライター。
+    fn write_footer(&self, writer: &mut BufWriter<LogFile>) {
+        if self.fallback.load(Ordering::Acquire) {
+            return;
+        }
+        if let Some(footer) = &self.footer {
+            if let Err(err) = writer.write_all(footer) {
+                self.report_error(AppenderError::Other {
+                    message: "Couldn't write footer",
+                    source: err,
+                });
+            }
+        }
+    }
+
+    /// 他のプロセスと、ローテーションと古いファイルの削除を調整するロックを獲得する。
+    ///
+    /// ロックを獲得するまで待機する。他のプロセスと調整しない設定の場合は何もしない。
+    /// ロックを獲得できなかった場合は、エラーを報告する。
+    ///
+    /// # 戻り値
+    ///
+    /// ロックを獲得した場合は、ロックしたファイル。ファイルを閉じたときにロックを解放する。
+    /// ロックを獲得しなかった場合はNone。
+    fn lock_processes(&self) -> Option<std::fs::File> {
+        #[cfg(feature = "process_lock")]
+        if let Some(lock_path) = &self.lock_path {
+            let result = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(lock_path)
+                .and_then(|file| file.lock().map(|_| file));
+            return match result {
+                Ok(file) => Some(file),
+                Err(err) => {
+                    self.report_error(AppenderError::Other {
+                        message: "Couldn't lock log directory",
+                        source: err,
+                    });
+                    None
+                }
+            };
+        }
+
+        None
+    }
+
+    /// アペンダーの内部で発生したエラーを報告する。
+    ///
+    /// エラーが発生したときに呼び出す関数が設定されている場合は、その関数にエラーを渡す。
+    /// 設定されていない場合は、エラーを標準エラー出力に出力する。
+    ///
+    /// # 引数
+    ///
+    /// - err: エラー。
+    fn report_error(&self, err: AppenderError) {
+        let mut on_error = self.on_error.lock().unwrap_or_else(PoisonError::into_inner);
+        match on_error.as_mut() {
+            Some(on_error) => on_error(&err),
+            None => eprintln!("{}", err),
+        }
+    }
+
+    /// 現在ログを記録しているファイルを指すシンボリックリンクを更新する。
+    ///
+    /// シンボリックリンクを作成する設定でない場合は何もしない。
+    ///
+    /// # 引数
+    ///
+    /// - date: 現在ログを記録しているファイルの日付。
+    /// - index: 現在ログを記録しているファイルの同じ日付のファイルの連番。
+    fn update_current_link(&self, date: &OffsetDateTime, index: usize) {
+        if !self.current_link {
+            return;
+        }
+
+        let link_name = format!(
+            "{}current.{}",
+            filename_head(&self.filename_prefix, self.separator),
+            self.extension
+        );
+        let target = self.active_path(date, index);
+        let target = target
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        if let Err(err) = self
+            .file_system
+            .replace_symlink(&self.directory, &link_name, &target)
+        {
+            self.report_error(AppenderError::Other {
+                message: "Couldn't update link to current log file",
+                source: err,
+            });
+        }
+    }
+
+    /// ローテーションによって閉じた`.part`ファイルの名前を、ログファイルの名前に変更する。
+    ///
+    /// 変更後の名前のファイルが存在する場合は、上書きせずに追記する。名前を変更できなかった
+    /// 場合は、エラーを報告する。
+    ///
+    /// # 引数
+    ///
+    /// - part_path: ローテーションによって閉じた`.part`ファイルのパス。
+    /// - path: 名前を変更した後のログファイルのパス。
+    ///
+    /// # 戻り値
+    ///
+    /// 名前を変更できた場合は変更した後のパス。変更できなかった場合は`.part`ファイルのパス。
+    fn complete_part_file(&self, part_path: PathBuf, path: PathBuf) -> PathBuf {
+        match move_file(self.file_system.as_ref(), &part_path, &path) {
+            Ok(()) => path,
+            Err(err) => {
+                self.report_error(AppenderError::Other {
+                    message: "Couldn't rename previous part file",
+                    source: err,
+                });
+                part_path
+            }
+        }
+    }
+
+    /// ローテーションによって閉じたファイルを圧縮して、ファイルを移動するディレクトリに移動した後、
+    /// ローテーションしたときに呼び出す関数を呼び出す。
+    ///
+    /// # 引数
+    ///
+    /// - path: ローテーションによって閉じたファイルのパス。
+    fn finalize_file(&self, mut path: PathBuf, closed: &ClosedFile) {
+        if self.compression != Compression::None {
+            match compress_file(self.file_system.as_ref(), &path, self.compression) {
+                Ok(compressed_path) => path = compressed_path,
+                Err(err) => self.report_error(AppenderError::Other {
+                    message: "Couldn't compress previous log file",
+                    source: err,
+                }),
+            }
+        }
+        if let Some(archive_directory) = &self.archive_directory {
+            let archived_path = archive_directory.join(path.file_name().unwrap_or_default());
+            match move_file(self.file_system.as_ref(), &path, &archived_path) {
+                Ok(()) => path = archived_path,
+                Err(err) => self.report_error(AppenderError::Other {
+                    message: "Couldn't move previous log file to archive",
+                    source: err,
+                }),
+            }
+        }
+        self.append_manifest(&path, closed);
+
+        let mut on_rollover = self
+            .on_rollover
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(on_rollover) = on_rollover.as_mut() {
+            on_rollover(&path);
+        }
+    }
+
+    /// マニフェストに、閉じたファイルを記録する。
+    ///
+    /// マニフェストに記録しない設定の場合は何もしない。記録できなかった場合は、エラーを報告する。
+    ///
+    /// # 引数
+    ///
+    /// - path: 圧縮や移動をした後の、閉じたファイルのパス。
+    /// - closed: 閉じたファイルにレコードを書き込んだ時刻と、ローテーションした理由。
+    fn append_manifest(&self, path: &Path, closed: &ClosedFile) {
+        let manifest_name = match &self.manifest_name {
+            Some(manifest_name) => manifest_name,
+            None => return,
+        };
+
+        let timestamp = |timestamp: i64| {
+            let date_time = OffsetDateTime::from_unix_timestamp(timestamp)
+                .ok()
+                .filter(|_| timestamp != NO_RECORD)?;
+            date_time
+                .to_offset(self.time_zone.offset_at(date_time))
+                .format(&Rfc3339)
+                .ok()
+                .map(|timestamp| json_string(&timestamp))
+        };
+        let entry = format!(
+            "{{\"file\":{},\"bytes\":{},\"first_record\":{},\"last_record\":{},\"reason\":\"{}\"}}\n",
+            json_string(&path.file_name().unwrap_or_default().to_string_lossy()),
+            self.file_system.file_len(path).unwrap_or(0),
+            timestamp(closed.first_record).unwrap_or_else(|| "null".to_owned()),
+            timestamp(closed.last_record).unwrap_or_else(|| "null".to_owned()),
+            closed.reason,
+        );
+        let result = self
+            .file_system
+            .append(&self.directory.join(manifest_name))
+            .and_then(|mut manifest| {
+                manifest.write_all(entry.as_bytes())?;
+                manifest.flush()
+            });
+        if let Err(err) = result {
+            self.report_error(AppenderError::Other {
+                message: "Couldn't write manifest",
+                source: err,
+            });
+        }
+    }
+
+    /// 古いファイルを削除する。
+    ///
+    /// 現在ログを記録しているファイルは、削除する対象に含めない。ファイルを検索できなかった場合や、
+    /// ファイルを削除できなかった場合は、エラーを報告する。削除する代わりに報告する設定の場合は、
+    /// 削除するファイルを`on_planned_removal`に設定した関数に渡して、ファイルを削除しない。
+    fn remove_old_files(&self) {
+        self.prune_old_files(|err| self.report_error(err));
+    }
+
+    /// 古いファイルを削除して、発生したエラーを引数に関数を呼び出す。
+    ///
+    /// 削除する代わりに報告する設定の場合は、削除するファイルを`on_planned_removal`に設定した関数に
+    /// 渡して、ファイルを削除しない。関数が設定されていない場合は、標準エラー出力に出力する。
+    /// 削除するファイルはエラーではないため、`on_error`では報告しない。
+    ///
+    /// # 引数
+    ///
+    /// - on_error: ファイルを検索できなかったとき、またはファイルを削除できなかったときに、
+    ///   エラーを引数に呼び出す関数。
+    fn prune_old_files(&self, mut on_error: impl FnMut(AppenderError)) {
+        // 同じディレクトリのファイルを削除する他のアペンダーと、ファイルの検索や削除が交互に
+        // 行われないように、プロセス内で直列化する
+        let lock = retention_lock(self.retained_directory());
+        let _lock = lock.lock().unwrap_or_else(PoisonError::into_inner);
+        if self.dry_run {
+            match self.pruner().plan() {
+                Ok(planned) => self.report_planned_removals(&planned),
+                Err(err) => on_error(AppenderError::ReadDir {
+                    path: self.retained_directory().to_owned(),
+                    source: err,
+                }),
+            }
+            return;
+        }
+
+        match self.pruner().prune(&mut on_error) {
+            Ok(removed) => {
+                self.files_deleted
+                    .fetch_add(removed.len() as u64, Ordering::Relaxed);
+            }
+            Err(err) => on_error(AppenderError::ReadDir {
+                path: self.retained_directory().to_owned(),
+                source: err,
+            }),
+        }
+    }
+
+    /// 古いファイルを削除する代わりに、削除するファイルを報告する。
+    ///
+    /// 報告する関数が設定されていない場合は、標準エラー出力に出力する。
+    ///
+    /// # 引数
+    ///
+    /// - planned: 削除するファイルのパス。
+    fn report_planned_removals(&self, planned: &[PathBuf]) {
+        let mut on_planned_removal = self
+            .on_planned_removal
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for path in planned {
+            match on_planned_removal.as_mut() {
+                Some(on_planned_removal) => on_planned_removal(path),
+                None => eprintln!(
+                    "Dry run of log file retention: {} would be removed",
+                    path.display()
+                ),
+            }
+        }
+    }
+
+    /// 設定された基準で古いファイルを削除する`Pruner`を作成する。
+    ///
+    /// # 戻り値
+    ///
+    /// 現在ログを記録しているファイルを削除しない`Pruner`インスタンス。
+    fn pruner(&self) -> Pruner<'_> {
+        Pruner {
+            file_system: self.file_system.as_ref(),
+            directory: self.retained_directory(),
+            log_file_regex: &self.log_file_regex,
+            date_format: &self.date_format,
+            filename_predicate: self.filename_predicate.as_ref(),
+            file_namer: self.file_namer.as_ref(),
+            retention: self.retention(),
+            retention_order: self.retention_order,
+            removal_strategy: &self.removal_strategy,
+            max_age_days: self.max_age_days,
+            min_free_bytes: self.min_free_bytes,
+            max_deletions: self.max_deletions_per_pass,
+            keep_minimum: self.keep_minimum,
+            protected_files: &self.protected,
+            manifest_name: self.manifest_name.as_deref(),
+            today: self.now().date(),
+            current: Some(self.current_path()),
+        }
+    }
+
+    /// 現在の保存する基準を返却する。
+    ///
+    /// # 戻り値
+    ///
+    /// 保存する基準。
+    fn retention(&self) -> Retention {
+        *self
+            .retention
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// 古いファイルを削除する対象のディレクトリを返却する。
+    ///
+    /// # 戻り値
+    ///
+    /// ファイルを移動するディレクトリが設定されている場合はそのディレクトリ。設定されていない場合は
+    /// ファイルを作成するディレクトリ。
+    fn retained_directory(&self) -> &Path {
+        self.archive_directory.as_deref().unwrap_or(&self.directory)
+    }
+
+    /// 現在ログを記録しているファイルのパスを返却する。
+    ///
+    /// # 戻り値
+    ///
+    /// 現在ログを記録しているファイルのパス。
+    fn current_path(&self) -> PathBuf {
+        self.active_path(&self.current_date(), self.segment.load(Ordering::Acquire))
+    }
+
+    /// フラッシュするたびに同期するように設定されている場合、現在ログを記録しているファイルの内容を
+    /// ストレージに同期する。
+    ///
+    /// ローテーションによってファイルが切り替わらないように、呼び出し元はライターのロックを
+    /// 獲得している必要がある。標準エラー出力に書き込んでいる場合は同期しない。
+    ///
+    /// # 戻り値
+    ///
+    /// 同期できなかった場合は、そのエラー。
+    fn sync_current_file(&self) -> io::Result<()> {
+        if !self.fsync_on_flush || self.fallback.load(Ordering::Acquire) {
+            return Ok(());
+        }
+
+        self.file_system.sync_all(&self.current_path())
+    }
+
+    /// ファイルを作成するディレクトリに残っている、現在ログを記録しているファイル以外のログファイルを、
+    /// ファイルを移動するディレクトリに移動する。
+    ///
+    /// ファイルを移動するディレクトリが設定されていない場合は何もしない。ファイルを検索できなかった
+    /// 場合や、ファイルを移動できなかった場合は、エラーを報告する。
+    fn archive_stale_files(&self) {
+        let archive_directory = match &self.archive_directory {
+            Some(archive_directory) => archive_directory,
+            None => return,
+        };
+
+        let pruner = Pruner {
+            directory: &self.directory,
+            ..self.pruner()
+        };
+        let (targets, protected) = match pruner.log_files() {
+            Ok(log_files) => log_files,
+            Err(err) => {
+                return self.report_error(AppenderError::ReadDir {
+                    path: self.directory.clone(),
+                    source: err,
+                })
+            }
+        };
+        for target in &targets[..targets.len() - protected] {
+            let result = move_file(
+                self.file_system.as_ref(),
+                &self.directory.join(target),
+                &archive_directory.join(target),
+            );
+            if let Err(err) = result {
+                self.report_error(AppenderError::Other {
+                    message: "Couldn't move log file to archive",
+                    source: err,
+                });
+            }
+        }
+    }
+}
+
+/// 古いログファイルを削除する基準。
+struct Pruner<'a> {
+    /// ログファイルを検索するファイルシステム。
+    file_system: &'a dyn FileSystem,
+    /// ログファイルを検索するディレクトリ。
+    directory: &'a Path,
+    /// ログファイル名に一致する正規表現。
+    log_file_regex: &'a Regex,
+    /// ログファイル名に含まれる日時の書式。
+    date_format: &'a DateFormat,
+    /// ログファイルであるかを判定する関数。
+    filename_predicate: Option<&'a FilenamePredicate>,
+    /// ログファイルに名前を付ける規則。
+    file_namer: &'a dyn FileNamer,
+    /// 保存するファイルの基準。
+    retention: Retention,
+    /// ログファイルを古い順に並べる方法。
+    retention_order: RetentionOrder,
+    /// 古いログファイルを取り除く方法。
+    removal_strategy: &'a RemovalStrategy,
+    /// ファイルを保存する日数。
+    max_age_days: Option<u32>,
+    /// 確保する空き容量。
+    min_free_bytes: Option<u64>,
+    /// 1回の削除で削除するファイルの最大数。
+    max_deletions: Option<usize>,
+    /// 現在ログを記録しているファイルを除いて、削除せずに残すファイルの最小数。
+    keep_minimum: usize,
+    /// 削除しないファイルの名前。
+    protected_files: &'a [String],
+    /// 閉じたファイルを記録するマニフェストのファイル名。
+    manifest_name: Option<&'a str>,
+    /// 今日の日付。
+    today: Date,
+    /// 現在ログを記録しているファイルのパス。
+    ///
+    /// 設定されている場合、最も新しいファイルとして扱い、並べ替えた順序に関わらず削除しない。
+    current: Option<PathBuf>,
+}
+
+impl Pruner<'_> {
+    /// ディレクトリに存在するログファイルを、古い順に並べて返却する。
+    ///
+    /// 取得したログファイルのファイル名を、ファイル名に含まれる日付と連番の昇順で並べ替える。
+    /// 現在ログを記録しているファイルは、日時に関わらず最後に移動する。
+    ///
+    /// # 戻り値
+    ///
+    /// 古い順に並べたログファイル名と、末尾に移動した現在ログを記録しているファイルの数(0または1)の
+    /// タプル。ディレクトリを読み込めなかった場合は、そのエラー。
+    fn log_files(&self) -> io::Result<(Vec<String>, usize)> {
+        let mut targets: Vec<String> = self
+            .file_system
+            .read_dir(self.directory)?
+            .iter()
+            .filter_map(|name| self.owned_log_file(name))
+            .collect();
+
+        match self.retention_order {
+            // 日時を解析できないファイルは、ファイル名の順に並べる
+            RetentionOrder::ByName => targets.sort_by_cached_key(|name| {
+                (
+                    log_file_sort_key(name, self.log_file_regex, self.date_format),
+                    name.clone(),
+                )
+            }),
+            // 更新日時が同じファイルは、ファイル名の順に並べる
+            RetentionOrder::ByMtime => targets.sort_by_cached_key(|name| {
+                (
+                    self.file_system.modified(&self.directory.join(name)).ok(),
+                    log_file_sort_key(name, self.log_file_regex, self.date_format),
+                    name.clone(),
+                )
+            }),
+        }
+        // 現在ログを記録しているファイルは、日時が未来のファイルがあっても削除しないように、最後に移動する
+        let mut protected = 0;
+        if self.current.is_some() {
+            if let Some(position) = targets.iter().position(|name| self.is_current(name)) {
+                let current = targets.remove(position);
+                targets.push(current);
+                protected = 1;
+            }
+        }
+
+        Ok((targets, protected))
+    }
+
+    /// 古いファイルを削除する。
+    ///
+    /// 古い順に並べたログファイルの先頭から、削除する基準を満たすまで、ログファイルを削除する。
+    /// 確保する空き容量が設定されている場合は、さらに空き容量が回復するまで、残りのログファイルを
+    /// 古い順に削除する。
+    ///
+    /// # 引数
+    ///
+    /// - on_error: ファイルを削除できなかったとき、または空き容量を取得できなかったときに、
+    ///   エラーを引数に呼び出す関数。
+    ///
+    /// # 戻り値
+    ///
+    /// 削除したファイルのパス。ディレクトリを読み込めなかった場合は、そのエラー。
+    fn prune(&self, mut on_error: impl FnMut(AppenderError)) -> io::Result<Vec<PathBuf>> {
+        let (targets, protected) = self.log_files()?;
+
+        let mut removed = Vec::new();
+        let selected = self.select_old_files(&targets, protected);
+        let max_deletions = self.max_deletions.unwrap_or(usize::MAX);
+        // 削除できなかったファイルも、削除を試みた数に含める
+        let attempts = selected.len().min(max_deletions);
+        for target in &selected[..attempts] {
+            self.remove_file(target, &mut removed, &mut on_error);
+        }
+        if let Some(min_free_bytes) = self.min_free_bytes {
+            let remaining = targets[..self.deletable_count(&targets, protected)]
+                .iter()
+                .filter(|name| !selected.contains(&name.as_str()))
+                .take(max_deletions - attempts);
+            for target in remaining {
+                match self.file_system.available_space(self.directory) {
+                    Ok(available) if available < min_free_bytes => {
+                        self.remove_file(target, &mut removed, &mut on_error)
+                    }
+                    Ok(_) => break,
+                    Err(err) => {
+                        on_error(AppenderError::Other {
+                            message: "Couldn't get available disk space",
+                            source: err,
+                        });
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// 古いファイルを削除せずに、削除するファイルを選択する。
+    ///
+    /// `prune`と同じ基準でファイルを選択する。確保する空き容量が設定されている場合は、選択した
+    /// ファイルを削除したときに回復する空き容量を、ファイルのサイズから見積もる。
+    ///
+    /// # 戻り値
+    ///
+    /// 削除するファイルのパス。ディレクトリを読み込めなかった場合、または空き容量を取得できなかった
+    /// 場合は、そのエラー。
+    fn plan(&self) -> io::Result<Vec<PathBuf>> {
+        let (targets, protected) = self.log_files()?;
+
+        let selected = self.select_old_files(&targets, protected);
+        let max_deletions = self.max_deletions.unwrap_or(usize::MAX);
+        let mut planned: Vec<PathBuf> = selected
+            .iter()
+            .filter(|name| !self.is_current(name))
+            .take(max_deletions)
+            .map(|name| self.directory.join(name))
+            .collect();
+        if let Some(min_free_bytes) = self.min_free_bytes {
+            let file_len = |path: &Path| self.file_system.file_len(path).unwrap_or(0);
+            let mut available = self.file_system.available_space(self.directory)?;
+            for path in &planned {
+                available = available.saturating_add(file_len(path));
+            }
+            let remaining = targets[..self.deletable_count(&targets, protected)]
+                .iter()
+                .filter(|name| !selected.contains(&name.as_str()))
+                .take(max_deletions.saturating_sub(planned.len()));
+            for target in remaining {
+                if min_free_bytes <= available {
+                    break;
+                }
+                let path = self.directory.join(target);
+                available = available.saturating_add(file_len(&path));
+                planned.push(path);
+            }
+        }
+
+        Ok(planned)
+    }
+
+    /// ログファイルを削除する。
+    ///
+    /// ファイルを移動する設定の場合は、削除せずに移動先のディレクトリへ移動する。
+    ///
+    /// # 引数
+    ///
+    /// - target: 削除するログファイル名。
+    /// - removed: 削除、または移動したファイルのパスを追加するベクタ。
+    /// - on_error: ファイルを削除できなかったときに呼び出す関数。
+    fn remove_file(
+        &self,
+        target: &str,
+        removed: &mut Vec<PathBuf>,
+        on_error: &mut impl FnMut(AppenderError),
+    ) {
+        // 並べ替えた順序に関わらず、現在ログを記録しているファイルは削除しない
+        if self.is_current(target) {
+            return;
+        }
+        let path = self.directory.join(target);
+        let result = match self.removal_strategy {
+            RemovalStrategy::Delete => self.file_system.remove_file(&path),
+            RemovalStrategy::MoveTo(trash) => self.move_to_trash(&path, target, trash),
+        };
+        match result {
+            Ok(()) => removed.push(path),
+            // 検索した後に、他のプロセスや運用者が削除したファイルは無視する
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => on_error(AppenderError::RemoveFile { path, source: err }),
+        }
+    }
+
+    /// ログファイルを、削除せずに移動先のディレクトリへ移動する。
+    ///
+    /// 移動先に同じ名前のファイルが存在する場合は、重複しない連番を付けた名前で移動する。
+    ///
+    /// # 引数
+    ///
+    /// - path: 移動するログファイルのパス。
+    /// - target: 移動するログファイル名。
+    /// - trash: 移動先のディレクトリ。
+    ///
+    /// # 戻り値
+    ///
+    /// 移動できなかった場合は、そのエラー。
+    fn move_to_trash(&self, path: &Path, target: &str, trash: &Path) -> io::Result<()> {
+        // 検索した後に削除されたファイルを、移動先に作成しないように確認する
+        self.file_system.file_len(path)?;
+        self.file_system.create_dir_all(trash)?;
+        let mut trashed = trash.join(target);
+        let mut index = 0;
+        while self.file_system.file_len(&trashed).is_ok() {
+            index += 1;
+            trashed = trash.join(format!("{}.{}", target, index));
+        }
+
+        move_file(self.file_system, path, &trashed)
+    }
+
+    /// 現在ログを記録しているファイルであるか確認する。
+    ///
+    /// # 引数
+    ///
+    /// - target: ログファイル名。
+    ///
+    /// # 戻り値
+    ///
+    /// 現在ログを記録しているファイルの場合はtrue。
+    fn is_current(&self, target: &str) -> bool {
+        self.current
+            .as_deref()
+            .is_some_and(|current| current == self.directory.join(target))
+    }
+
+    /// 削除する対象のログファイルであるか確認する。
+    ///
+    /// ファイル名を判定する関数が設定されている場合はその関数で、設定されていない場合は
+    /// ログファイルに名前を付ける規則で判定する。
+    ///
+    /// # 引数
+    ///
+    /// - filename: ファイル名。
+    ///
+    /// # 戻り値
+    ///
+    /// 削除する対象のログファイルの場合はそのファイル名。そうでない場合はNone。
+    fn owned_log_file(&self, filename: &str) -> Option<String> {
+        // 書き込んでいる途中の`.part`ファイル、マニフェスト及び削除しないファイルは、判定する関数に
+        // 関わらず対象に含めない
+        if Path::new(filename).extension() == Some(PART_EXTENSION.as_ref())
+            || self.manifest_name == Some(filename)
+            || self
+                .protected_files
+                .iter()
+                .any(|protected| protected == filename)
+        {
+            return None;
+        }
+        match self.filename_predicate {
+            Some(predicate) => predicate(filename).then(|| filename.to_owned()),
+            None => self
+                .file_namer
+                .is_owned(filename)
+                .then(|| filename.to_owned()),
+        }
+    }
+
+    /// 削除する古いファイルを選択する。
+    ///
+    /// 削除する基準に該当するファイルと、保存する日数を超えたファイルを選択する。
+    /// 末尾の`protected`個のファイルと、その前の残すファイルの最小数のファイルは、削除する対象に
+    /// 含めない。
+    ///
+    /// # 引数
+    ///
+    /// - targets: 古い順に並べ替えたログファイル名。
+    /// - protected: 削除しない末尾のファイルの数。
+    ///
+    /// # 戻り値
+    ///
+    /// 削除するログファイル名。
+    fn select_old_files<'a>(&self, targets: &'a [String], protected: usize) -> Vec<&'a str> {
+        let candidates = targets.len().saturating_sub(protected);
+        let count = self.count_old_files(targets, candidates);
+
+        targets[..self.deletable_count(targets, protected)]
+            .iter()
+            .enumerate()
+            .filter(|(i, name)| *i < count || self.is_expired(name))
+            .map(|(_, name)| name.as_str())
+            .collect()
+    }
+
+    /// 残すファイルの最小数を除いて、削除できるファイルの数を返却する。
+    ///
+    /// # 引数
+    ///
+    /// - targets: 古い順に並べ替えたログファイル名。
+    /// - protected: 削除しない末尾のファイルの数。
+    ///
+    /// # 戻り値
+    ///
+    /// `targets`の先頭から、削除できるファイルの数。
+    fn deletable_count(&self, targets: &[String], protected: usize) -> usize {
+        targets
+            .len()
+            .saturating_sub(protected)
+            .saturating_sub(self.keep_minimum)
+    }
+
+    /// ログファイルが保存する日数を超えているか確認する。
+    ///
+    /// # 引数
+    ///
+    /// - filename: ログファイル名。
+    ///
+    /// # 戻り値
+    ///
+    /// 保存する日数を超えている場合はtrue。保存する日数を超えていない場合、保存する日数が
+    /// 設定されていない場合、またはファイル名に含まれる日付を解析できない場合はfalse。
+    fn is_expired(&self, filename: &str) -> bool {
+        let max_age_days = match self.max_age_days {
+            Some(max_age_days) => max_age_days,
+            None => return false,
+        };
+
+        match log_file_date(filename, self.log_file_regex, self.date_format) {
+            Some(date) => i64::from(max_age_days) < (self.today - date).whole_days(),
+            None => false,
+        }
+    }
+
+    /// 削除する基準に従って、削除する古いファイルの数を返却する。
+    ///
+    /// # 引数
+    ///
+    /// - targets: 古い順に並べ替えたログファイル名。
+    /// - candidates: `targets`の先頭から、削除できるファイルの数。
+    ///
+    /// # 戻り値
+    ///
+    /// `targets`の先頭から削除するファイルの数。
+    fn count_old_files(&self, targets: &[String], candidates: usize) -> usize {
+        match self.retention {
+            // 0は無制限を表すため、ファイルの数による削除はしない
+            Retention::Count(0) => 0,
+            Retention::Count(max_count) => candidates.saturating_sub(max_count),
+            Retention::TotalBytes(max_total_bytes) => {
+                // 削除している間に消えたファイルのサイズは0として扱う
+                let sizes: Vec<u64> = targets
+                    .iter()
+                    .map(|name| {
+                        self.file_system
+                            .file_len(&self.directory.join(name))
+                            .unwrap_or(0)
+                    })
+                    .collect();
+                let mut total: u64 = sizes.iter().sum();
+                let mut count = 0;
+                while count < candidates && max_total_bytes < total {
+                    total -= sizes[count];
+                    count += 1;
+                }
+
+                count
+            }
+        }
+    }
+}
+
+/// ファイル名が、既定の設定の`DailyRollingFileAppender`が作成するログファイルの名前であるか確認する。
+///
+/// ログファイル名は`{prefix}-<yyyymmdd>.{extension}`、または同じ日付の2つ目以降のファイルである
+/// `{prefix}-<yyyymmdd>.<index>.{extension}`で、圧縮されたファイル(`.gz`、`.zst`)にも一致する。
+/// 同期ツールなどによってファイル名の前後に付けられた空白やバイト順マークは、取り除いてから
+/// 確認するため、そのようなファイルもログファイルとして保存する基準に含まれる。
+/// 既定の設定のアペンダーと同じく、日付の後にホスト名やプロセスIDのタグを含むファイル名
+/// (`{prefix}-<yyyymmdd>-host1.{extension}`など)には一致しない。
+///
+/// # Arguments
+///
+/// * filename: ファイル名。
+/// * prefix: ログファイルの接頭語。
+/// * extension: ログファイルの拡張子。
+///
+/// # Returns
+///
+/// ログファイルの名前の場合はtrue。
+pub fn is_log_file(filename: &str, prefix: &str, extension: &str) -> bool {
+    DefaultNamer::new(prefix, extension).is_owned(filename)
+}
+
+/// 既定の設定の`DailyRollingFileAppender`が作成するログファイルの名前から、日付を取り出す。
+///
+/// `is_log_file`と同じ形式のファイル名のうち、`{prefix}-`に続く`yyyymmdd`の部分を日付として解析する。
+/// 拡張子は問わない。連番や圧縮した拡張子を含むファイル名からも日付を取り出し、ファイル名の
+/// 前後の空白やバイト順マークは取り除いてから解析する。`RetentionOrder::ByName`で古いファイルを
+/// 削除するときは、ファイル名から取り出した同じ日付の順に並べる。
+///
+/// # Arguments
+///
+/// * filename: ファイル名。
+/// * prefix: ログファイルの接頭語。
+///
+/// # Returns
+///
+/// ファイル名に含まれる日付。ログファイルの名前でない場合や、存在しない日付の場合はNone。
+pub fn parse_log_date(filename: &str, prefix: &str) -> Option<Date> {
+    let cached = LOG_DATE_REGEX
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+        .filter(|(cached_prefix, _)| cached_prefix == prefix)
+        .map(|(_, re)| re.clone());
+    let re = match cached {
+        Some(re) => re,
+        None => {
+            // 他のスレッドを待たせないように、ロックを獲得せずに正規表現を作成する
+            let re = Regex::new(&log_file_pattern(
+                prefix,
+                DEFAULT_SEPARATOR,
+                ANY_EXTENSION_PATTERN,
+                &DAILY_DATE_FORMAT,
+                "",
+            ))
+            .ok()?;
+            *LOG_DATE_REGEX
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = Some((prefix.to_owned(), re.clone()));
+            re
+        }
+    };
+
+    log_file_date(filename, &re, &DAILY_DATE_FORMAT)
+}
+
+/// 既定の設定の`DailyRollingFileAppender`が作成するログファイルの名前を返却する。
+///
+/// # Arguments
+///
+/// * prefix: ログファイルの接頭語。
+/// * extension: ログファイルの拡張子。
+/// * date: ログファイルの日付。
+/// * index: 同じ日付のファイルの連番。最初のファイルは0。
+///
+/// # Returns
+///
+/// ログファイル名。
+pub fn create_daily_log_filename(
+    prefix: &str,
+    extension: &str,
+    date: Date,
+    index: usize,
+) -> String {
+    DefaultNamer::new(prefix, extension).segment_name(&start_of_day(date), index)
+}
+
+/// ディレクトリに存在する既定の設定のログファイルのうち、新しい順に`max_count`個を残して、
+/// 古いファイルを削除する。
+///
+/// `DailyRollingFileAppender`が古いファイルを削除するときと同じ規則で、ログファイルを判定して
+/// 並べ替える。ただし、現在ログを記録しているファイルを区別しないため、`max_count`には
+/// 残すファイルの数を指定する。`max_count`が0の場合は、何も削除しない。
+///
+/// # Arguments
+///
+/// * directory: ログファイルを検索するディレクトリ。
+/// * prefix: ログファイルの接頭語。
+/// * extension: ログファイルの拡張子。
+/// * max_count: 残すファイルの数。0の場合は無制限。
+///
+/// # Returns
+///
+/// 削除したファイルのパス。ディレクトリを読み込めなかった場合は、そのエラー。削除できなかった
+/// ファイルがある場合は、他のファイルを削除した後に、最初に発生したエラー。
+pub fn prune_log_files(
+    directory: impl AsRef<Path>,
+    prefix: &str,
+    extension: &str,
+    max_count: usize,
+) -> io::Result<Vec<PathBuf>> {
+    let file_namer = DefaultNamer::new(prefix, extension);
+    let pruner = Pruner {
+        file_system: &OsFileSystem::new(),
+        directory: directory.as_ref(),
+        log_file_regex: &file_namer.log_file_regex,
+        date_format: &file_namer.date_format,
+        filename_predicate: None,
+        file_namer: &file_namer,
+        retention: Retention::Count(max_count),
+        retention_order: RetentionOrder::ByName,
+        removal_strategy: &RemovalStrategy::Delete,
+        max_age_days: None,
+        min_free_bytes: None,
+        max_deletions: None,
+        keep_minimum: 0,
+        protected_files: &[],
+        manifest_name: None,
+        today: SystemClock.now_date(),
+        current: None,
+    };
+    let mut first_error = None;
+    let removed = pruner.prune(|err| {
+        if first_error.is_none() {
+            first_error = Some(io::Error::from(err));
+        }
+    })?;
+
+    match first_error {
+        Some(err) => Err(err),
+        None => Ok(removed),
+    }
+}
+
+/// ログファイル名と照合するために、ファイル名の前後の空白とゼロ幅の文字を取り除く。
+///
+/// 同期ツールなどによって、ファイル名の前後に空白やバイト順マークが付けられたファイルも、
+/// アペンダーが作成したログファイルとして保存する基準に含めるために使用する。ファイルを削除や
+/// 移動するときは、取り除く前のファイル名を使用する。
+///
+/// # 引数
+///
+/// - filename: ファイル名。
+///
+/// # 戻り値
+///
+/// 前後の空白とゼロ幅の文字を取り除いたファイル名。
+fn normalize_filename(filename: &str) -> &str {
+    filename.trim_matches(|c: char| c.is_whitespace() || matches!(c, '\u{200b}' | '\u{feff}'))
+}
+
+/// ディレクトリエントリがログファイルであるか確認する。
+///
+/// # 引数
+///
+/// - entry: ディレクトリエントリ。
+/// - re: `tagged_log_file_regex`で作成したログファイル名に一致する正規表現。
+///
+/// # 戻り値
+///
+/// ログファイルの場合はそのディレクトリエントリ。ログファイルでない場合はNone。
+fn match_log_file(filename: &str, re: &Regex) -> Option<String> {
+    match re.is_match(normalize_filename(filename)) {
+        true => Some(filename.to_owned()),
+        false => None,
+    }
+}
+
+/// タグを含まないログファイル名に一致する正規表現を作成する。
+///
+/// # 引数
+///
+/// - prefix: ログファイルの接頭語。
+/// - separator: 接頭語と日時の間の区切り文字。
+/// - extension: ログファイルの拡張子。
+/// - date_format: ログファイル名に含める日時の書式。
+///
+/// # 戻り値
+///
+/// ログファイル名に一致する正規表現。
+#[cfg(test)]
+fn log_file_regex(
+    prefix: &str,
+    separator: char,
+    extension: &str,
+    date_format: &DateFormat,
+) -> Regex {
+    tagged_log_file_regex(prefix, separator, extension, date_format, "")
+}
+
+/// ログファイル名に一致する正規表現を作成する。
+///
+/// 1つ目のキャプチャグループは日時、2つ目のキャプチャグループは同じ日時のファイルの連番、
+/// 3つ目のキャプチャグループはサイズによってローテーションした時刻(`HHMMSS`)に一致する。
+/// gzipまたはZstandardで圧縮されたログファイル(`.gz`、`.zst`)にも一致する。
+/// 正規表現は接頭語、拡張子、日時の書式及びタグのみに依存するため、`Inner`を作成するときに
+/// 一度だけ作成して、古いファイルを削除するたびに再利用する。
+///
+/// # 引数
+///
+/// - prefix: ログファイルの接頭語。
+/// - separator: 接頭語と日時の間の区切り文字。
+/// - extension: ログファイルの拡張子。
+/// - date_format: ログファイル名に含める日時の書式。
+/// - tag_pattern: 日時の後に続くタグに一致する正規表現のパターン。キャプチャグループを含めない。
+///
+/// # 戻り値
+///
+/// ログファイル名に一致する正規表現。
+fn tagged_log_file_regex(
+    prefix: &str,
+    separator: char,
+    extension: &str,
+    date_format: &DateFormat,
+    tag_pattern: &str,
+) -> Regex {
+    let pattern = log_file_pattern(
+        prefix,
+        separator,
+        &regex::escape(extension),
+        date_format,
+        tag_pattern,
+    );
+
+    Regex::new(&pattern).unwrap()
+}
+
+/// ログファイル名に一致する正規表現のパターンを作成する。
+///
+/// キャプチャグループは、`tagged_log_file_regex`で作成する正規表現と同じである。
+///
+/// # 引数
+///
+/// - prefix: ログファイルの接頭語。
+/// - separator: 接頭語と日時の間の区切り文字。
+/// - extension_pattern: ログファイルの拡張子に一致する正規表現のパターン。キャプチャグループを
+///   含めない。
+/// - date_format: ログファイル名に含める日時の書式。
+/// - tag_pattern: 日時の後に続くタグに一致する正規表現のパターン。キャプチャグループを含めない。
+///
+/// # 戻り値
+///
+/// ログファイル名に一致する正規表現のパターン。
+fn log_file_pattern(
+    prefix: &str,
+    separator: char,
+    extension_pattern: &str,
+    date_format: &DateFormat,
+    tag_pattern: &str,
+) -> String {
+    format!(
+        r"^{}({}){}(?:\.(\d+)|T(\d{{6}}))?\.{}(?:\.gz|\.zst)?$",
+        regex::escape(&filename_head(prefix, separator)),
+        date_format.pattern,
+        tag_pattern,
+        extension_pattern
+    )
+}
+
+/// 区切り文字に続くタグに、完全に一致する正規表現のパターンを作成する。
+///
+/// # 引数
+///
+/// - tag: ファイル名に含めるタグ。
+/// - separator: 日時とタグの間の区切り文字。
+///
+/// # 戻り値
+///
+/// タグに一致するパターン。タグがない場合は空のパターン。
+fn exact_tag_pattern(tag: Option<&str>, separator: char) -> String {
+    match tag {
+        Some(tag) => format!(
+            "{}{}",
+            regex::escape(separator.encode_utf8(&mut [0; 4])),
+            regex::escape(tag)
+        ),
+        None => String::new(),
+    }
+}
+
+/// ホスト名などのタグとプロセスIDから、ファイル名に含めるタグを作成する。
+///
+/// # 引数
+///
+/// - host_tag: ホスト名などのタグ。
+/// - include_pid: プロセスIDを含めるか。
+/// - separator: タグとプロセスIDの間の区切り文字。
+///
+/// # 戻り値
+///
+/// ファイル名に含めるタグ。含めるものがない場合はNone。タグが空の場合と、ピリオドまたはファイル名に
+/// 使用できない文字を含む場合は`InvalidInput`エラー。
+fn instance_tag(
+    host_tag: Option<&str>,
+    include_pid: bool,
+    separator: char,
+) -> io::Result<Option<String>> {
+    let mut parts = Vec::new();
+    if let Some(host_tag) = host_tag {
+        // ピリオドは連番や拡張子と区別できないため、使用できない
+        if host_tag.is_empty()
+            || host_tag
+                .chars()
+                .any(|c| c == '.' || is_illegal_filename_char(c))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "host tag of the appender is not valid",
+            ));
+        }
+        parts.push(host_tag.to_owned());
+    }
+    if include_pid {
+        parts.push(std::process::id().to_string());
+    }
+
+    Ok((!parts.is_empty()).then(|| parts.join(separator.encode_utf8(&mut [0; 4]))))
+}
+
+/// ログファイルを古い順に並べ替えるためのキーを返却する。
+///
+/// 連番を持たないファイルは、その日付の最初のファイルであるため、連番を0として扱う。連番は数値として
+/// 比較するため、連番の桁数が異なっても、`.10`のファイルは`.2`のファイルより新しいファイルとなる。
+/// 時刻を接尾辞とするファイルは、時刻(`HHMMSS`)を数値にして連番として扱う。
+/// 日時を解析できないファイルは、最も古いファイルとして扱う。
+///
+/// # 引数
+///
+/// - filename: ログファイル名。
+/// - re: `tagged_log_file_regex`で作成したログファイル名に一致する正規表現。
+/// - date_format: ログファイル名に含める日時の書式。
+///
+/// # 戻り値
+///
+/// ログファイル名に含まれる日時と連番のタプル。
+fn log_file_sort_key(
+    filename: &str,
+    re: &Regex,
+    date_format: &DateFormat,
+) -> (Option<PrimitiveDateTime>, usize) {
+    match re.captures(normalize_filename(filename)) {
+        Some(captures) => {
+            let index = captures
+                .get(2)
+                .or_else(|| captures.get(3))
+                .and_then(|index| index.as_str().parse().ok())
+                .unwrap_or(0);
+            (date_format.parse(&captures[1]), index)
+        }
+        None => (None, 0),
+    }
+}
+
+impl Rotation {
+    /// ローテーションする間隔を返却する。
+    fn duration(&self) -> Duration {
+        match self {
+            Rotation::Daily => Duration::days(1),
+            Rotation::Hourly => Duration::hours(1),
+            Rotation::Minutely => Duration::minutes(1),
+        }
+    }
+
+    /// ログファイル名に含める日時の既定の書式を返却する。
+    fn default_date_format(&self) -> &'static str {
+        match self {
+            Rotation::Daily => "[year][month][day]",
+            Rotation::Hourly => "[year][month][day][hour]",
+            Rotation::Minutely => "[year][month][day][hour][minute]",
+        }
+    }
+
+    /// 日時をローテーションする間隔で切り捨てる。
+    ///
+    /// 日時は、タイムゾーンに関わらず比較できるように、UTCの日時として扱う。
+    ///
+    /// # 引数
+    ///
+    /// - date_time: 日時。
+    ///
+    /// # 戻り値
+    ///
+    /// ローテーションする間隔で切り捨てた日時。
+    fn truncate(&self, date_time: OffsetDateTime) -> OffsetDateTime {
+        let time = match self {
+            Rotation::Daily => Time::MIDNIGHT,
+            Rotation::Hourly => Time::from_hms(date_time.hour(), 0, 0)
+                .expect("Invalid time; this is a bug in restricted-rolling-file-appender"),
+            Rotation::Minutely => Time::from_hms(date_time.hour(), date_time.minute(), 0)
+                .expect("Invalid time; this is a bug in restricted-rolling-file-appender"),
+        };
+
+        PrimitiveDateTime::new(date_time.date(), time).assume_utc()
+    }
+}
+
+impl DateFormat {
+    /// 日時の書式を作成する。
+    ///
+    /// 書式を検証するために、桁数が変わらない2つの日時を書式化して、数字以外の文字が一致すること、
+    /// 及び書式化した文字列を解析して、ローテーションする間隔で切り捨てた日時に戻ることを確認する。
+    ///
+    /// # 引数
+    ///
+    /// - description: `time`クレートの書式記述。
+    /// - rotation: ファイルをローテーションする間隔。
+    ///
+    /// # 戻り値
+    ///
+    /// `DateFormat`インスタンス。書式がログファイル名に使用できない場合は`InvalidInput`エラー。
+    fn new(description: &str, rotation: Rotation) -> io::Result<Self> {
+        let invalid_input =
+            |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message.to_owned());
+        let description = format_description::parse_owned::<1>(description)
+            .map_err(|err| invalid_input(&format!("invalid date format: {}", err)))?;
+
+        let samples = [
+            PrimitiveDateTime::new(
+                Date::from_calendar_date(1999, time::Month::December, 31).unwrap(),
+                Time::from_hms(23, 59, 59).unwrap(),
+            )
+            .assume_utc(),
+            PrimitiveDateTime::new(
+                Date::from_calendar_date(2000, time::Month::January, 2).unwrap(),
+                Time::from_hms(3, 4, 5).unwrap(),
+            )
+            .assume_utc(),
+        ];
+        let mut patterns = Vec::with_capacity(samples.len());
+        for sample in &samples {
+            let formatted = sample
+                .format(&description)
+                .map_err(|err| invalid_input(&format!("invalid date format: {}", err)))?;
+            if formatted.is_empty() || formatted.chars().any(is_illegal_filename_char) {
+                return Err(invalid_input(
+                    "date format produces characters illegal in filenames",
+                ));
+            }
+            patterns.push(date_pattern(&formatted));
+        }
+        if patterns.iter().any(|pattern| pattern != &patterns[0]) {
+            return Err(invalid_input(
+                "date format must consist of fixed-width numeric components",
+            ));
+        }
+
+        let date_format = Self {
+            description,
+            pattern: patterns.swap_remove(0),
+        };
+        for sample in samples {
+            let truncated = rotation.truncate(sample);
+            let parsed = date_format
+                .parse(&date_format.format(&sample))
+                .map(|date_time| rotation.truncate(date_time.assume_utc()));
+            if parsed != Some(truncated) {
+                return Err(invalid_input(
+                    "date format must identify the date and time of each rotation",
+                ));
+            }
+        }
+
+        Ok(date_format)
+    }
+
+    /// ログファイル名に含める日時の文字列を返却する。
+    ///
+    /// # 引数
+    ///
+    /// - date_time: 日時。
+    ///
+    /// # 戻り値
+    ///
+    /// ログファイル名に含める日時の文字列。
+    fn format(&self, date_time: &OffsetDateTime) -> String {
+        date_time
+            .format(&self.description)
+            .expect("Invalid date format; this is a bug in restricted-rolling-file-appender")
+    }
+
+    /// ログファイル名に含まれる日時の文字列を解析する。
+    ///
+    /// 書式に含まれない時、分及び秒は0として扱う。
+    ///
+    /// # 引数
+    ///
+    /// - s: ログファイル名に含まれる日時の文字列。
+    ///
+    /// # 戻り値
+    ///
+    /// 日時。日時として正しくない場合はNone。
+    fn parse(&self, s: &str) -> Option<PrimitiveDateTime> {
+        let mut parsed = Parsed::new();
+        let remaining = parsed.parse_item(s.as_bytes(), &self.description).ok()?;
+        if !remaining.is_empty() {
+            return None;
+        }
+        let date =
+            Date::from_calendar_date(parsed.year()?, parsed.month()?, parsed.day()?.get()).ok()?;
+        let time = Time::from_hms(
+            parsed.hour_24().unwrap_or(0),
+            parsed.minute().unwrap_or(0),
+            parsed.second().unwrap_or(0),
+        )
+        .ok()?;
+
+        Some(PrimitiveDateTime::new(date, time))
+    }
+}
+
+impl From<Rotation> for DateFormat {
+    fn from(rotation: Rotation) -> Self {
+        Self::new(rotation.default_date_format(), rotation)
+            .expect("Invalid date format; this is a bug in restricted-rolling-file-appender")
+    }
+}
+
+/// ファイル名に使用できない文字であるか確認する。
+///
+/// 制御文字と、主要なファイルシステムでパスの区切りなどに使用される文字を、使用できない文字とする。
+fn is_illegal_filename_char(c: char) -> bool {
+    c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+}
+
+/// 書式化した日時の文字列から、その書式で書式化した日時に一致する正規表現のパターンを作成する。
+///
+/// 数字は任意の数字に一致させ、それ以外の文字はそのまま一致させる。
+fn date_pattern(formatted: &str) -> String {
+    formatted
+        .chars()
+        .map(|c| match c.is_ascii_digit() {
+            true => r"\d".to_owned(),
+            false => regex::escape(&c.to_string()),
+        })
+        .collect()
+}
+
+impl Clock for SystemClock {
+    fn now_date(&self) -> Date {
+        OffsetDateTime::now_utc().date()
+    }
+
+    fn now_date_in(&self, time_zone: TimeZone) -> Date {
+        match time_zone {
+            TimeZone::Utc => self.now_date(),
+            _ => self.now_in(time_zone).date(),
+        }
+    }
+
+    fn now_in(&self, time_zone: TimeZone) -> OffsetDateTime {
+        let now = OffsetDateTime::now_utc();
+        now.to_offset(time_zone.offset_at(now))
+    }
+}
+
+impl Compression {
+    /// 圧縮したファイル名に付ける拡張子を返却する。
+    ///
+    /// # 戻り値
+    ///
+    /// 先頭のピリオドを除いた拡張子。圧縮しない場合はNone。
+    fn extension(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            #[cfg(feature = "gzip")]
+            Compression::Gzip { .. } => Some("gz"),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { .. } => Some("zst"),
+        }
+    }
+
+    /// 入力を圧縮して、出力に書き込む。
+    ///
+    /// 圧縮しない場合は、入力をそのまま出力に書き込む。
+    ///
+    /// # 引数
+    ///
+    /// - input: 圧縮する内容を読み込むリーダー。
+    /// - output: 圧縮した内容を書き込むライター。
+    fn encode(&self, input: &mut dyn io::Read, mut output: LogFile) -> io::Result<()> {
+        match *self {
+            Compression::None => {
+                io::copy(input, &mut output)?;
+            }
+            #[cfg(feature = "gzip")]
+            Compression::Gzip { level } => {
+                let mut encoder = GzEncoder::new(output, flate2::Compression::new(level.min(9)));
+                io::copy(input, &mut encoder)?;
+                output = encoder.finish()?;
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { level } => {
+                let mut encoder = zstd::stream::write::Encoder::new(output, level)?;
+                io::copy(input, &mut encoder)?;
+                output = encoder.finish()?;
+            }
+        }
+
+        output.flush()
+    }
+}
+
+impl TimeZone {
+    /// 指定された日時における、タイムゾーンのUTCからのオフセットを返却する。
+    ///
+    /// # 引数
+    ///
+    /// - date_time: 日時。
+    ///
+    /// # 戻り値
+    ///
+    /// UTCからのオフセット。ローカルのオフセットを取得できない場合はUTC。
+    fn offset_at(&self, date_time: OffsetDateTime) -> UtcOffset {
+        match self {
+            TimeZone::Utc => UtcOffset::UTC,
+            TimeZone::Local => UtcOffset::local_offset_at(date_time).unwrap_or(UtcOffset::UTC),
+            TimeZone::Fixed(offset) => *offset,
+        }
+    }
+
+    /// 時計から、タイムゾーンにおける現在日時を取得する。
+    ///
+    /// 時計が返却した日時を、タイムゾーンのオフセットに変換する。
+    ///
+    /// # 引数
+    ///
+    /// - clock: 時計。
+    ///
+    /// # 戻り値
+    ///
+    /// タイムゾーンにおける現在日時。
+    fn now(&self, clock: &dyn Clock) -> OffsetDateTime {
+        let now = clock.now_in(*self);
+        now.to_offset(self.offset_at(now))
+    }
+}
+
+/// 日付を、その日の0時0分0秒(UTC)に設定された`OffsetDateTime`に変換する。
+///
+/// タイムゾーンに関わらず日付を比較できるように、日付はUTCの0時0分0秒として扱う。
+///
+/// # 引数
+///
+/// - date: 日付。
+///
+/// # 戻り値
+///
+/// 日付の0時0分0秒に設定された`OffsetDateTime`。
+fn start_of_day(date: Date) -> OffsetDateTime {
+    date.midnight().assume_utc()
+}
+
+/// ディレクトリに存在するログファイルのうち、最も新しいファイルの日時を返却する。
+///
+/// 書き込んでいる途中に終了したプロセスが残した`.part`ファイルも、拡張子を除いたファイル名で
+/// 日時を判定する。
+///
+/// # 引数
+///
+/// - file_system: ログファイルを検索するファイルシステム。
+/// - directory: ログファイルを検索するディレクトリ。
+/// - re: `tagged_log_file_regex`で作成したログファイル名に一致する正規表現。
+/// - date_format: ログファイル名に含まれる日時の書式。
+///
+/// # 戻り値
+///
+/// 最も新しいログファイルの日時。ログファイルが存在しない場合、またはディレクトリを読み込めなかった
+/// 場合はNone。
+fn newest_log_date(
+    file_system: &dyn FileSystem,
+    directory: &Path,
+    re: &Regex,
+    date_format: &DateFormat,
+) -> Option<PrimitiveDateTime> {
+    file_system
+        .read_dir(directory)
+        .ok()?
+        .iter()
+        .filter_map(|name| {
+            let part_suffix = format!(".{}", PART_EXTENSION);
+            let name = name.strip_suffix(&part_suffix).unwrap_or(name);
+            log_file_sort_key(name, re, date_format).0
+        })
+        .max()
+}
+
+/// 指定された日時のログファイルのうち、最も新しいファイルの連番を返却する。
+///
+/// 時刻を接尾辞とするファイルは、`SizeSuffix::Time`でローテーションしたときと同様に、
+/// 日付と時刻をUTCの日時とみなしたUNIX時間を連番とする。
+///
+/// # 引数
+///
+/// - file_system: ログファイルを検索するファイルシステム。
+/// - directory: ログファイルを検索するディレクトリ。
+/// - re: `tagged_log_file_regex`で作成したログファイル名に一致する正規表現。
+/// - date_format: ログファイル名に含める日時の書式。
+/// - date: ローテーションする間隔で切り捨てた日時。
+///
+/// # 戻り値
+///
+/// 最も新しいファイルの連番。同じ日時のファイルが存在しない場合はNone。
+fn newest_segment_index(
+    file_system: &dyn FileSystem,
+    directory: &Path,
+    re: &Regex,
+    date_format: &DateFormat,
+    date: &OffsetDateTime,
+) -> Option<usize> {
+    let date = PrimitiveDateTime::new(date.date(), date.time());
+
+    file_system
+        .read_dir(directory)
+        .ok()?
+        .iter()
+        .filter_map(|name| {
+            // 前回の実行が閉じずに終了した`.part`ファイルも、同じ日時のファイルとして扱う
+            let part_suffix = format!(".{}", PART_EXTENSION);
+            let name = name.strip_suffix(&part_suffix).unwrap_or(name);
+            let captures = re.captures(normalize_filename(name))?;
+            if date_format.parse(&captures[1])? != date {
+                return None;
+            }
+            if let Some(index) = captures.get(2) {
+                return index.as_str().parse().ok();
+            }
+            match captures.get(3) {
+                Some(time) => {
+                    let time = time.as_str();
+                    let time = Time::from_hms(
+                        time[0..2].parse().ok()?,
+                        time[2..4].parse().ok()?,
+                        time[4..6].parse().ok()?,
+                    )
+                    .ok()?;
+                    Some(
+                        PrimitiveDateTime::new(date.date(), time)
+                            .assume_utc()
+                            .unix_timestamp() as usize,
+                    )
+                }
+                None => Some(0),
+            }
+        })
+        .max()
+}
+
+/// サイズによってローテーションした時刻を接尾辞とするファイルの連番を返却する。
+///
+/// 日時と同様に、タイムゾーンにおける時刻をUTCの時刻として扱ったUNIX時間を連番とする。
+///
+/// # 引数
+///
+/// - time_zone: ファイル名に含める時刻のタイムゾーン。
+/// - clock: 現在の日時を返却する時計。
+///
+/// # 戻り値
+///
+/// 現在の時刻を表現する連番。
+fn time_suffix_index(time_zone: TimeZone, clock: &dyn Clock) -> usize {
+    let now = time_zone.now(clock);
+
+    PrimitiveDateTime::new(now.date(), now.time())
+        .assume_utc()
+        .unix_timestamp() as usize
+}
+
+/// ログファイル名に含まれる日付を返却する。
+///
+/// # 引数
+///
+/// - filename: ログファイル名。
+/// - re: `tagged_log_file_regex`で作成したログファイル名に一致する正規表現。
+/// - date_format: ログファイル名に含める日時の書式。
+///
+/// # 戻り値
+///
+/// ログファイル名に含まれる日付。ログファイル名でない場合、または日付として正しくない場合はNone。
+fn log_file_date(filename: &str, re: &Regex, date_format: &DateFormat) -> Option<Date> {
+    let captures = re.captures(normalize_filename(filename))?;
+
+    date_format
+        .parse(&captures[1])
+        .map(|date_time| date_time.date())
+}
+
+/// ローテーションするログファイルの名前を作成して、返却する。
+///
+/// ログファイル名は、`{filename_prefix}{separator}<yyyymmdd>.{extension}`となる。
+/// 同じ日付の2つ目以降のファイルは、`{filename_prefix}{separator}<yyyymmdd>.<index>.{extension}`となる。
+/// 1日より短い間隔でローテーションする場合、日付の後に時や分が続く。接頭語が空の場合は、
+/// 区切り文字を付けずに日時から始まる。
+///
+/// # 引数
+///
+/// - filename_prefix: ファイル名の接頭語。
+/// - separator: 接頭語と日時の間の区切り文字。
+/// - extension: ファイルの拡張子。
+/// - date_format: ファイル名に含める日時の書式。
+/// - date: ファイルの日付。
+/// - index: 同じ日付のファイルの連番。
+///
+/// # 戻り値
+///
+/// ログファイル名。
+fn create_log_filename(
+    filename_prefix: &str,
+    separator: char,
+    extension: &str,
+    date_format: &DateFormat,
+    date: &OffsetDateTime,
+    index: usize,
+) -> String {
+    let head = filename_head(filename_prefix, separator);
+    let date = date_format.format(date);
+
+    match index {
+        0 => format!("{}{}.{}", head, date, extension),
+        _ => format!("{}{}.{}.{}", head, date, index, extension),
+    }
+}
+
+/// ログファイル名の日時の前に付ける、接頭語と区切り文字を返却する。
+///
+/// 接頭語が空の場合は、日時から始まるファイル名(`20220527.log`)とするため、区切り文字も付けない。
+///
+/// # 引数
+///
+/// - filename_prefix: ファイル名の接頭語。
+/// - separator: 接頭語と日時の間の区切り文字。
+///
+/// # 戻り値
+///
+/// 接頭語と区切り文字。接頭語が空の場合は空文字列。
+fn filename_head(filename_prefix: &str, separator: char) -> String {
+    match filename_prefix.is_empty() {
+        true => String::new(),
+        false => format!("{}{}", filename_prefix, separator),
+    }
+}
+
+/// 文字列を、JSONの文字列として引用する。
+///
+/// # 引数
+///
+/// - s: 引用する文字列。
+///
+/// # 戻り値
+///
+/// 二重引用符で囲み、特殊な文字をエスケープした文字列。
+fn json_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+/// サイズによってローテーションした時刻を接尾辞とする、ログファイルの名前を作成して、返却する。
+///
+/// ログファイル名は、`{filename_prefix}{separator}<yyyymmdd>T<HHMMSS>.{extension}`となる。
+///
+/// # 引数
+///
+/// - filename_prefix: ファイル名の接頭語。
+/// - separator: 接頭語と日時の間の区切り文字。
+/// - extension: ファイルの拡張子。
+/// - date_format: ファイル名に含める日時の書式。
+/// - date: ファイルの日付。
+/// - time: ローテーションした時刻。
+///
+/// # 戻り値
+///
+/// ログファイル名。
+fn create_time_suffixed_log_filename(
+    filename_prefix: &str,
+    separator: char,
+    extension: &str,
+    date_format: &DateFormat,
+    date: &OffsetDateTime,
+    time: &OffsetDateTime,
+) -> String {
+    format!(
+        "{}{}T{:02}{:02}{:02}.{}",
+        filename_head(filename_prefix, separator),
+        date_format.format(date),
+        time.hour(),
+        time.minute(),
+        time.second(),
+        extension
+    )
+}
+
+/// ライターを作成する。
+///
+/// ヘッダーを返却する関数が指定された場合、開いたファイルが空であれば、ヘッダーを書き込む。
+///
+/// # 引数
+///
+/// - file_system: ファイルを作成するファイルシステム。
+/// - path: ログファイルのパス。
+/// - open_mode: 既に存在するファイルを扱う方法。
+/// - header: ファイルの先頭に書き込むヘッダーを返却する関数。
+/// - date: ログファイルの日付。
+/// - open_attempts: 一時的なエラーでファイルを開けなかった場合に、開くことを試みる最大の回数。
+///
+/// # 戻り値
+///
+/// ファイルへのライターと、ヘッダーを含めた開いたファイルのサイズ(バイト)のタプル。
+fn create_writer(
+    file_system: &dyn FileSystem,
+    path: &Path,
+    open_mode: OpenMode,
+    header: Option<&HeaderFn>,
+    date: &OffsetDateTime,
+    open_attempts: usize,
+) -> io::Result<(LogFile, u64)> {
+    // ディレクトリが削除されている場合に備えて、開く前にディレクトリを作成する。ファイルを開けなかった
+    // 場合に、その原因ではなくディレクトリを作成したときのエラーを返却しないように、先に作成する
+    if let Some(parent) = path.parent() {
+        file_system.create_dir_all(parent)?;
+    }
+    let mut backoff = OPEN_RETRY_BACKOFF;
+    let mut attempt = 1;
+    let mut new_file = loop {
+        let result = match open_mode {
+            OpenMode::Append => file_system.append(path),
+            OpenMode::Truncate => file_system.create(path),
+        };
+        match result {
+            Ok(new_file) => break new_file,
+            Err(err) if attempt < open_attempts && is_retryable(&err) => {
+                thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(err) => return Err(err),
+        }
+    };
+    // 同じファイルに追記する場合は、ヘッダーを書き込まない
+    let mut len = file_system.file_len(path)?;
+    if let Some(header) = header {
+        if len == 0 {
+            let header = header(date);
+            new_file.write_all(&header)?;
+            len = header.len() as u64;
+        }
+    }
+
+    Ok((new_file, len))
+}
+
+/// 古いファイルを削除するディレクトリの、プロセス内で共有するロックを返却する。
+///
+/// # 引数
+///
+/// - directory: 古いファイルを削除するディレクトリ。
+///
+/// # 戻り値
+///
+/// ディレクトリのロック。同じディレクトリには、同じロックを返却する。
+fn retention_lock(directory: &Path) -> Arc<Mutex<()>> {
+    let mut locks = RETENTION_LOCKS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(lock) = locks.get(directory).and_then(Weak::upgrade) {
+        return lock;
+    }
+    locks.retain(|_, lock| lock.strong_count() > 0);
+    let lock = Arc::new(Mutex::new(()));
+    locks.insert(directory.to_path_buf(), Arc::downgrade(&lock));
+
+    lock
+}
+
+/// 終端を付けたり、文字エンコーディングを変換したりしたレコードを書き込む。
+///
+/// 元のレコードをそのまま書き込む場合は1度だけ書き込み、一部しか書き込めなかった場合はその
+/// バイト数を返却する。終端を付けたり変換したりした場合は、書き込んだバイト数を元のレコードの
+/// バイト数に対応付けられないため、全て書き込む。
+///
+/// # 引数
+///
+/// - writer: ログを記録しているファイルへのライター。
+/// - buf: `write`に渡されたバイト列。
+/// - record: `Inner::prepare_record`で作成したレコード。
+///
+/// # 戻り値
+///
+/// `buf`のうち書き込んだバイト数と、ファイルに書き込んだバイト数のタプル。
+fn write_prepared(
+    writer: &mut impl Write,
+    buf: &[u8],
+    record: Cow<'_, [u8]>,
+) -> io::Result<(usize, usize)> {
+    if let Cow::Borrowed(record) = record {
+        let written = writer.write(record)?;
+        return Ok((written, written));
+    }
+    writer.write_all(&record)?;
+
+    Ok((buf.len(), record.len()))
+}
+
+/// ヘッダーとフッターを、ファイルの文字エンコーディングに変換するように設定を変更する。
+///
+/// UTF-16の場合は、ヘッダーの前にバイト順マークを付ける。UTF-8の場合は、変換しないように
+/// 文字エンコーディングの設定を取り除く。
+///
+/// # 引数
+///
+/// - builder: `DailyRollingFileAppender`を構築するビルダー。
+///
+/// # 戻り値
+///
+/// ヘッダーとフッターを変換するビルダー。
+#[cfg(feature = "encoding")]
+fn encode_header_and_footer(
+    mut builder: DailyRollingFileAppenderBuilder,
+) -> DailyRollingFileAppenderBuilder {
+    let encoding = match builder.encoding {
+        Some(encoding) if encoding != encoding_rs::UTF_8 => encoding,
+        _ => {
+            builder.encoding = None;
+            return builder;
+        }
+    };
+
+    let bom: &[u8] = match encoding {
+        encoding if encoding == encoding_rs::UTF_16LE => b"\xff\xfe",
+        encoding if encoding == encoding_rs::UTF_16BE => b"\xfe\xff",
+        _ => b"",
+    };
+    let header = builder.header.take();
+    if header.is_some() || !bom.is_empty() {
+        builder.header = Some(Box::new(move |date| {
+            let mut bytes = bom.to_vec();
+            if let Some(header) = &header {
+                bytes.extend_from_slice(&encode_record(encoding, &header(date)));
+            }
+
+            bytes
+        }));
+    }
+    builder.footer = builder
+        .footer
+        .map(|footer| encode_record(encoding, &footer).into_owned());
+
+    builder
+}
+
+/// UTF-8のレコードを、文字エンコーディングに変換する。
+///
+/// `encoding_rs`はUTF-16に変換できないため、UTF-16の場合は標準ライブラリで変換する。
+///
+/// # 引数
+///
+/// - encoding: 変換先の文字エンコーディング。
+/// - record: UTF-8のレコード。
+///
+/// # 戻り値
+///
+/// 変換したレコード。
+#[cfg(feature = "encoding")]
+fn encode_record<'b>(encoding: &'static encoding_rs::Encoding, record: &'b [u8]) -> Cow<'b, [u8]> {
+    let text = String::from_utf8_lossy(record);
+    if encoding == encoding_rs::UTF_16LE {
+        return Cow::Owned(text.encode_utf16().flat_map(u16::to_le_bytes).collect());
+    }
+    if encoding == encoding_rs::UTF_16BE {
+        return Cow::Owned(text.encode_utf16().flat_map(u16::to_be_bytes).collect());
+    }
+
+    match (encoding.encode(&text).0, &text) {
+        (Cow::Borrowed(_), Cow::Borrowed(_)) => Cow::Borrowed(record),
+        (encoded, _) => Cow::Owned(encoded.into_owned()),
+    }
+}
+
+/// ファイルを開けなかったエラーが、再試行すれば解消する可能性のある一時的なエラーか判定する。
+///
+/// # 引数
+///
+/// - err: ファイルを開けなかったエラー。
+///
+/// # 戻り値
+///
+/// 一時的なエラーの場合は`true`。
+fn is_retryable(err: &io::Error) -> bool {
+    use io::ErrorKind::*;
+
+    matches!(
+        err.kind(),
+        Interrupted | WouldBlock | TimedOut | ResourceBusy | StaleNetworkFileHandle
+    )
+}
+
+/// ファイルを圧縮して、元のファイルを削除する。
+///
+/// 圧縮したファイルは`{元のファイル名}.{圧縮方式の拡張子}`となる。既に圧縮したファイルが存在する
+/// 場合は、上書きせずにgzipのメンバー、またはZstandardのフレームとして追記する。
+///
+/// # 引数
+///
+/// - file_system: ファイルを圧縮するファイルシステム。
+/// - path: 圧縮するファイルのパス。
+/// - compression: 圧縮する方式。
+///
+/// # 戻り値
+///
+/// 圧縮したファイルのパス。圧縮しない方式の場合は、元のファイルのパス。
+fn compress_file(
+    file_system: &dyn FileSystem,
+    path: &Path,
+    compression: Compression,
+) -> io::Result<PathBuf> {
+    let extension = match compression.extension() {
+        Some(extension) => extension,
+        None => return Ok(path.to_path_buf()),
+    };
+    let mut compressed_path = path.as_os_str().to_owned();
+    compressed_path.push(".");
+    compressed_path.push(extension);
+    let compressed_path = PathBuf::from(compressed_path);
+
+    let mut input = file_system.open(path)?;
+    let output = file_system.append(&compressed_path)?;
+    compression.encode(&mut *input, output)?;
+    file_system.remove_file(path)?;
+
+    Ok(compressed_path)
+}
+
+/// ファイルを移動する。
+///
+/// ファイルの名前を変更できない場合(異なるファイルシステムへの移動など)は、ファイルを複製した後に
+/// 元のファイルを削除する。移動先にファイルが存在する場合は、上書きしないように追記する。
+///
+/// # 引数
+///
+/// - file_system: ファイルを移動するファイルシステム。
+/// - from: 移動するファイルのパス。
+/// - to: 移動先のファイルのパス。
+///
+/// # 戻り値
+///
+/// ファイルを移動できなかった場合は、そのエラー。
+fn move_file(file_system: &dyn FileSystem, from: &Path, to: &Path) -> io::Result<()> {
+    if file_system.file_len(to).is_err() {
+        match file_system.rename(from, to) {
+            Ok(()) => return Ok(()),
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::CrossesDevices | io::ErrorKind::Unsupported
+                ) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    let mut input = file_system.open(from)?;
+    let mut output = file_system.append(to)?;
+    io::copy(&mut input, &mut output)?;
+    output.flush()?;
+    // Windowsでは開いているファイルを削除できないため、削除する前に閉じる
+    drop(input);
+    file_system.remove_file(from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        fs::{self, DirEntry, File},
+        sync::{Arc, Mutex},
+    };
+
+    fn today() -> OffsetDateTime {
+        start_of_day(SystemClock.now_date())
+    }
+
+    /// 返却する日時を変更できる時計。
+    #[derive(Clone)]
+    struct MockClock(Arc<Mutex<OffsetDateTime>>);
+
+    impl MockClock {
+        fn new(date: Date) -> Self {
+            Self::with_date_time(start_of_day(date))
+        }
+
+        fn with_date_time(date_time: OffsetDateTime) -> Self {
+            Self(Arc::new(Mutex::new(date_time)))
+        }
+
+        fn advance(&self, duration: Duration) {
+            let mut date_time = self.0.lock().unwrap();
+            *date_time += duration;
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now_date(&self) -> Date {
+            self.0.lock().unwrap().date()
+        }
+
+        fn now_in(&self, _time_zone: TimeZone) -> OffsetDateTime {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    /// 開く方法に応じてファイルを開くテスト用のファイルシステムの関数。
+    type OpenHook = Box<
+        dyn Fn(&dyn FileSystem, &Path, OpenMode) -> io::Result<Box<dyn Write + Send>> + Send + Sync,
+    >;
+
+    /// パスを受け取るテスト用のファイルシステムの関数。
+    type PathHook = Box<dyn Fn(&dyn FileSystem, &Path) -> io::Result<()> + Send + Sync>;
+
+    /// ディレクトリを読み込むテスト用のファイルシステムの関数。
+    type ReadDirHook = Box<dyn Fn(&dyn FileSystem, &Path) -> io::Result<Vec<String>> + Send + Sync>;
+
+    /// ファイルの名前を変更するテスト用のファイルシステムの関数。
+    type RenameHook = Box<dyn Fn(&dyn FileSystem, &Path, &Path) -> io::Result<()> + Send + Sync>;
+
+    /// 操作を内部のファイルシステムに転送し、一部の操作を関数で差し替えられるファイルシステム。
+    ///
+    /// 差し替える関数は内部のファイルシステムを受け取るため、エラーを返却する前後に、内部の
+    /// ファイルシステムの操作を呼び出せる。
+    struct HookedFileSystem {
+        inner: Box<dyn FileSystem>,
+        on_create_dir_all: Option<PathHook>,
+        on_open: Option<OpenHook>,
+        on_read_dir: Option<ReadDirHook>,
+        on_rename: Option<RenameHook>,
+    }
+
+    impl HookedFileSystem {
+        fn new(inner: impl FileSystem + 'static) -> Self {
+            Self {
+                inner: Box::new(inner),
+                on_create_dir_all: None,
+                on_open: None,
+                on_read_dir: None,
+                on_rename: None,
+            }
+        }
+
+        fn on_create_dir_all(
+            mut self,
+            hook: impl Fn(&dyn FileSystem, &Path) -> io::Result<()> + Send + Sync + 'static,
+        ) -> Self {
+            self.on_create_dir_all = Some(Box::new(hook));
+
+            self
+        }
+
+        /// 追記するとき(`OpenMode::Append`)と、空にするとき(`OpenMode::Truncate`)に、ファイルを
+        /// 開く関数を設定する。
+        fn on_open(
+            mut self,
+            hook: impl Fn(&dyn FileSystem, &Path, OpenMode) -> io::Result<Box<dyn Write + Send>>
+                + Send
+                + Sync
+                + 'static,
+        ) -> Self {
+            self.on_open = Some(Box::new(hook));
+
+            self
+        }
+
+        fn on_read_dir(
+            mut self,
+            hook: impl Fn(&dyn FileSystem, &Path) -> io::Result<Vec<String>> + Send + Sync + 'static,
+        ) -> Self {
+            self.on_read_dir = Some(Box::new(hook));
+
+            self
+        }
+
+        fn on_rename(
+            mut self,
+            hook: impl Fn(&dyn FileSystem, &Path, &Path) -> io::Result<()> + Send + Sync + 'static,
+        ) -> Self {
+            self.on_rename = Some(Box::new(hook));
+
+            self
+        }
+
+        fn open_with(&self, path: &Path, open_mode: OpenMode) -> io::Result<Box<dyn Write + Send>> {
+            match &self.on_open {
+                Some(hook) => hook(self.inner.as_ref(), path, open_mode),
+                None => open_writer(self.inner.as_ref(), path, open_mode),
+            }
+        }
+    }
+
+    impl FileSystem for HookedFileSystem {
+        fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+            match &self.on_create_dir_all {
+                Some(hook) => hook(self.inner.as_ref(), path),
+                None => self.inner.create_dir_all(path),
+            }
+        }
+
+        fn append(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+            self.open_with(path, OpenMode::Append)
+        }
+
+        fn create(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+            self.open_with(path, OpenMode::Truncate)
+        }
+
+        fn open(&self, path: &Path) -> io::Result<Box<dyn io::Read + Send>> {
+            self.inner.open(path)
+        }
+
+        fn file_len(&self, path: &Path) -> io::Result<u64> {
+            self.inner.file_len(path)
+        }
+
+        fn modified(&self, path: &Path) -> io::Result<std::time::SystemTime> {
+            self.inner.modified(path)
+        }
+
+        fn read_dir(&self, path: &Path) -> io::Result<Vec<String>> {
+            match &self.on_read_dir {
+                Some(hook) => hook(self.inner.as_ref(), path),
+                None => self.inner.read_dir(path),
+            }
+        }
+
+        fn remove_file(&self, path: &Path) -> io::Result<()> {
+            self.inner.remove_file(path)
+        }
+
+        fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+            match &self.on_rename {
+                Some(hook) => hook(self.inner.as_ref(), from, to),
+                None => self.inner.rename(from, to),
+            }
+        }
+
+        fn sync_all(&self, path: &Path) -> io::Result<()> {
+            self.inner.sync_all(path)
+        }
+
+        fn available_space(&self, path: &Path) -> io::Result<u64> {
+            self.inner.available_space(path)
+        }
+
+        fn replace_symlink(
+            &self,
+            directory: &Path,
+            link_name: &str,
+            target: &str,
+        ) -> io::Result<()> {
+            self.inner.replace_symlink(directory, link_name, target)
+        }
+    }
+
+    /// 開く方法に応じて、ファイルシステムでファイルを開く。
+    fn open_writer(
+        file_system: &dyn FileSystem,
+        path: &Path,
+        open_mode: OpenMode,
+    ) -> io::Result<Box<dyn Write + Send>> {
+        match open_mode {
+            OpenMode::Append => file_system.append(path),
+            OpenMode::Truncate => file_system.create(path),
+        }
+    }
+
+    /// 書き込むと常にエラーとなるライター。
+    struct FailingWriter;
+
+    impl Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk failure"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_is_log_file() {
+        let prefix = "foo";
+
+        let log_filenames = vec![
+            "foo-00000000.log",
+            "foo-20220527.log",
+            "foo-20220527.1.log",
+            "foo-20220527.log.gz",
+            "foo-20220527.1.log.gz",
+            "foo-20220527.log.zst",
+            "foo-20220527.log ",
+            " foo-20220527.log",
+            "\u{feff}foo-20220527.log",
+        ];
+        for filename in log_filenames {
+            assert!(
+                match_log_file(
+                    filename,
+                    &log_file_regex(
+                        prefix,
+                        DEFAULT_SEPARATOR,
+                        DEFAULT_EXTENSION,
+                        &DateFormat::from(Rotation::Daily)
+                    )
+                )
+                .is_some(),
+                "filename={}",
+                filename
+            );
+        }
+
+        let not_log_filenames = vec![
+            "foo.log",
+            "20220527.log",
+            "foo-2022052a.log",
+            "foo-20220527.log.part",
+            "foo-20220527.txt",
+            "foo-20220527.a.log",
+            "foo-20220527..log",
+            "foo-20220527.gz",
+            "foo-20220527.log.zip",
+        ];
+        for filename in not_log_filenames {
+            assert!(
+                match_log_file(
+                    filename,
+                    &log_file_regex(
+                        prefix,
+                        DEFAULT_SEPARATOR,
+                        DEFAULT_EXTENSION,
+                        &DateFormat::from(Rotation::Daily)
+                    )
+                )
+                .is_none(),
+                "filename={}",
+                filename
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_log_file_with_tags() {
+        // タグを設定したアペンダーは、そのタグを含むファイルだけを管理するはず
+        let namer = DefaultNamer::with_settings(
+            "foo",
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            DateFormat::from(Rotation::Daily),
+            SizeSuffix::Counter,
+            Some("host1-1234".to_owned()),
+        );
+        let tagged_filenames = vec![
+            "foo-20220527-host1-1234.log",
+            "foo-20220527-host1-1234.1.log",
+            "foo-20220527-host1-1234.log.gz",
+        ];
+        for filename in tagged_filenames {
+            assert!(namer.is_owned(filename), "filename={}", filename);
+            // 既定の設定のアペンダーは、タグを含むファイルを管理しないため一致しないはず
+            assert!(
+                !is_log_file(filename, "foo", DEFAULT_EXTENSION),
+                "filename={}",
+                filename
+            );
+        }
+
+        let not_owned_filenames = vec![
+            "foo-20220527.log",
+            "foo-20220527-host1.log",
+            "foo-20220527-host2-1234.log",
+            "foo-20220527-host1-1234.txt",
+            "foo-host1-1234-20220527.log",
+        ];
+        for filename in not_owned_filenames {
+            assert!(!namer.is_owned(filename), "filename={}", filename);
+        }
+        // 別のツールが作成したファイルは、既定の設定のログファイルとして扱わないはず
+        for filename in ["foo-20220527-backup.log", "foo-20220527-.log"] {
+            assert!(
+                !is_log_file(filename, "foo", DEFAULT_EXTENSION),
+                "filename={}",
+                filename
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_log_date() {
+        let date = |year, month, day| Date::from_calendar_date(year, month, day).ok();
+        let cases = [
+            ("foo-20220527.log", date(2022, time::Month::May, 27)),
+            ("foo-20220527.1.txt", date(2022, time::Month::May, 27)),
+            (
+                "foo-20220527T153000.log.gz",
+                date(2022, time::Month::May, 27),
+            ),
+            (" foo-20220527.log", date(2022, time::Month::May, 27)),
+            ("foo-20221231.log", date(2022, time::Month::December, 31)),
+            ("foo-20240229.log", date(2024, time::Month::February, 29)),
+            ("foo-00010101.log", date(1, time::Month::January, 1)),
+            ("foo-99991231.log", date(9999, time::Month::December, 31)),
+        ];
+        for (filename, expected) in cases {
+            assert!(expected.is_some());
+            assert_eq!(parse_log_date(filename, "foo"), expected, "{}", filename);
+        }
+
+        let filenames = [
+            "foo-20230229.log",
+            "foo-20221301.log",
+            "foo-20220100.log",
+            "foo-20220532.log",
+            "foo-2022052.log",
+            "foo-202205270.log",
+            "foo-20220527",
+            "bar-20220527.log",
+            "foo_20220527.log",
+            "foo-20220527.log.part",
+            "foo-20220527-backup.log",
+            "foo-20220527-host1-1234.log.zst",
+            "README",
+        ];
+        for filename in filenames {
+            assert_eq!(parse_log_date(filename, "foo"), None, "{}", filename);
+        }
+        assert_eq!(
+            parse_log_date("20220527.log", ""),
+            date(2022, time::Month::May, 27)
+        );
+    }
+
+    #[test]
+    fn test_is_log_file_with_regex_metacharacters() {
+        // 接頭語に含まれる正規表現のメタ文字は、文字として扱われるはず
+        let cases = vec![
+            (
+                "app.service",
+                "app.service-20220527.log",
+                "appXservice-20220527.log",
+            ),
+            ("app+", "app+-20220527.log", "appp-20220527.log"),
+            ("log(1)", "log(1)-20220527.log", "log1-20220527.log"),
+        ];
+        for (prefix, log_filename, not_log_filename) in cases {
+            assert!(
+                match_log_file(
+                    log_filename,
+                    &log_file_regex(
+                        prefix,
+                        DEFAULT_SEPARATOR,
+                        DEFAULT_EXTENSION,
+                        &DateFormat::from(Rotation::Daily)
+                    )
+                )
+                .is_some(),
+                "filename={}",
+                log_filename
+            );
+            assert!(
+                match_log_file(
+                    not_log_filename,
+                    &log_file_regex(
+                        prefix,
+                        DEFAULT_SEPARATOR,
+                        DEFAULT_EXTENSION,
+                        &DateFormat::from(Rotation::Daily)
+                    )
+                )
+                .is_none(),
+                "filename={}",
+                not_log_filename
+            );
+        }
+
+        // 拡張子の前のピリオドは、任意の文字に一致しないはず
+        assert!(match_log_file(
+            "foo-20220527Xlog",
+            &log_file_regex(
+                "foo",
+                DEFAULT_SEPARATOR,
+                DEFAULT_EXTENSION,
+                &DateFormat::from(Rotation::Daily)
+            )
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_create_daily_log_filename() {
+        let filename_prefix = "foo";
+        let date = today();
+        let month: u8 = date.month().into();
+        let expected = format!(
+            "{}-{:04}{:02}{:02}.log",
+            filename_prefix,
+            date.year(),
+            month,
+            date.day()
+        );
+
+        let path = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &date,
+            0,
+        );
+        assert_eq!(expected, path);
+
+        let expected = format!(
+            "{}-{:04}{:02}{:02}.2.log",
+            filename_prefix,
             date.year(),
             month,
             date.day()
         );
+        let path = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &date,
+            2,
+        );
+        assert_eq!(expected, path);
+    }
+
+    #[test]
+    fn test_cached_log_file_regex() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let prefix = "app.service";
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(prefix)
+            .rotation(Rotation::Hourly)
+            .build()
+            .expect("failed to build appender");
+
+        // アペンダーが保持している正規表現は、毎回作成する正規表現と同じファイルに一致するはず
+        let re = log_file_regex(
+            prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Hourly),
+        );
+        let filenames = vec![
+            "app.service-2022052709.log",
+            "app.service-2022052709.1.log",
+            "app.service-2022052709.log.gz",
+            "app.service-20220527.log",
+            "appXservice-2022052709.log",
+            "app.service-2022052709.txt",
+        ];
+        for filename in filenames {
+            assert_eq!(
+                match_log_file(filename, &appender.inner().log_file_regex),
+                match_log_file(filename, &re),
+                "filename={}",
+                filename
+            );
+        }
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_log_file_sort_key() {
+        let prefix = "foo";
+        let mut filenames = vec![
+            "foo-20220528.log",
+            "foo-20220527.10.log",
+            "foo-20220527.2.log",
+            "foo-20220527.log",
+        ];
+        let date_format = DateFormat::from(Rotation::Daily);
+        let re = log_file_regex(prefix, DEFAULT_SEPARATOR, DEFAULT_EXTENSION, &date_format);
+        filenames.sort_by_cached_key(|name| log_file_sort_key(name, &re, &date_format));
+        assert_eq!(
+            filenames,
+            vec![
+                "foo-20220527.log",
+                "foo-20220527.2.log",
+                "foo-20220527.10.log",
+                "foo-20220528.log",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_date_format() {
+        let date_time = PrimitiveDateTime::new(
+            Date::from_calendar_date(2022, time::Month::May, 27).unwrap(),
+            Time::from_hms(9, 5, 30).unwrap(),
+        )
+        .assume_utc();
+
+        assert_eq!(
+            DateFormat::from(Rotation::Daily).format(&date_time),
+            "20220527"
+        );
+        assert_eq!(
+            DateFormat::from(Rotation::Hourly).format(&date_time),
+            "2022052709"
+        );
+        assert_eq!(
+            DateFormat::from(Rotation::Minutely).format(&date_time),
+            "202205270905"
+        );
+
+        for rotation in [Rotation::Daily, Rotation::Hourly, Rotation::Minutely] {
+            let date_format = DateFormat::from(rotation);
+            let truncated = rotation.truncate(date_time);
+            assert_eq!(
+                date_format.format(&truncated),
+                date_format.format(&date_time)
+            );
+            assert_eq!(truncated.second(), 0);
+            assert_eq!(
+                date_format.parse(&date_format.format(&date_time)),
+                Some(PrimitiveDateTime::new(truncated.date(), truncated.time()))
+            );
+        }
+
+        let date_format = DateFormat::new("[year]-[month]-[day]", Rotation::Daily).unwrap();
+        assert_eq!(date_format.format(&date_time), "2022-05-27");
+        assert_eq!(
+            date_format.parse("2022-05-27"),
+            Some(date_time.date().midnight())
+        );
+        assert_eq!(date_format.parse("20220527"), None);
+    }
+
+    #[test]
+    fn test_invalid_date_format() {
+        let invalid_formats = [
+            // 書式として正しくない
+            ("[year", Rotation::Daily),
+            // ファイル名に使用できない文字を含む
+            ("[year]/[month]/[day]", Rotation::Daily),
+            ("[year]:[month]:[day]", Rotation::Daily),
+            // 日付を特定できない
+            ("[year][month]", Rotation::Daily),
+            ("[year repr:last_two][month][day]", Rotation::Daily),
+            // 固定長の数字でない
+            ("[year][month repr:long][day]", Rotation::Daily),
+            (
+                "[year][month padding:none][day padding:none]",
+                Rotation::Daily,
+            ),
+            // ローテーションする間隔より粗い
+            ("[year][month][day]", Rotation::Hourly),
+            ("[year][month][day][hour]", Rotation::Minutely),
+        ];
+        for (description, rotation) in invalid_formats {
+            let err = DateFormat::new(description, rotation).err();
+            assert_eq!(
+                err.map(|err| err.kind()),
+                Some(io::ErrorKind::InvalidInput),
+                "description={}",
+                description
+            );
+        }
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let result = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .date_format("[year]/[month]/[day]")
+            .build();
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidInput);
+        assert!(find_files(directory.path()).is_empty());
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_remove_old_files_with_custom_date_format() {
+        let prefix = "foo";
+        let date_format = DateFormat::new("[year]-[month]-[day]", Rotation::Daily).unwrap();
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        // 今日の1日前から5日前までのログファイルを作成
+        let today = today();
+        let log_names: Vec<String> = (1..=5)
+            .map(|days| {
+                let date = today - Duration::days(days);
+                create_log_filename(
+                    prefix,
+                    DEFAULT_SEPARATOR,
+                    DEFAULT_EXTENSION,
+                    &date_format,
+                    &date,
+                    0,
+                )
+            })
+            .collect();
+        // 既定の書式のファイルは、このアペンダーのログファイルでない
+        let other = create_log_filename(
+            prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &(today - Duration::days(10)),
+            0,
+        );
+        for filename in log_names.iter().chain([&other]) {
+            File::create(directory.path().join(filename)).unwrap();
+        }
+
+        // 今日のファイルを除いて2つのログファイルを残すアペンダーを作成
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(prefix)
+            .max_count(2)
+            .date_format("[year]-[month]-[day]")
+            .build()
+            .expect("failed to build appender");
+        appender.inner().remove_old_files();
+
+        // 今日と1日前、2日前のファイル、及び既定の書式のファイルが残っているはず
+        let current = create_log_filename(
+            prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &date_format,
+            &today,
+            0,
+        );
+        assert_eq!(appender.current_path(), directory.path().join(&current));
+        let filenames: Vec<_> = find_files(directory.path())
+            .iter()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(filenames.len(), 4);
+        assert!(filenames.contains(&current));
+        for filename in log_names[..2].iter().chain([&other]) {
+            assert!(filenames.contains(filename));
+        }
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_is_log_file_with_hourly_rotation() {
+        let prefix = "foo";
+        assert!(match_log_file(
+            "foo-2022052709.log",
+            &log_file_regex(
+                prefix,
+                DEFAULT_SEPARATOR,
+                DEFAULT_EXTENSION,
+                &DateFormat::from(Rotation::Hourly)
+            )
+        )
+        .is_some());
+        assert!(match_log_file(
+            "foo-2022052709.2.log",
+            &log_file_regex(
+                prefix,
+                DEFAULT_SEPARATOR,
+                DEFAULT_EXTENSION,
+                &DateFormat::from(Rotation::Hourly)
+            )
+        )
+        .is_some());
+        assert!(match_log_file(
+            "foo-20220527.log",
+            &log_file_regex(
+                prefix,
+                DEFAULT_SEPARATOR,
+                DEFAULT_EXTENSION,
+                &DateFormat::from(Rotation::Hourly)
+            )
+        )
+        .is_none());
+        assert!(match_log_file(
+            "foo-202205270905.log",
+            &log_file_regex(
+                prefix,
+                DEFAULT_SEPARATOR,
+                DEFAULT_EXTENSION,
+                &DateFormat::from(Rotation::Hourly)
+            )
+        )
+        .is_none());
+    }
+
+    fn write_to_log(appender: &mut DailyRollingFileAppender, msg: &str) {
+        appender
+            .write_all(msg.as_bytes())
+            .expect("Failed to write to appender");
+        appender.flush().expect("Failed to flush!");
+    }
+
+    fn find_str_in_log_files(dir_path: &Path, expected_value: &str) -> bool {
+        let dir_contents = fs::read_dir(dir_path).expect("Failed to read directory");
+
+        for entry in dir_contents {
+            let path = entry.expect("Expected dir entry").path();
+            let file = fs::read_to_string(&path).expect("Failed to read file");
+
+            if file.as_str() == expected_value {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn create_daily_log_path(directory: &Path, filename: &str) -> String {
+        directory.join(filename).to_str().unwrap().to_string()
+    }
+
+    fn find_str_in_log_file(path: &Path, expected_value: &str) -> bool {
+        let file = fs::read_to_string(path).expect("Failed to read file");
+
+        file.as_str() == expected_value
+    }
+
+    #[test]
+    fn test_write_log() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let mut appender = DailyRollingFileAppender::new(3, directory.path(), "foo");
+
+        let expected_value = "Hello";
+        write_to_log(&mut appender, expected_value);
+        assert!(find_str_in_log_files(directory.path(), expected_value));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_rolling_file() {
+        // 昨日の日付でアペンダーを作成
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let filename_prefix = "foo";
+        let today = today();
+        let yesterday = today + Duration::days(-1);
+        let mut appender =
+            DailyRollingFileAppender::new_test(3, directory.path(), filename_prefix, yesterday);
+
+        // ログを出力
+        let expected_value = "Hello";
+        write_to_log(&mut appender, expected_value);
+
+        // 昨日のログファイルにはログが記録されていないはず
+        let yesterday_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &yesterday,
+            0,
+        );
+        let yesterday_path = create_daily_log_path(directory.path(), &yesterday_name);
+        assert!(find_str_in_log_file(Path::new(&yesterday_path), ""));
+
+        // 今日のログファイルにはログが記録されているはず
+        let today_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            0,
+        );
+        let today_path = create_daily_log_path(directory.path(), &today_name);
+        assert!(find_str_in_log_file(Path::new(&today_path), expected_value));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_rolling_file_by_size() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let filename_prefix = "foo";
+        let today = today();
+        let mut appender =
+            DailyRollingFileAppender::new(3, directory.path(), filename_prefix).with_max_bytes(5);
+
+        // 最大サイズに達するまでは、同じファイルにログが記録されるはず
+        write_to_log(&mut appender, "Hel");
+        write_to_log(&mut appender, "lo");
+        // 最大サイズに達したため、次のログは連番を付けたファイルに記録されるはず
+        write_to_log(&mut appender, "World");
+
+        let first_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            0,
+        );
+        let first_path = create_daily_log_path(directory.path(), &first_name);
+        assert!(find_str_in_log_file(Path::new(&first_path), "Hello"));
+
+        let second_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            1,
+        );
+        let second_path = create_daily_log_path(directory.path(), &second_name);
+        assert!(find_str_in_log_file(Path::new(&second_path), "World"));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_rolling_file_by_size_after_restart() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let clock = MockClock::new(Date::from_calendar_date(2022, time::Month::May, 27).unwrap());
+        let build = || {
+            DailyRollingFileAppender::builder()
+                .directory(directory.path())
+                .filename_prefix("foo")
+                .max_bytes(10)
+                .clock(clock.clone())
+                .build()
+                .expect("failed to build appender")
+        };
+
+        // 最大サイズの手前まで書き込んだ後に再起動
+        let mut appender = build();
+        let first_path = appender.current_path();
+        write_to_log(&mut appender, "Hello");
+        write_to_log(&mut appender, "Wor");
+        drop(appender);
+
+        // 再起動する前に書き込んだサイズを含めて、最大サイズに達したときにローテーションするはず
+        let mut appender = build();
+        assert_eq!(appender.current_path(), first_path);
+        write_to_log(&mut appender, "ld");
+        write_to_log(&mut appender, "!");
+        assert_eq!(fs::read_to_string(&first_path).unwrap(), "HelloWorld");
+        assert_ne!(appender.current_path(), first_path);
+        assert_eq!(fs::read_to_string(appender.current_path()).unwrap(), "!");
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_max_size() {
+        let builder = DailyRollingFileAppender::builder().max_size_kib(512);
+        assert_eq!(builder.max_bytes, Some(512 * 1024));
+        let builder = builder.max_size_mib(10);
+        assert_eq!(builder.max_bytes, Some(10 * 1024 * 1024));
+        let builder = builder.max_size("10MB".parse().unwrap());
+        assert_eq!(builder.max_bytes, Some(10_000_000));
+    }
+
+    #[test]
+    fn test_unique_segments_after_restart() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let path = |index| {
+            directory
+                .path()
+                .join(create_daily_log_filename("foo", "log", date, index))
+        };
+        for index in 0..3 {
+            fs::write(path(index), "Old").unwrap();
+        }
+        let build = |open_mode| {
+            DailyRollingFileAppender::builder()
+                .directory(directory.path())
+                .filename_prefix("foo")
+                .max_bytes(100)
+                .unique_segments(true)
+                .open_mode(open_mode)
+                .current_link(cfg!(unix))
+                .clock(MockClock::new(date))
+                .build()
+                .expect("failed to build appender")
+        };
+
+        // 追記する場合は、最も新しいファイルから記録を再開するはず
+        let mut appender = build(OpenMode::Append);
+        assert_eq!(appender.current_path(), path(2));
+        write_to_log(&mut appender, "Hello");
+        drop(appender);
+        assert!(find_str_in_log_file(&path(2), "OldHello"));
+
+        // 空にする場合は、既に存在するファイルを空にせず、次のファイルから記録を再開し、
+        // シンボリックリンクはそのファイルを指すはず
+        let mut appender = build(OpenMode::Truncate);
+        assert_eq!(appender.current_path(), path(3));
+        #[cfg(unix)]
+        assert_eq!(
+            fs::read_link(directory.path().join("foo-current.log")).unwrap(),
+            Path::new(path(3).file_name().unwrap())
+        );
+        write_to_log(&mut appender, "World");
+        drop(appender);
+        assert!(find_str_in_log_file(&path(2), "OldHello"));
+        assert!(find_str_in_log_file(&path(3), "World"));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_retention_of_same_date_segments() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let max_count = 8;
+        let path = |index| {
+            directory
+                .path()
+                .join(create_daily_log_filename("foo", "log", date, index))
+        };
+        // 連番が2桁になり、名前の順では`.10.log`が`.2.log`より前になるファイルを作成
+        for index in 0..max_count + 3 {
+            fs::write(path(index), "Old").unwrap();
+        }
+
+        let appender = DailyRollingFileAppender::builder()
+            .max_count(max_count)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .max_bytes(100)
+            .unique_segments(true)
+            .open_mode(OpenMode::Truncate)
+            .clock(MockClock::new(date))
+            .build()
+            .expect("failed to build appender");
+
+        // 連番を数値として比較して、最も古い3つのファイルのみを削除するはず
+        assert_eq!(appender.current_path(), path(max_count + 3));
+        for index in 0..3 {
+            assert!(!path(index).exists(), "index={}", index);
+        }
+        for index in 3..=max_count + 3 {
+            assert!(path(index).exists(), "index={}", index);
+        }
+        drop(appender);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_removal_strategy_move_to() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let name = |days| create_daily_log_filename("foo", "log", date - Duration::days(days), 0);
+        let trash = directory.path().join(".trash");
+        for days in 1..=5 {
+            fs::write(directory.path().join(name(days)), format!("Old {}", days)).unwrap();
+        }
+        // 移動先に同じ名前のファイルを作成
+        fs::create_dir(&trash).unwrap();
+        fs::write(trash.join(name(5)), "Trashed").unwrap();
+
+        let appender = DailyRollingFileAppender::builder()
+            .max_count(2)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .removal_strategy(RemovalStrategy::MoveTo(".trash".into()))
+            .clock(MockClock::new(date))
+            .build()
+            .expect("failed to build appender");
+
+        // 保存する基準を超えたファイルは、削除せずに移動先へ移動し、同じ名前のファイルを上書きしないはず
+        let mut trashed: Vec<_> = find_files(&trash)
+            .iter()
+            .map(|entry| entry.file_name().into_string().unwrap())
+            .collect();
+        trashed.sort();
+        let mut expected = vec![name(3), name(4), name(5), format!("{}.1", name(5))];
+        expected.sort();
+        assert_eq!(trashed, expected);
+        assert!(find_str_in_log_file(&trash.join(name(5)), "Trashed"));
+        assert!(find_str_in_log_file(
+            &trash.join(format!("{}.1", name(5))),
+            "Old 5"
+        ));
+
+        // 移動したファイルは保存する基準に含めず、再度削除しても移動しないはず
+        assert_eq!(appender.list_files().unwrap().len(), 3);
+        appender.enforce_retention_now().unwrap();
+        assert_eq!(appender.list_files().unwrap().len(), 3);
+        assert_eq!(find_files(&trash).len(), 4);
+        drop(appender);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_roll_on_start() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let path = |index| {
+            directory
+                .path()
+                .join(create_daily_log_filename("foo", "log", date, index))
+        };
+        let build = || {
+            DailyRollingFileAppender::builder()
+                .max_count(1)
+                .directory(directory.path())
+                .filename_prefix("foo")
+                .roll_on_start(true)
+                .current_link(cfg!(unix))
+                .clock(MockClock::new(date))
+                .build()
+                .expect("failed to build appender")
+        };
+
+        // 同じ日に2回起動すると、起動ごとに別のファイルに記録し、シンボリックリンクはそのファイルを
+        // 指すはず
+        let mut appender = build();
+        assert_eq!(appender.current_path(), path(0));
+        write_to_log(&mut appender, "First");
+        drop(appender);
+        let mut appender = build();
+        assert_eq!(appender.current_path(), path(1));
+        #[cfg(unix)]
+        assert_eq!(
+            fs::read_link(directory.path().join("foo-current.log")).unwrap(),
+            Path::new(path(1).file_name().unwrap())
+        );
+        write_to_log(&mut appender, "Second");
+        drop(appender);
+        assert!(find_str_in_log_file(&path(0), "First"));
+        assert!(find_str_in_log_file(&path(1), "Second"));
+
+        // 保存するファイルの最大数を超えた古い実行のファイルは削除し、直前の実行のファイルは残すはず
+        let appender = build();
+        assert_eq!(appender.current_path(), path(2));
+        assert!(!path(0).exists());
+        assert!(find_str_in_log_file(&path(1), "Second"));
+        drop(appender);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_roll_on_start_after_crash_with_part_files() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let path = |index| {
+            directory
+                .path()
+                .join(create_daily_log_filename("foo", "log", date, index))
+        };
+        let part_path = |index| {
+            let mut path = path(index);
+            path.as_mut_os_string().push(".part");
+            path
+        };
+        // 前回の実行が、`.part`ファイルを閉じずに終了した
+        fs::write(part_path(0), "First").unwrap();
+
+        // 前回の実行の`.part`ファイルに追記せず、次のファイルから記録を開始するはず
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .roll_on_start(true)
+            .part_files(true)
+            .clock(MockClock::new(date))
+            .build()
+            .expect("failed to build appender");
+        assert_eq!(appender.current_path(), part_path(1));
+        write_to_log(&mut appender, "Second");
+        assert!(find_str_in_log_file(&part_path(0), "First"));
+        assert!(find_str_in_log_file(&part_path(1), "Second"));
+        drop(appender);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_unique_segments_under_concurrent_size_rollover() {
+        use tracing_subscriber::fmt::writer::MakeWriter;
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        // 時計を止めて、すべてのローテーションが1秒以内に行われる状況にする
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .max_bytes(10)
+            .max_count(0)
+            .size_suffix(SizeSuffix::Time)
+            .unique_segments(true)
+            .clock(MockClock::new(date))
+            .build()
+            .expect("failed to build appender");
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..50 {
+                        let mut writer = appender.make_writer();
+                        writer.write_all(b"message\n").unwrap();
+                    }
+                });
+            }
+        });
+        let rollover_count = appender.stats().rollover_count;
+        drop(appender);
+
+        // ローテーションするたびに異なるファイルに切り替えて、すべての記録が残っているはず
+        let files = find_files(directory.path());
+        assert_eq!(files.len() as u64, rollover_count + 1);
+        let contents = files
+            .iter()
+            .map(|file| fs::read_to_string(file.path()).unwrap())
+            .collect::<Vec<_>>();
+        // 最大サイズを確認した後に、他のスレッドが書き込む可能性があるため、スレッドの数だけ超える
+        assert!(contents.iter().all(|content| content.len() <= 10 + 8 * 8));
+        assert_eq!(
+            contents.iter().map(|c| c.lines().count()).sum::<usize>(),
+            8 * 50
+        );
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_builder() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let mut appender = DailyRollingFileAppender::builder()
+            .max_count(3)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .max_bytes(5)
+            .build()
+            .expect("failed to build appender");
+        assert_eq!(appender.inner().retention(), Retention::Count(3));
+        assert_eq!(appender.inner().max_bytes(), Some(5));
+
+        let expected_value = "Hello";
+        write_to_log(&mut appender, expected_value);
+        let name = create_log_filename(
+            "foo",
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today(),
+            0,
+        );
+        let path = create_daily_log_path(directory.path(), &name);
+        assert!(find_str_in_log_file(Path::new(&path), expected_value));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_relative_directory() {
+        /// 破棄したときに、作成したときのカレントディレクトリに戻すガード。
+        ///
+        /// カレントディレクトリはプロセス全体で共有するため、テストが失敗しても他のテストに
+        /// 影響しないように、必ず元に戻す。
+        struct CurrentDirGuard(PathBuf);
+
+        impl Drop for CurrentDirGuard {
+            fn drop(&mut self) {
+                let _ = env::set_current_dir(&self.0);
+            }
+        }
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let other = tempfile::tempdir().expect("failed to create temp dir");
+        let guard = CurrentDirGuard(env::current_dir().unwrap());
+
+        // 相対パスでアペンダーを作成した後に、カレントディレクトリを変更してローテーション
+        env::set_current_dir(directory.path()).unwrap();
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory("logs")
+            .filename_prefix("foo")
+            .max_bytes(5)
+            .build()
+            .expect("failed to build appender");
+        env::set_current_dir(other.path()).unwrap();
+        write_to_log(&mut appender, "Hello");
+        write_to_log(&mut appender, "World");
+        drop(guard);
+
+        // すべてのファイルが、アペンダーを作成したときのディレクトリに作成されているはず
+        let logs = directory.path().join("logs");
+        assert_eq!(appender.current_path().parent(), Some(logs.as_path()));
+        assert_eq!(find_files(&logs).len(), 2);
+        assert!(find_str_in_log_files(&logs, "Hello"));
+        assert!(find_str_in_log_files(&logs, "World"));
+        assert_eq!(fs::read_dir(other.path()).unwrap().count(), 0);
+
+        other
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.");
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_builder_without_required_settings() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+
+        // ディレクトリを設定していない
+        let result = DailyRollingFileAppender::builder()
+            .filename_prefix("foo")
+            .build();
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidInput);
+
+        // ファイル名の接頭語を設定していない
+        let result = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .build();
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidInput);
+
+        // ファイルが作成されていないことを確認
+        assert!(find_files(directory.path()).is_empty());
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_permission_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let log_directory = directory.path().join("logs");
+        let appender = DailyRollingFileAppender::builder()
+            .directory(&log_directory)
+            .filename_prefix("foo")
+            .file_system(OsFileSystem::new().file_mode(0o640).directory_mode(0o750))
+            .build()
+            .expect("failed to build appender");
+
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&log_directory), 0o750);
+        assert_eq!(mode(&appender.current_path()), 0o640);
+    }
+
+    #[test]
+    fn test_try_new_with_unwritable_directory() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        // ディレクトリと同じ名前のファイルが存在するため、ディレクトリを作成できない
+        let file_path = directory.path().join("bar");
+        File::create(&file_path).unwrap();
+
+        let result = DailyRollingFileAppender::try_new(3, &file_path, "foo");
+        assert!(result.is_err());
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_create_writer_with_permission_denied() {
+        use crate::file_system::MemoryFileSystem;
+
+        // ファイルを開くと常に`PermissionDenied`エラーとなり、呼び出された操作を記録するファイルシステム
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let create_dir_calls = Arc::clone(&calls);
+        let open_calls = Arc::clone(&calls);
+        let file_system = HookedFileSystem::new(MemoryFileSystem::new())
+            .on_create_dir_all(move |inner, path| {
+                create_dir_calls.lock().unwrap().push("create_dir_all");
+                inner.create_dir_all(path)
+            })
+            .on_open(move |_, _, open_mode| {
+                open_calls.lock().unwrap().push(match open_mode {
+                    OpenMode::Append => "append",
+                    OpenMode::Truncate => "create",
+                });
+                Err(io::Error::from(io::ErrorKind::PermissionDenied))
+            });
+
+        // ディレクトリを作成してからファイルを開き、ファイルを開けなかったエラーを返却するはず
+        let result = create_writer(
+            &file_system,
+            Path::new("/logs/foo-20220527.log"),
+            OpenMode::Append,
+            None,
+            &today(),
+            DEFAULT_OPEN_ATTEMPTS,
+        );
+        assert_eq!(
+            result.err().unwrap().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+        assert_eq!(*calls.lock().unwrap(), vec!["create_dir_all", "append"]);
+    }
+
+    #[test]
+    fn test_create_writer_with_retryable_error() {
+        use crate::file_system::MemoryFileSystem;
+
+        // ファイルを開くと、指定した回数だけ`WouldBlock`エラーとなるファイルシステム
+        let memory = MemoryFileSystem::new();
+        let failures = Arc::new(AtomicUsize::new(2));
+        let remaining = Arc::clone(&failures);
+        let file_system =
+            HookedFileSystem::new(memory.clone()).on_open(move |inner, path, open_mode| {
+                match open_mode == OpenMode::Append
+                    && remaining
+                        .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1))
+                        .is_ok()
+                {
+                    true => Err(io::Error::from(io::ErrorKind::WouldBlock)),
+                    false => open_writer(inner, path, open_mode),
+                }
+            });
+
+        // 2回失敗した後に開けるようになれば、3回目に開いたファイルに記録するはず
+        let path = Path::new("/logs/foo-20220527.log");
+        let (mut file, _) =
+            create_writer(&file_system, path, OpenMode::Append, None, &today(), 3).unwrap();
+        file.write_all(b"Hello").unwrap();
+        assert_eq!(failures.load(Ordering::Acquire), 0);
+        assert_eq!(memory.read(path).unwrap(), b"Hello");
+
+        // 試みる回数を超えて失敗した場合は、エラーを返却するはず
+        failures.store(2, Ordering::Release);
+        let result = create_writer(&file_system, path, OpenMode::Append, None, &today(), 2);
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::WouldBlock);
+    }
+
+    #[test]
+    fn test_stderr_fallback() {
+        use crate::file_system::MemoryFileSystem;
+
+        let file_system = MemoryFileSystem::new();
+        let writable = Arc::new(AtomicBool::new(false));
+        let clock = MockClock::new(Date::from_calendar_date(2022, time::Month::May, 27).unwrap());
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let errors_in_hook = Arc::clone(&errors);
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory("/fallback/logs")
+            .filename_prefix("foo")
+            .stderr_fallback(true)
+            .clock(clock.clone())
+            .file_system(HookedFileSystem::new(file_system.clone()).on_open({
+                // 書き込めるようにするまで、ファイルを開くと`PermissionDenied`エラーとなる
+                let writable = Arc::clone(&writable);
+                move |inner, path, open_mode| match writable.load(Ordering::Acquire) {
+                    true => open_writer(inner, path, open_mode),
+                    false => Err(io::Error::from(io::ErrorKind::PermissionDenied)),
+                }
+            }))
+            .on_error(move |err| {
+                let create_writer = matches!(err, AppenderError::CreateWriter { .. });
+                errors_in_hook
+                    .lock()
+                    .unwrap()
+                    .push((create_writer, err.kind()))
+            })
+            .build()
+            .expect("failed to build appender");
+
+        // 構築したときに、標準エラー出力に切り替えたことがエラーを報告する関数に通知されるはず
+        assert_eq!(
+            *errors.lock().unwrap(),
+            vec![(true, io::ErrorKind::PermissionDenied)]
+        );
+
+        // ファイルを作成できない間は、標準エラー出力に記録するはず
+        write_to_log(&mut appender, "Hello\n");
+        assert!(file_system.paths().is_empty());
+        assert_eq!(errors.lock().unwrap().len(), 2);
+        assert_eq!(errors.lock().unwrap()[1].1, io::ErrorKind::PermissionDenied);
+
+        // 書き込めるようになっても、再試行する時期まではファイルを作成しないはず
+        writable.store(true, Ordering::Release);
+        write_to_log(&mut appender, "Again\n");
+        assert!(file_system.paths().is_empty());
+
+        // 再試行する時期になったらファイルを作成して、以降のログをファイルに記録するはず
+        clock.advance(Duration::seconds(1));
+        write_to_log(&mut appender, "World\n");
+        write_to_log(&mut appender, "!\n");
+        let path = appender.current_path();
+        assert_eq!(file_system.paths(), vec![path.clone()]);
+        assert_eq!(file_system.read(&path).unwrap(), b"World\n!\n");
+        assert_eq!(errors.lock().unwrap().len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_try_new_with_non_utf8_prefix() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let prefix = OsStr::from_bytes(&[0x66, 0x6f, 0x80]);
+
+        let result = DailyRollingFileAppender::try_new(3, directory.path(), prefix);
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidInput);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_separator() {
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        for separator in ['_', '.'] {
+            let directory = tempfile::tempdir().expect("failed to create temp dir");
+            // 他の区切り文字のファイルは、このアペンダーのファイルとして扱わない
+            let other = create_log_filename(
+                "foo",
+                DEFAULT_SEPARATOR,
+                DEFAULT_EXTENSION,
+                &DateFormat::from(Rotation::Daily),
+                &start_of_day(date - Duration::days(3)),
+                0,
+            );
+            File::create(directory.path().join(&other)).unwrap();
+            let old = format!("foo{}20220524.log", separator);
+            File::create(directory.path().join(&old)).unwrap();
+
+            let clock = MockClock::new(date);
+            let mut appender = DailyRollingFileAppender::builder()
+                .max_count(1)
+                .directory(directory.path())
+                .filename_prefix("foo")
+                .separator(separator)
+                .clock(clock.clone())
+                .build()
+                .expect("failed to build appender");
+            assert_eq!(
+                appender.current_path(),
+                directory
+                    .path()
+                    .join(format!("foo{}20220527.log", separator))
+            );
+
+            write_to_log(&mut appender, "Hello");
+            clock.advance(Duration::days(1));
+            write_to_log(&mut appender, "World");
+
+            let mut filenames: Vec<String> = find_files(directory.path())
+                .iter()
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .collect();
+            filenames.sort();
+            let mut expected = vec![
+                other.clone(),
+                format!("foo{}20220527.log", separator),
+                format!("foo{}20220528.log", separator),
+            ];
+            expected.sort();
+            assert_eq!(filenames, expected, "{:?}", separator);
+        }
+    }
+
+    #[test]
+    fn test_invalid_separator() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        for separator in ['/', '\\', '1'] {
+            let result = DailyRollingFileAppender::builder()
+                .directory(directory.path())
+                .filename_prefix("foo")
+                .separator(separator)
+                .build();
+            assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidInput);
+        }
+        assert_eq!(find_files(directory.path()).len(), 0);
+    }
+
+    #[test]
+    fn test_invalid_filename_prefix() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let long_prefix = "a".repeat(MAX_FILENAME_LEN);
+        for prefix in ["foo/bar", "foo\\bar", "foo\0bar", &long_prefix] {
+            let result = DailyRollingFileAppender::try_new(1, directory.path(), prefix);
+            assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidInput);
+        }
+        assert_eq!(find_files(directory.path()).len(), 0);
+    }
+
+    #[test]
+    fn test_custom_extension() {
+        let prefix = "foo";
+        let extension = "jsonl";
+
+        assert!(match_log_file(
+            "foo-20220527.jsonl",
+            &log_file_regex(
+                prefix,
+                DEFAULT_SEPARATOR,
+                extension,
+                &DateFormat::from(Rotation::Daily)
+            )
+        )
+        .is_some());
+        assert!(match_log_file(
+            "foo-20220527.1.jsonl",
+            &log_file_regex(
+                prefix,
+                DEFAULT_SEPARATOR,
+                extension,
+                &DateFormat::from(Rotation::Daily)
+            )
+        )
+        .is_some());
+        assert!(match_log_file(
+            "foo-20220527.log",
+            &log_file_regex(
+                prefix,
+                DEFAULT_SEPARATOR,
+                extension,
+                &DateFormat::from(Rotation::Daily)
+            )
+        )
+        .is_none());
+        assert!(match_log_file(
+            "foo-20220527.jsonl.gz",
+            &log_file_regex(
+                prefix,
+                DEFAULT_SEPARATOR,
+                extension,
+                &DateFormat::from(Rotation::Daily)
+            )
+        )
+        .is_some());
+        assert!(match_log_file(
+            "foo-20220527.jsonl.zip",
+            &log_file_regex(
+                prefix,
+                DEFAULT_SEPARATOR,
+                extension,
+                &DateFormat::from(Rotation::Daily)
+            )
+        )
+        .is_none());
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(prefix)
+            .extension(".jsonl")
+            .build()
+            .expect("failed to build appender");
+
+        let expected_value = "Hello";
+        write_to_log(&mut appender, expected_value);
+        let name = create_log_filename(
+            prefix,
+            DEFAULT_SEPARATOR,
+            extension,
+            &DateFormat::from(Rotation::Daily),
+            &today(),
+            0,
+        );
+        let path = create_daily_log_path(directory.path(), &name);
+        assert!(find_str_in_log_file(Path::new(&path), expected_value));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_layer() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .build()
+            .expect("failed to build appender");
+        let path = appender.current_path();
+
+        let subscriber = tracing_subscriber::registry().with(appender.layer());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("Hello, layer");
+        });
+
+        let contents = fs::read_to_string(path).unwrap();
+        assert!(contents.contains("INFO"));
+        assert!(contents.contains("Hello, layer"));
+        assert!(!contents.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_whole_records() {
+        use tracing_subscriber::fmt::writer::MakeWriter;
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .max_bytes(20)
+            .atomic_records(true)
+            .whole_records(true)
+            .build()
+            .expect("failed to build appender");
+
+        // 最大サイズの手前まで書き込んだ後、最大サイズを超えるレコードを書き込む
+        write_to_log(&mut appender, "{\"a\":1}\n");
+        write_to_log(&mut appender, "{\"a\":22}\n");
+        write_to_log(&mut appender, "{\"a\":333}\n");
+        // ライターに1つのレコードを分けて書き込む
+        for i in 0..3 {
+            let mut writer = appender.make_writer();
+            write!(writer, "{{\"b\":").unwrap();
+            writeln!(writer, "{}}}", i).unwrap();
+        }
+
+        // 各ファイルには最大サイズを超えない範囲で、分割されていないレコードのみが記録されるはず
+        let re = Regex::new(r#"^\{"[ab]":\d+\}$"#).unwrap();
+        let mut contents: Vec<_> = find_files(directory.path())
+            .iter()
+            .map(|entry| fs::read_to_string(entry.path()).unwrap())
+            .collect();
+        contents.sort();
+        assert_eq!(
+            contents,
+            vec![
+                "{\"a\":1}\n{\"a\":22}\n",
+                "{\"a\":333}\n{\"b\":0}\n",
+                "{\"b\":1}\n{\"b\":2}\n"
+            ]
+        );
+        for contents in &contents {
+            assert!(contents.len() <= 20);
+            assert!(contents.ends_with('\n'));
+            assert!(contents.lines().all(|line| re.is_match(line)));
+        }
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_atomic_records() {
+        use tracing_subscriber::fmt::writer::MakeWriter;
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .atomic_records(true)
+            .build()
+            .expect("failed to build appender");
+
+        // 1つのレコードを複数回に分けて書き込む
+        thread::scope(|scope| {
+            for i in 0..8 {
+                let appender = &appender;
+                scope.spawn(move || {
+                    for j in 0..100 {
+                        let mut writer = appender.make_writer();
+                        write!(writer, "thread-{}", i).unwrap();
+                        thread::yield_now();
+                        writeln!(writer, " line-{}", j).unwrap();
+                    }
+                });
+            }
+        });
+
+        let contents = fs::read_to_string(appender.current_path()).unwrap();
+        let re = Regex::new(r"^thread-\d line-\d+$").unwrap();
+        assert_eq!(contents.lines().count(), 800);
+        assert!(contents.lines().all(|line| re.is_match(line)));
+    }
+
+    #[test]
+    fn test_write_through_shared_reference() {
+        use tracing_subscriber::fmt::writer::MakeWriter;
+
+        fn write_shared(mut writer: impl Write, s: &str) {
+            writer.write_all(s.as_bytes()).unwrap();
+            writer.flush().unwrap();
+        }
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        for (prefix, atomic_records) in [("foo", false), ("bar", true)] {
+            let appender = DailyRollingFileAppender::builder()
+                .directory(directory.path())
+                .filename_prefix(prefix)
+                .atomic_records(atomic_records)
+                .build()
+                .expect("failed to build appender");
+            let writer = appender.make_writer();
+
+            // 共有参照からも、所有している場合と同じファイルに書き込めるはず
+            write_shared(&writer, "Hello");
+            write_shared(&writer, " World");
+            drop(writer);
+            assert!(find_str_in_log_file(
+                &appender.current_path(),
+                "Hello World"
+            ));
+        }
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_list_files() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let filenames = [
+            "foo-20220525.log.gz",
+            "foo-20220526.log",
+            "foo-20220526.1.log",
+            "foo-20220526.10.log",
+            "foo-20220526.2.log",
+            "bar-20220526.log",
+            "foo-unknown.log",
+        ];
+        for filename in filenames {
+            File::create(directory.path().join(filename)).unwrap();
+        }
+        let appender = DailyRollingFileAppender::builder()
+            .max_count(10)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(MockClock::new(date))
+            .build()
+            .expect("failed to build appender");
+
+        let expected: Vec<PathBuf> = [
+            "foo-20220527.log",
+            "foo-20220526.10.log",
+            "foo-20220526.2.log",
+            "foo-20220526.1.log",
+            "foo-20220526.log",
+            "foo-20220525.log.gz",
+        ]
+        .iter()
+        .map(|name| directory.path().join(name))
+        .collect();
+        assert_eq!(appender.list_files().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_managed_files() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let filenames = [
+            ("foo-20220525.log", "Hello"),
+            ("foo-20220526.log", "World"),
+            ("bar-20220526.log", "Other"),
+            ("foo-unknown.log", "Other"),
+        ];
+        for (filename, contents) in filenames {
+            fs::write(directory.path().join(filename), contents).unwrap();
+        }
+        let mut appender = DailyRollingFileAppender::builder()
+            .max_count(10)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(MockClock::new(date))
+            .build()
+            .expect("failed to build appender");
+        write_to_log(&mut appender, "!");
+
+        // ログファイルでないファイルは含まれないはず
+        assert_eq!(appender.managed_file_count().unwrap(), 3);
+        assert_eq!(appender.managed_total_bytes().unwrap(), 11);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send<T: Send>() {}
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        // 共有して使用する型は、コンパイル時に`Send`かつ`Sync`であることを確認する
+        assert_send_sync::<DailyRollingFileAppender>();
+        assert_send_sync::<DefaultNamer>();
+        assert_send_sync::<crate::file_system::OsFileSystem>();
+        assert_send_sync::<crate::file_system::MemoryFileSystem>();
+        assert_send::<DailyRollingFileAppenderBuilder>();
+        assert_send::<RollingWriter<'static>>();
+    }
+
+    #[test]
+    fn test_debug() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let appender = DailyRollingFileAppender::builder()
+            .max_count(3)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(MockClock::new(date))
+            .build()
+            .expect("failed to build appender");
+
+        let debug = format!("{:?}", appender);
+        assert!(debug.starts_with("DailyRollingFileAppender {"));
+        assert!(debug.contains(&format!("{:?}", directory.path())));
+        assert!(debug.contains(r#"filename_prefix: "foo""#));
+        assert!(debug.contains("retention: Count(3)"));
+        assert!(debug.contains("current_date: 2022-05-27 0:00:00.0 +00:00:00"));
+        assert!(format!("{:?}", appender.inner()).starts_with("Inner {"));
+    }
+
+    #[test]
+    fn test_write_after_long_idle() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let name = |days| {
+            create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                0,
+            )
+        };
+        for days in 1..=3 {
+            File::create(directory.path().join(name(-days))).unwrap();
+        }
+        let clock = MockClock::new(first_date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .max_count(2)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+
+        // 10日間書き込まずに、日付が変わった後に1回だけ書き込む
+        write_to_log(&mut appender, "Hello");
+        clock.advance(Duration::days(10));
+        write_to_log(&mut appender, "World");
+
+        // 間の日付のファイルは作成されず、現在の日付のファイルと保存する最大数のファイルだけが
+        // 残るはず
+        let mut files: Vec<_> = find_files(directory.path())
+            .into_iter()
+            .map(|entry| entry.path())
+            .collect();
+        files.sort();
+        let expected: Vec<_> = [-1, 0, 10]
+            .into_iter()
+            .map(|days| directory.path().join(name(days)))
+            .collect();
+        assert_eq!(files, expected);
+        assert!(find_str_in_log_file(&expected[1], "Hello"));
+        assert!(find_str_in_log_file(&expected[2], "World"));
+        assert_eq!(appender.stats().rollover_count, 1);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_rolling_file_with_mock_clock() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let filename_prefix = "foo";
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let clock = MockClock::new(first_date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(filename_prefix)
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+
+        write_to_log(&mut appender, "Hello");
+        clock.advance(Duration::days(1));
+        write_to_log(&mut appender, "World");
+
+        let first_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &start_of_day(first_date),
+            0,
+        );
+        let first_path = create_daily_log_path(directory.path(), &first_name);
+        assert!(find_str_in_log_file(Path::new(&first_path), "Hello"));
+
+        let second_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &start_of_day(first_date + Duration::days(1)),
+            0,
+        );
+        let second_path = create_daily_log_path(directory.path(), &second_name);
+        assert!(find_str_in_log_file(Path::new(&second_path), "World"));
+        assert_eq!(find_files(directory.path()).len(), 2);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_set_max_count() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let name = |days| {
+            create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                0,
+            )
+        };
+        for days in 1..=5 {
+            File::create(directory.path().join(name(-days))).unwrap();
+        }
+        let clock = MockClock::new(first_date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .max_count(5)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+        assert_eq!(find_files(directory.path()).len(), 6);
+
+        // 最大数を減らすと、直ちに超えた古いファイルが削除されるはず
+        appender.set_max_count(2);
+        assert_eq!(
+            appender.list_files().unwrap(),
+            vec![
+                appender.current_path(),
+                directory.path().join(name(-1)),
+                directory.path().join(name(-2)),
+            ]
+        );
+
+        // 以降のローテーションでも、新しい最大数に従って削除されるはず
+        clock.advance(Duration::days(1));
+        write_to_log(&mut appender, "Hello");
+        assert_eq!(find_files(directory.path()).len(), 3);
+        assert!(!directory.path().join(name(-2)).exists());
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_read_recent() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .build()
+            .expect("failed to build appender");
+        write_to_log(&mut appender, "1\n2\n3\r\n4\n5\n6");
+
+        // 改行で終わっていない最後の行は含まれないはず
+        assert_eq!(appender.read_recent(3).unwrap(), ["3", "4", "5"]);
+        assert_eq!(appender.read_recent(10).unwrap(), ["1", "2", "3", "4", "5"]);
+        assert!(appender.read_recent(0).unwrap().is_empty());
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_part_files() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let path = |days| {
+            directory.path().join(create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                0,
+            ))
+        };
+        let part_path = |days| {
+            let mut path = path(days);
+            path.as_mut_os_string().push(".part");
+            path
+        };
+        let clock = MockClock::new(first_date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .max_count(1)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .part_files(true)
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+
+        // 書き込んでいる間は、`.part`ファイルに記録するはず
+        write_to_log(&mut appender, "Hello");
+        assert_eq!(appender.current_path(), part_path(0));
+        assert!(find_str_in_log_file(&part_path(0), "Hello"));
+        assert!(!path(0).exists());
+
+        // ローテーションしたときに、ログファイルの名前に変更するはず
+        clock.advance(Duration::days(1));
+        write_to_log(&mut appender, "World");
+        assert!(!part_path(0).exists());
+        assert!(find_str_in_log_file(&path(0), "Hello"));
+        assert!(find_str_in_log_file(&part_path(1), "World"));
+
+        // `.part`ファイルは保存するファイルの数に含まれないはず
+        clock.advance(Duration::days(1));
+        write_to_log(&mut appender, "!");
+        let mut files: Vec<_> = find_files(directory.path())
+            .into_iter()
+            .map(|entry| entry.path())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec![path(1), part_path(2)]);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_took_rollover() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let clock = MockClock::new(Date::from_calendar_date(2022, time::Month::May, 27).unwrap());
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+
+        write_to_log(&mut appender, "Hello");
+        assert!(!appender.took_rollover());
+
+        clock.advance(Duration::days(1));
+        write_to_log(&mut appender, "World");
+        assert!(appender.took_rollover());
+        // 記録は消去されるはず
+        assert!(!appender.took_rollover());
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_force_rollover() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let name = |days, index| {
+            create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                index,
+            )
+        };
+        let mut appender = DailyRollingFileAppender::builder()
+            .max_count(1)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(MockClock::new(first_date))
+            .build()
+            .expect("failed to build appender");
+        // 保存するファイルの最大数を超える古いファイルを、構築した後に作成
+        for days in 1..=3 {
+            File::create(directory.path().join(name(-days, 0))).unwrap();
+        }
+
+        write_to_log(&mut appender, "Hello");
+        appender.force_rollover().unwrap();
+        write_to_log(&mut appender, "World");
+
+        // 同じ日付で接尾辞が異なるファイルにローテーションし、古いファイルが削除されるはず
+        let first = directory.path().join(name(0, 0));
+        let second = directory.path().join(name(0, 1));
+        assert_eq!(appender.current_path(), second);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "Hello");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "World");
+        assert_eq!(appender.stats().rollover_count, 1);
+        assert_eq!(find_files(directory.path()).len(), 2);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_reopen() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let name = |days| {
+            create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                0,
+            )
+        };
+        let mut appender = DailyRollingFileAppender::builder()
+            .max_count(1)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(MockClock::new(first_date))
+            .build()
+            .expect("failed to build appender");
+        // 保存するファイルの最大数を超える古いファイルを、構築した後に作成
+        for days in 1..=3 {
+            File::create(directory.path().join(name(-days))).unwrap();
+        }
+
+        // logrotateと同様に、ログを記録しているファイルの名前を変更してから開き直す
+        write_to_log(&mut appender, "Hello");
+        let current = appender.current_path();
+        let rotated = directory.path().join("rotated.log");
+        fs::rename(&current, &rotated).unwrap();
+        appender.reopen().unwrap();
+        write_to_log(&mut appender, "World");
+
+        assert_eq!(fs::read_to_string(&rotated).unwrap(), "Hello");
+        assert_eq!(fs::read_to_string(&current).unwrap(), "World");
+        // ファイルの日付は変わらず、古いファイルも削除されないはず
+        assert_eq!(appender.current_path(), current);
+        assert_eq!(appender.stats().rollover_count, 0);
+        assert_eq!(find_files(directory.path()).len(), 5);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[cfg(all(feature = "process_lock", feature = "gzip"))]
+    #[test]
+    fn test_process_lock() {
+        use flate2::read::MultiGzDecoder;
+        use std::io::Read;
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let name = |days| {
+            create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                0,
+            )
+        };
+        // 同じディレクトリにログを記録する2つのプロセスを、2つのアペンダーで再現する
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let build = |clock: &MockClock| {
+            let errors = Arc::clone(&errors);
+            DailyRollingFileAppender::builder()
+                .directory(directory.path())
+                .filename_prefix("foo")
+                .compress(true)
+                .process_lock(true)
+                .directory_lock(false)
+                .clock(clock.clone())
+                .on_error(move |err| errors.lock().unwrap().push(err.to_string()))
+                .build()
+                .expect("failed to build appender")
+        };
+        let (first_clock, second_clock) = (MockClock::new(first_date), MockClock::new(first_date));
+        let mut first = build(&first_clock);
+        let mut second = build(&second_clock);
+        assert!(directory.path().join("foo.lock").exists());
+
+        write_to_log(&mut first, "Hello ");
+        write_to_log(&mut second, "World ");
+        first_clock.advance(Duration::days(1));
+        second_clock.advance(Duration::days(1));
+        write_to_log(&mut first, "Hello ");
+        write_to_log(&mut second, "World ");
+
+        // 先にローテーションしたプロセスのみが、閉じたファイルを圧縮するはず
+        let compressed_path = directory.path().join(format!("{}.gz", name(0)));
+        let mut decoder = MultiGzDecoder::new(File::open(compressed_path).unwrap());
+        let mut compressed = String::new();
+        decoder.read_to_string(&mut compressed).unwrap();
+        assert_eq!(compressed, "Hello World ");
+        assert!(!directory.path().join(name(0)).exists());
+        assert_eq!(
+            fs::read_to_string(directory.path().join(name(1))).unwrap(),
+            "Hello World "
+        );
+        assert_eq!(second.stats().rollover_count, 1);
+        assert!(errors.lock().unwrap().is_empty(), "{:?}", errors);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_archive_directory() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let archive = tempfile::tempdir().expect("failed to create temp dir");
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let name = |days| {
+            create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                0,
+            )
+        };
+        // 前回停止するまでにローテーションしたファイルを作成
+        fs::write(directory.path().join(name(-10)), "Stale").unwrap();
+
+        let clock = MockClock::new(first_date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .max_count(2)
+            .directory(directory.path())
+            .archive_directory(archive.path())
+            .filename_prefix("foo")
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+        // 構築したときに、残っていたファイルが移動されるはず
+        assert!(archive.path().join(name(-10)).exists());
+        assert_eq!(find_files(directory.path()).len(), 1);
+
+        for msg in ["Hello", "World", "!"] {
+            write_to_log(&mut appender, msg);
+            clock.advance(Duration::days(1));
+        }
+        write_to_log(&mut appender, "Bye");
+
+        // ファイルを作成するディレクトリには現在のファイルのみが残り、古いファイルの削除は
+        // ファイルを移動するディレクトリに対して行われるはず
+        let current = directory.path().join(name(3));
+        assert_eq!(find_files(directory.path()).len(), 1);
+        assert!(find_str_in_log_file(&current, "Bye"));
+        assert_eq!(find_files(archive.path()).len(), 2);
+        assert!(find_str_in_log_file(&archive.path().join(name(1)), "World"));
+        assert!(find_str_in_log_file(&archive.path().join(name(2)), "!"));
+        assert_eq!(
+            appender.list_files().unwrap(),
+            vec![
+                current,
+                archive.path().join(name(2)),
+                archive.path().join(name(1))
+            ]
+        );
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.");
+        archive
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_archive_directory_across_file_systems() {
+        use crate::file_system::MemoryFileSystem;
+
+        let directory = Path::new("/logs");
+        let archive = Path::new("/archive");
+        let file_system = MemoryFileSystem::new();
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let path = |directory: &Path, days| {
+            directory.join(create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                0,
+            ))
+        };
+        // 移動先に同じ名前のファイルが存在する場合は、上書きせずに追記するはず
+        file_system
+            .append(&path(archive, 0))
+            .unwrap()
+            .write_all(b"Previous ")
+            .unwrap();
+
+        let clock = MockClock::new(first_date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory)
+            .archive_directory(archive)
+            .filename_prefix("foo")
+            .clock(clock.clone())
+            .file_system(
+                // 異なるファイルシステムへの移動と同様に、名前の変更が常に失敗する
+                HookedFileSystem::new(file_system.clone())
+                    .on_rename(|_, _, _| Err(io::Error::from(io::ErrorKind::CrossesDevices))),
+            )
+            .build()
+            .expect("failed to build appender");
+        write_to_log(&mut appender, "Hello");
+        clock.advance(Duration::days(1));
+        write_to_log(&mut appender, "World");
+
+        // 名前を変更できないため、複製した後に元のファイルが削除されるはず
+        assert_eq!(
+            file_system.paths(),
+            vec![path(archive, 0), path(directory, 1)]
+        );
+        assert_eq!(
+            file_system.read(path(archive, 0)).unwrap(),
+            b"Previous Hello"
+        );
+        assert_eq!(file_system.read(path(directory, 1)).unwrap(), b"World");
+    }
+
+    #[test]
+    fn test_host_tag_and_pid() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let pid = std::process::id();
+        let name = |tag: &str, days| {
+            let name = create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                0,
+            );
+            match tag {
+                "" => name,
+                _ => name.replace(".log", &format!("-{}.log", tag)),
+            }
+        };
+        let host1 = format!("host1-{}", pid);
+        for days in 1..=3 {
+            for tag in ["", host1.as_str(), "host2"] {
+                File::create(directory.path().join(name(tag, -days))).unwrap();
+            }
+        }
+        let build = |builder: DailyRollingFileAppenderBuilder| {
+            builder
+                .max_count(1)
+                .directory(directory.path())
+                .filename_prefix("foo")
+                .clock(MockClock::new(first_date))
+                .build()
+                .expect("failed to build appender")
+        };
+
+        // タグはファイル名の日時の後に含まれ、同じタグのファイルのみが削除されるはず
+        let first = build(
+            DailyRollingFileAppender::builder()
+                .host_tag("host1")
+                .include_pid(true),
+        );
+        let second = build(DailyRollingFileAppender::builder().host_tag("host2"));
+        assert_eq!(first.current_path(), directory.path().join(name(&host1, 0)));
+        assert_eq!(
+            second.current_path(),
+            directory.path().join(name("host2", 0))
+        );
+        assert_eq!(
+            first.list_files().unwrap(),
+            vec![
+                first.current_path(),
+                directory.path().join(name(&host1, -1))
+            ]
+        );
+        assert_eq!(
+            second.list_files().unwrap(),
+            vec![
+                second.current_path(),
+                directory.path().join(name("host2", -1))
+            ]
+        );
+        // タグを含まないファイルは削除されないはず
+        for days in 1..=3 {
+            assert!(directory.path().join(name("", -days)).exists());
+        }
+
+        // ピリオドを含むタグは使用できないはず
+        let result = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .host_tag("host1.example.com")
+            .build();
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidInput);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_file_namer() {
+        use crate::file_system::MemoryFileSystem;
+
+        /// ファイル名に日付とプロセスIDを含める規則。
+        struct PidNamer(u32);
+
+        impl FileNamer for PidNamer {
+            fn current_name(&self, date: &Date) -> String {
+                format!("app-{}-{}.log", date, self.0)
+            }
+
+            fn is_owned(&self, filename: &str) -> bool {
+                filename.starts_with("app-") && filename.ends_with(&format!("-{}.log", self.0))
+            }
+        }
+
+        let directory = Path::new("/namer/logs");
+        let file_system = MemoryFileSystem::new();
+        // 他のプロセスのファイルは削除されないはず
+        let other = directory.join("app-2022-05-20-999.log");
+        file_system.append(&other).unwrap();
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let clock = MockClock::new(first_date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .max_count(1)
+            .max_bytes(5)
+            .directory(directory)
+            .filename_prefix("app")
+            .file_namer(PidNamer(1234))
+            .clock(clock.clone())
+            .file_system(file_system.clone())
+            .build()
+            .expect("failed to build appender");
+        for msg in ["Hello", "World"] {
+            write_to_log(&mut appender, msg);
+            clock.advance(Duration::days(1));
+        }
+        write_to_log(&mut appender, "Hello");
+        write_to_log(&mut appender, "Bye");
+
+        assert_eq!(
+            file_system.paths(),
+            vec![
+                other,
+                directory.join("app-2022-05-29-1234.log"),
+                directory.join("app-2022-05-29-1234.log.1"),
+            ]
+        );
+        assert_eq!(
+            file_system
+                .read(directory.join("app-2022-05-29-1234.log.1"))
+                .unwrap(),
+            b"Bye"
+        );
+
+        // 既定の規則は、公開されている関数と同じ規則で名前を付けて判定するはず
+        let namer = DefaultNamer::new("foo", DEFAULT_EXTENSION);
+        let name = namer.current_name(&first_date);
+        assert_eq!(
+            name,
+            create_daily_log_filename("foo", DEFAULT_EXTENSION, first_date, 0)
+        );
+        assert!(namer.is_owned(&name));
+        assert!(namer.is_owned(&namer.segment_name(&start_of_day(first_date), 2)));
+        assert!(!namer.is_owned("bar-20220527.log"));
+    }
+
+    #[test]
+    fn test_memory_file_system() {
+        use crate::file_system::MemoryFileSystem;
+
+        let directory = Path::new("/memory/logs");
+        let file_system = MemoryFileSystem::new();
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let clock = MockClock::new(first_date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .max_count(1)
+            .directory(directory)
+            .filename_prefix("foo")
+            .clock(clock.clone())
+            .file_system(file_system.clone())
+            .build()
+            .expect("failed to build appender");
+
+        for msg in ["Hello", "World", "!"] {
+            write_to_log(&mut appender, msg);
+            clock.advance(Duration::days(1));
+        }
+        write_to_log(&mut appender, "Bye");
+
+        // 最も古いファイルは削除され、現在のファイルと1つ前のファイルのみが残る
+        let path = |days| {
+            directory.join(create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                0,
+            ))
+        };
+        assert_eq!(file_system.paths(), vec![path(2), path(3)]);
+        assert_eq!(file_system.read(path(2)).unwrap(), b"!");
+        assert_eq!(file_system.read(path(3)).unwrap(), b"Bye");
+        assert_eq!(appender.current_path(), path(3));
+    }
+
+    #[test]
+    fn test_stats() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let clock = MockClock::new(first_date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .max_count(1)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+        assert_eq!(appender.stats(), AppenderStats::default());
+
+        write_to_log(&mut appender, "Hello");
+        clock.advance(Duration::days(1));
+        write_to_log(&mut appender, "World");
+        clock.advance(Duration::days(1));
+        {
+            use tracing_subscriber::fmt::writer::MakeWriter;
+
+            appender.make_writer().write_all(b"!").unwrap();
+        }
+
+        assert_eq!(
+            appender.stats(),
+            AppenderStats {
+                bytes_written: 11,
+                rollover_count: 2,
+                files_deleted: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_open_mode() {
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        for (open_mode, expected) in [
+            (OpenMode::Append, "HelloWorld"),
+            (OpenMode::Truncate, "World"),
+        ] {
+            let directory = tempfile::tempdir().expect("failed to create temp dir");
+            let build = || {
+                DailyRollingFileAppender::builder()
+                    .directory(directory.path())
+                    .filename_prefix("foo")
+                    .open_mode(open_mode)
+                    .clock(MockClock::new(date))
+                    .build()
+                    .expect("failed to build appender")
+            };
+
+            // プロセスを再起動して、同じ日付のファイルに書き込む
+            let mut appender = build();
+            write_to_log(&mut appender, "Hello");
+            drop(appender);
+            let mut appender = build();
+            write_to_log(&mut appender, "World");
+
+            let contents = fs::read_to_string(appender.current_path()).unwrap();
+            assert_eq!(contents, expected, "{:?}", open_mode);
+        }
+    }
+
+    #[test]
+    fn test_truncate_same_file_on_rollover() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date_time = Date::from_calendar_date(2022, time::Month::May, 27)
+            .unwrap()
+            .with_hms(15, 30, 0)
+            .unwrap()
+            .assume_utc();
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .max_bytes(5)
+            .size_suffix(SizeSuffix::Time)
+            .open_mode(OpenMode::Truncate)
+            .clock(MockClock::with_date_time(date_time))
+            .build()
+            .expect("failed to build appender");
+
+        // 同じ時刻に2回ローテーションすると、2回目は同じファイルを開き直す
+        write_to_log(&mut appender, "Hello");
+        write_to_log(&mut appender, "World");
+        write_to_log(&mut appender, "!");
+
+        let contents = fs::read_to_string(appender.current_path()).unwrap();
+        assert_eq!(contents, "World!");
+    }
+
+    #[test]
+    fn test_retention_with_mangled_filenames() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let name = |days| {
+            create_daily_log_filename("foo", DEFAULT_EXTENSION, date + Duration::days(days), 0)
+        };
+        // 前後に空白が付けられたファイルも、日付の順に保存する基準に含めるはず
+        let mangled = [format!("{} ", name(-3)), format!(" {}", name(-2))];
+        for filename in &mangled {
+            File::create(directory.path().join(filename)).unwrap();
+        }
+        File::create(directory.path().join(name(-1))).unwrap();
+
+        let appender = DailyRollingFileAppender::builder()
+            .max_count(2)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(MockClock::new(date))
+            .build()
+            .expect("failed to build appender");
+
+        let mut files: Vec<_> = find_files(directory.path())
+            .into_iter()
+            .map(|entry| entry.path())
+            .collect();
+        files.sort();
+        let mut expected = vec![
+            directory.path().join(&mangled[1]),
+            directory.path().join(name(-1)),
+            appender.current_path(),
+        ];
+        expected.sort();
+        assert_eq!(files, expected);
+        assert_eq!(appender.stats().files_deleted, 1);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_max_deletions_per_pass() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let name = |days| {
+            create_daily_log_filename("foo", DEFAULT_EXTENSION, date + Duration::days(days), 0)
+        };
+        for days in 1..=10 {
+            File::create(directory.path().join(name(-days))).unwrap();
+        }
+        let remaining = || {
+            let mut files: Vec<_> = find_files(directory.path())
+                .into_iter()
+                .map(|entry| entry.path())
+                .collect();
+            files.sort();
+            files
+        };
+
+        // 構築したときに、最も古い3つのファイルを削除するはず
+        let appender = DailyRollingFileAppender::builder()
+            .max_count(2)
+            .max_deletions_per_pass(3)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(MockClock::new(date))
+            .build()
+            .expect("failed to build appender");
+        let expected: Vec<_> = (-7..=0)
+            .map(|days| directory.path().join(name(days)))
+            .collect();
+        assert_eq!(remaining(), expected);
+        assert_eq!(appender.plan_removals().unwrap().len(), 3);
+
+        // 残りのファイルは、以降の削除で古い順に削除するはず
+        appender.enforce_retention_now().unwrap();
+        assert_eq!(remaining(), expected[3..]);
+        appender.enforce_retention_now().unwrap();
+        assert_eq!(remaining(), expected[5..]);
+        assert_eq!(appender.stats().files_deleted, 8);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_min_free_bytes() {
+        use crate::file_system::MemoryFileSystem;
+
+        let directory = Path::new("/free/logs");
+        let file_system = MemoryFileSystem::with_capacity(35);
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let clock = MockClock::new(first_date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .max_count(10)
+            .min_free_bytes(10)
+            .directory(directory)
+            .filename_prefix("foo")
+            .clock(clock.clone())
+            .file_system(file_system.clone())
+            .build()
+            .expect("failed to build appender");
+
+        // 3日目までは空き容量が足りているため、保存する基準に従ってファイルを残す
+        for _ in 0..3 {
+            write_to_log(&mut appender, "0123456789");
+            clock.advance(Duration::days(1));
+        }
+        assert_eq!(file_system.paths().len(), 3);
+
+        // 4日目にローテーションしたとき、空き容量が5バイトとなるため、最も古いファイルを削除する
+        write_to_log(&mut appender, "0123456789");
+        let path = |days| {
+            directory.join(create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                0,
+            ))
+        };
+        assert_eq!(file_system.paths(), vec![path(1), path(2), path(3)]);
+    }
+
+    #[test]
+    fn test_sink_factory() {
+        /// 書き込んだ内容を共有するバッファに追加する出力先。
+        struct SharedSink(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for SharedSink {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        type Sinks = Arc<Mutex<Vec<(PathBuf, Arc<Mutex<Vec<u8>>>)>>>;
+        let sinks: Sinks = Default::default();
+        let factory = {
+            let sinks = Arc::clone(&sinks);
+            move |path: &Path| -> io::Result<Box<dyn Write + Send>> {
+                let buffer = Arc::new(Mutex::new(Vec::new()));
+                sinks
+                    .lock()
+                    .unwrap()
+                    .push((path.to_owned(), Arc::clone(&buffer)));
+                Ok(Box::new(SharedSink(buffer)))
+            }
+        };
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let clock = MockClock::new(first_date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory("/sinks")
+            .filename_prefix("foo")
+            .header(|_| b"[".to_vec())
+            .max_bytes(20)
+            .sink_factory(factory)
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+
+        // 日時とサイズによるローテーションのたびに、ファイル名の規則に従って出力先を作成するはず
+        write_to_log(&mut appender, "Hello");
+        clock.advance(Duration::days(1));
+        write_to_log(&mut appender, "0123456789012345678901");
+        write_to_log(&mut appender, "World");
+        drop(appender);
+        let path = |days, index| {
+            Path::new("/sinks").join(create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                index,
+            ))
+        };
+        let sinks = sinks.lock().unwrap();
+        let sinks: Vec<_> = sinks
+            .iter()
+            .map(|(path, buffer)| (path.clone(), buffer.lock().unwrap().clone()))
+            .collect();
+        assert_eq!(
+            sinks,
+            vec![
+                (path(0, 0), b"[Hello".to_vec()),
+                (path(1, 0), b"[0123456789012345678901".to_vec()),
+                (path(1, 1), b"[World".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keep_minimum() {
+        use crate::file_system::MemoryFileSystem;
+
+        type Configure = fn(DailyRollingFileAppenderBuilder) -> DailyRollingFileAppenderBuilder;
+
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let cases: [(&str, Configure, i64); 5] = [
+            ("count", |b| b.max_count(1).keep_minimum(3), 3),
+            ("bytes", |b| b.max_total_bytes(1).keep_minimum(2), 2),
+            ("age", |b| b.max_age_days(1).keep_minimum(3), 3),
+            ("free", |b| b.min_free_bytes(100).keep_minimum(2), 2),
+            // 設定しなかった場合も、最も新しいファイルを1つ残すはず
+            ("default", |b| b.max_total_bytes(1), 1),
+        ];
+        for (name, configure, expected) in cases {
+            let directory = Path::new("/keep").join(name);
+            let file_system = MemoryFileSystem::with_capacity(60);
+            file_system.create_dir_all(&directory).unwrap();
+            let path = |days| {
+                directory.join(create_daily_log_filename(
+                    "foo",
+                    DEFAULT_EXTENSION,
+                    date - Duration::days(days),
+                    0,
+                ))
+            };
+            for days in 1..=5 {
+                let mut file = file_system.create(&path(days)).unwrap();
+                file.write_all(b"0123456789").unwrap();
+            }
+            let builder = DailyRollingFileAppender::builder()
+                .directory(&directory)
+                .filename_prefix("foo")
+                .clock(MockClock::new(date))
+                .file_system(file_system.clone());
+            let appender = configure(builder)
+                .build()
+                .expect("failed to build appender");
+
+            // 新しい順に、残すファイルの最小数のファイルと、現在ログを記録しているファイルが残るはず
+            let mut expected_paths: Vec<_> = (1..=expected).rev().map(path).collect();
+            expected_paths.push(appender.current_path());
+            assert_eq!(file_system.paths(), expected_paths, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_write_across_rollover() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let clock = MockClock::new(date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .max_bytes(10)
+            .buffer_capacity(4)
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+        write_to_log(&mut appender, "Hello");
+        let first = appender.current_path();
+
+        // 日時の境界を過ぎた後の1回の書き込みは、全て新しいファイルに記録されるはず
+        clock.advance(Duration::days(1));
+        let buf = b"0123456789";
+        assert_eq!(appender.write(buf).unwrap(), buf.len());
+        appender.flush().unwrap();
+        let second = appender.current_path();
+        assert_ne!(first, second);
+        assert_eq!(fs::read(&first).unwrap(), b"Hello");
+        assert_eq!(fs::read(&second).unwrap(), buf);
+
+        // 最大サイズを超える1回の書き込みも、全て新しいファイルに記録されるはず
+        assert_eq!(appender.write(b"World").unwrap(), 5);
+        appender.flush().unwrap();
+        let third = appender.current_path();
+        assert_ne!(second, third);
+        assert_eq!(fs::read(&second).unwrap(), buf);
+        assert_eq!(fs::read(&third).unwrap(), b"World");
+        drop(appender);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_protected() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let path = |days| {
+            directory.path().join(create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                date - Duration::days(days),
+                0,
+            ))
+        };
+        for days in 1..=3 {
+            fs::write(path(days), "Old").unwrap();
+        }
+        fs::write(directory.path().join("README"), "Read me").unwrap();
+
+        // 全てのファイルを対象とする誤った関数を設定しても、削除しないファイルは残るはず
+        let appender = DailyRollingFileAppender::builder()
+            .max_count(1)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .filename_predicate(|_| true)
+            .protected(vec![
+                "README".to_owned(),
+                path(3).file_name().unwrap().to_str().unwrap().to_owned(),
+            ])
+            .clock(MockClock::new(date))
+            .build()
+            .expect("failed to build appender");
+        assert!(directory.path().join("README").exists());
+        assert!(path(1).exists() && !path(2).exists() && path(3).exists());
+        assert!(!appender.list_files().unwrap().contains(&path(3)));
+        drop(appender);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_recreate_removed_directory() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let log_directory = directory.path().join("logs");
+        let date_time = Date::from_calendar_date(2022, time::Month::May, 27)
+            .unwrap()
+            .with_hms(15, 30, 0)
+            .unwrap()
+            .assume_utc();
+        let clock = MockClock::with_date_time(date_time);
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(&log_directory)
+            .filename_prefix("foo")
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+        write_to_log(&mut appender, "Hello");
+
+        // 運用者がディレクトリを削除した後に書き込む
+        fs::remove_dir_all(&log_directory).unwrap();
+        clock.advance(Duration::seconds(1));
+        write_to_log(&mut appender, "World");
+
+        let contents = fs::read_to_string(appender.current_path()).unwrap();
+        assert_eq!(contents, "World");
+    }
+
+    #[test]
+    fn test_resume_from_newest_log_file() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        // 時計が進んでいたときに作成されたファイル
+        let future_name =
+            create_daily_log_filename("foo", DEFAULT_EXTENSION, date + Duration::days(2), 0);
+        fs::write(directory.path().join(&future_name), "Hello").unwrap();
+
+        let clock = MockClock::new(date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+        assert_eq!(appender.current_path(), directory.path().join(&future_name));
+
+        // 最も新しいファイルの日時を過ぎるまでは、そのファイルに追記する
+        write_to_log(&mut appender, "World");
+        clock.advance(Duration::days(2));
+        write_to_log(&mut appender, "!");
+        assert_eq!(find_files(directory.path()).len(), 1);
+        let contents = fs::read_to_string(directory.path().join(&future_name)).unwrap();
+        assert_eq!(contents, "HelloWorld!");
+
+        clock.advance(Duration::days(1));
+        write_to_log(&mut appender, "Bye");
+        assert_eq!(find_files(directory.path()).len(), 2);
+    }
+
+    #[test]
+    fn test_empty_prefix() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let filenames = [
+            "20220525.log",
+            "20220526.log",
+            "-20220524.log",
+            "foo-20220524.log",
+            "20220524.txt",
+        ];
+        for filename in filenames {
+            fs::write(directory.path().join(filename), "Old").unwrap();
+        }
+        assert!(is_log_file("20220527.3.log.gz", "", "log"));
+        assert!(!is_log_file("-20220527.log", "", "log"));
+
+        let mut appender = DailyRollingFileAppender::builder()
+            .max_count(1)
+            .directory(directory.path())
+            .filename_prefix("")
+            .max_bytes(5)
+            .clock(MockClock::new(date))
+            .build()
+            .expect("failed to build appender");
+        write_to_log(&mut appender, "Hello");
+        write_to_log(&mut appender, "World");
+
+        // 区切り文字を付けずに日時から始まる名前のファイルを作成して、その名前のファイルのみを削除するはず
+        let mut names: Vec<_> = find_files(directory.path())
+            .iter()
+            .map(|entry| entry.file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "-20220524.log",
+                "20220524.txt",
+                "20220527.1.log",
+                "20220527.log",
+                "foo-20220524.log",
+            ]
+        );
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_next_rollover() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let clock = MockClock::with_date_time(start_of_day(date) + Duration::hours(10));
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+
+        // 翌日の0時にローテーションするはず
+        assert_eq!(
+            appender.next_rollover(),
+            start_of_day(date + Duration::days(1))
+        );
+
+        // ローテーションした後は、その翌日の0時にローテーションするはず
+        clock.advance(Duration::days(1));
+        write_to_log(&mut appender, "Hello");
+        assert_eq!(
+            appender.next_rollover(),
+            start_of_day(date + Duration::days(2))
+        );
+        drop(appender);
+
+        // 時間ごとにローテーションする場合は、次の時間の始まりにローテーションするはず
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("bar")
+            .rotation(Rotation::Hourly)
+            .time_zone(TimeZone::Fixed(UtcOffset::from_hms(9, 0, 0).unwrap()))
+            .clock(MockClock::with_date_time(
+                start_of_day(date) + Duration::minutes(90),
+            ))
+            .build()
+            .expect("failed to build appender");
+        let next_rollover = appender.next_rollover();
+        assert_eq!(next_rollover, start_of_day(date) + Duration::hours(2));
+        assert_eq!(
+            next_rollover.offset(),
+            UtcOffset::from_hms(9, 0, 0).unwrap()
+        );
+        drop(appender);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_manifest() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let clock = MockClock::new(date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .max_bytes(10)
+            .manifest(true)
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+        write_to_log(&mut appender, "Hello");
+        clock.advance(Duration::days(1) + Duration::hours(1));
+        write_to_log(&mut appender, "0123456789");
+        clock.advance(Duration::minutes(1));
+        write_to_log(&mut appender, "World");
+
+        // ローテーションごとに、閉じたファイルを1行ずつ記録するはず
+        let manifest = fs::read_to_string(directory.path().join("foo-manifest.jsonl")).unwrap();
+        let entries: Vec<serde_json::Value> = manifest
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                serde_json::json!({
+                    "file": "foo-20220527.log",
+                    "bytes": 5,
+                    "first_record": "2022-05-27T00:00:00Z",
+                    "last_record": "2022-05-27T00:00:00Z",
+                    "reason": "date",
+                }),
+                serde_json::json!({
+                    "file": "foo-20220528.log",
+                    "bytes": 10,
+                    "first_record": "2022-05-28T01:00:00Z",
+                    "last_record": "2022-05-28T01:00:00Z",
+                    "reason": "size",
+                }),
+            ]
+        );
+
+        // マニフェストはログファイルとして扱わないはず
+        assert!(!is_log_file("foo-manifest.jsonl", "foo", "log"));
+        let files = appender.list_files().unwrap();
+        assert_eq!(files.len(), 3);
+        assert!(files
+            .iter()
+            .all(|path| !path.ends_with("foo-manifest.jsonl")));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_clock_moves_backward() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let path = |days, part: bool| {
+            let name =
+                create_daily_log_filename("foo", DEFAULT_EXTENSION, date + Duration::days(days), 0);
+            directory.path().join(match part {
+                true => format!("{}.{}", name, PART_EXTENSION),
+                false => name,
+            })
+        };
+        for part_files in [false, true] {
+            let clock = MockClock::new(date);
+            let build = || {
+                DailyRollingFileAppender::builder()
+                    .directory(directory.path())
+                    .filename_prefix("foo")
+                    .part_files(part_files)
+                    .clock(clock.clone())
+                    .build()
+                    .expect("failed to build appender")
+            };
+            let mut appender = build();
+            write_to_log(&mut appender, "Hello");
+            clock.advance(Duration::days(1));
+            write_to_log(&mut appender, "World");
+
+            // 時計が前日に戻っても、前日のファイルに戻らずに、翌日のファイルに記録し続けるはず
+            clock.advance(Duration::days(-1));
+            write_to_log(&mut appender, "Again");
+            assert_eq!(appender.current_path(), path(1, part_files));
+            drop(appender);
+
+            // 時計が戻った状態で構築しても、最も新しいファイルから記録を再開するはず
+            let mut appender = build();
+            assert_eq!(appender.current_path(), path(1, part_files));
+            write_to_log(&mut appender, "!");
+            drop(appender);
+            assert!(find_str_in_log_file(&path(0, false), "Hello"));
+            assert!(find_str_in_log_file(&path(1, part_files), "WorldAgain!"));
+            for days in 0..=1 {
+                fs::remove_file(path(days, days == 1 && part_files)).unwrap();
+            }
+        }
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_retry_failed_rollover() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let filename_prefix = "foo";
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let clock = MockClock::new(first_date);
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(filename_prefix)
+            .clock(clock.clone())
+            .on_error({
+                let errors = Arc::clone(&errors);
+                move |err| errors.lock().unwrap().push(err.kind())
+            })
+            .build()
+            .expect("failed to build appender");
+        let first_path = appender.current_path();
+        write_to_log(&mut appender, "Hello");
+
+        // 翌日のファイルと同じ名前のディレクトリを作成して、ファイルを作成できないようにする
+        let second_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &start_of_day(first_date + Duration::days(1)),
+            0,
+        );
+        let second_path = directory.path().join(second_name);
+        fs::create_dir(&second_path).unwrap();
+        clock.advance(Duration::days(1));
+
+        // ローテーションに失敗した場合は、エラーを報告して、前のファイルに書き込むはず
+        write_to_log(&mut appender, "World");
+        assert_eq!(errors.lock().unwrap().len(), 1);
+        assert_eq!(appender.current_path(), first_path);
+        assert!(find_str_in_log_file(&first_path, "HelloWorld"));
+
+        // ファイルを作成できるようになった後に書き込むと、ローテーションを再試行するはず
+        fs::remove_dir(&second_path).unwrap();
+        write_to_log(&mut appender, "Again");
+        assert_eq!(errors.lock().unwrap().len(), 1);
+        assert_eq!(appender.current_path(), second_path);
+        assert!(find_str_in_log_file(&second_path, "Again"));
+        assert!(find_str_in_log_file(&first_path, "HelloWorld"));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_time_size_suffix() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let clock = MockClock::with_date_time(
+            PrimitiveDateTime::new(date, Time::from_hms(15, 30, 0).unwrap()).assume_utc(),
+        );
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .max_bytes(5)
+            .max_count(1)
+            .size_suffix(SizeSuffix::Time)
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+
+        // サイズによってローテーションしたファイルの名前に、ローテーションした時刻が付くはず
+        write_to_log(&mut appender, "Hello");
+        clock.advance(Duration::seconds(1));
+        write_to_log(&mut appender, "World");
+        let second_path = directory.path().join("foo-20220527T153001.log");
+        assert_eq!(appender.current_path(), second_path);
+        assert!(find_str_in_log_file(&second_path, "World"));
+        assert!(find_str_in_log_file(
+            &directory.path().join("foo-20220527.log"),
+            "Hello"
+        ));
+
+        // 古い順に削除されるはず
+        clock.advance(Duration::minutes(10));
+        write_to_log(&mut appender, "Again");
+        let third_path = directory.path().join("foo-20220527T154001.log");
+        assert_eq!(appender.current_path(), third_path);
+        let mut filenames: Vec<_> = find_files(directory.path())
+            .iter()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        filenames.sort();
+        assert_eq!(
+            filenames,
+            vec!["foo-20220527T153001.log", "foo-20220527T154001.log"]
+        );
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_compress_rolled_file() {
+        use flate2::read::MultiGzDecoder;
+        use std::io::Read;
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let filename_prefix = "foo";
+        let today = today();
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(filename_prefix)
+            .max_bytes(5)
+            .compress(true)
+            .build()
+            .expect("failed to build appender");
+
+        write_to_log(&mut appender, "Hello");
+        write_to_log(&mut appender, "World");
+
+        // ローテーションしたファイルは圧縮され、元のファイルは削除されているはず
+        let first_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            0,
+        );
+        let first_path = directory.path().join(&first_name);
+        assert!(!first_path.exists());
+        let compressed_path = directory.path().join(format!("{}.gz", first_name));
+        let mut decoder = MultiGzDecoder::new(File::open(compressed_path).unwrap());
+        let mut contents = String::new();
+        decoder.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "Hello");
+
+        // 現在ログを記録しているファイルは圧縮されていないはず
+        let second_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            1,
+        );
+        let second_path = create_daily_log_path(directory.path(), &second_name);
+        assert!(find_str_in_log_file(Path::new(&second_path), "World"));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    #[test]
+    fn test_compression() {
+        use std::io::Read;
+
+        // 圧縮方式と、圧縮したファイルの拡張子及び元の内容に戻す関数
+        type Decode = fn(File) -> String;
+        let mut compressions: Vec<(Compression, &str, Decode)> = Vec::new();
+        #[cfg(feature = "gzip")]
+        compressions.push((Compression::Gzip { level: 9 }, "gz", |file| {
+            let mut contents = String::new();
+            flate2::read::MultiGzDecoder::new(file)
+                .read_to_string(&mut contents)
+                .unwrap();
+            contents
+        }));
+        #[cfg(feature = "zstd")]
+        compressions.push((Compression::Zstd { level: 19 }, "zst", |file| {
+            let mut contents = String::new();
+            zstd::stream::read::Decoder::new(file)
+                .unwrap()
+                .read_to_string(&mut contents)
+                .unwrap();
+            contents
+        }));
+
+        for (compression, extension, decode) in compressions {
+            let directory = tempfile::tempdir().expect("failed to create temp dir");
+            let mut appender = DailyRollingFileAppender::builder()
+                .directory(directory.path())
+                .filename_prefix("foo")
+                .max_bytes(5)
+                .compression(compression)
+                .build()
+                .expect("failed to build appender");
+            let first_path = appender.current_path();
+            write_to_log(&mut appender, "Hello");
+            write_to_log(&mut appender, "World");
+
+            // ローテーションしたファイルは圧縮され、元の内容に戻せるはず
+            assert!(!first_path.exists());
+            let mut compressed_path = first_path.into_os_string();
+            compressed_path.push(".");
+            compressed_path.push(extension);
+            let compressed_path = PathBuf::from(compressed_path);
+            assert_eq!(decode(File::open(&compressed_path).unwrap()), "Hello");
+            // 圧縮したファイルも、アペンダーが管理するログファイルとして扱われるはず
+            assert_eq!(
+                appender.list_files().unwrap(),
+                vec![appender.current_path(), compressed_path]
+            );
+
+            directory.close().expect(
+                "Failed to explicitly close TempDir. TempDir should delete once out of scope.",
+            )
+        }
+    }
+
+    #[test]
+    fn test_remove_old_files_by_total_bytes() {
+        let prefix = "foo";
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        // 今日の1日前から5日前までの、10バイトのログファイルを作成
+        let today = today();
+        let log_names: Vec<String> = (1..=5)
+            .map(|days| {
+                let date = today - Duration::days(days);
+                create_log_filename(
+                    prefix,
+                    DEFAULT_SEPARATOR,
+                    DEFAULT_EXTENSION,
+                    &DateFormat::from(Rotation::Daily),
+                    &date,
+                    0,
+                )
+            })
+            .collect();
+        for filename in &log_names {
+            fs::write(directory.path().join(filename), "0123456789").unwrap();
+        }
+
+        // 今日のファイルを含めて、合計サイズを25バイト以下にするアペンダーを作成して、ログを出力
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(prefix)
+            .max_total_bytes(25)
+            .build()
+            .expect("failed to build appender");
+        write_to_log(&mut appender, "Hello");
+        appender.inner().remove_old_files();
+
+        // 今日のファイル(5バイト)と、1日前と2日前のファイル(20バイト)が残っているはず
+        let filenames: Vec<_> = find_files(directory.path())
+            .iter()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(filenames.len(), 3);
+        assert!(filenames.contains(&create_log_filename(
+            prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            0
+        )));
+        for filename in &log_names[..2] {
+            assert!(filenames.contains(filename));
+        }
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_log_file_date() {
+        let prefix = "foo";
+        let date_format = DateFormat::from(Rotation::Daily);
+        let re = log_file_regex(prefix, DEFAULT_SEPARATOR, DEFAULT_EXTENSION, &date_format);
+        let expected = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        assert_eq!(
+            log_file_date("foo-20220527.log", &re, &date_format),
+            Some(expected)
+        );
+        assert_eq!(
+            log_file_date("foo-20220527.3.log.gz", &re, &date_format),
+            Some(expected)
+        );
+        assert_eq!(log_file_date("foo-00000000.log", &re, &date_format), None);
+        assert_eq!(log_file_date("foo-20220230.log", &re, &date_format), None);
+        assert_eq!(log_file_date("bar-20220527.log", &re, &date_format), None);
+    }
+
+    #[test]
+    fn test_remove_old_files_by_age() {
+        let prefix = "foo";
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        // 今日の1日前から10日前までのログファイルを作成
+        let today = today();
+        let log_names: Vec<String> = (1..=10)
+            .map(|days| {
+                let date = today - Duration::days(days);
+                create_log_filename(
+                    prefix,
+                    DEFAULT_SEPARATOR,
+                    DEFAULT_EXTENSION,
+                    &DateFormat::from(Rotation::Daily),
+                    &date,
+                    0,
+                )
+            })
+            .collect();
+        // 日付を解析できないログファイル
+        let invalid_name = format!("{}-00000000.{}", prefix, DEFAULT_EXTENSION);
+        for filename in log_names.iter().chain([&invalid_name]) {
+            File::create(directory.path().join(filename)).unwrap();
+        }
+
+        // 保存するファイルの最大数は多いが、保存する日数を3日とするアペンダーを作成
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(prefix)
+            .max_count(20)
+            .max_age_days(3)
+            .build()
+            .expect("failed to build appender");
+        appender.inner().remove_old_files();
+
+        let filenames: Vec<_> = find_files(directory.path())
+            .iter()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        // 今日から3日前までのファイルは残っているはず
+        for filename in &log_names[..3] {
+            assert!(filenames.contains(filename));
+        }
+        // 今日から4日前以前のファイルは削除されているはず
+        for filename in &log_names[3..] {
+            assert!(!filenames.contains(filename));
+        }
+        // 日付を解析できないファイルは削除されていないはず
+        assert!(filenames.contains(&invalid_name));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    /// タイムゾーンによって異なる日付を返却する時計。
+    struct TimeZoneClock {
+        utc: Date,
+        local: Date,
+    }
+
+    impl Clock for TimeZoneClock {
+        fn now_date(&self) -> Date {
+            self.utc
+        }
+
+        fn now_date_in(&self, time_zone: TimeZone) -> Date {
+            match time_zone {
+                TimeZone::Utc => self.utc,
+                TimeZone::Local | TimeZone::Fixed(_) => self.local,
+            }
+        }
+    }
+
+    #[test]
+    fn test_local_time_zone() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let filename_prefix = "foo";
+        let utc = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let local = utc + Duration::days(1);
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(filename_prefix)
+            .time_zone(TimeZone::Local)
+            .clock(TimeZoneClock { utc, local })
+            .build()
+            .expect("failed to build appender");
+
+        // 最初に作成するファイルと、ローテーションの確認の両方でローカルの日付を使用するため、
+        // ローカルの日付のファイルにログが記録されるはず
+        write_to_log(&mut appender, "Hello");
+        let name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &start_of_day(local),
+            0,
+        );
+        let path = create_daily_log_path(directory.path(), &name);
+        assert!(find_str_in_log_file(Path::new(&path), "Hello"));
+        assert_eq!(find_files(directory.path()).len(), 1);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_fixed_time_zone_at_midnight() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let filename_prefix = "foo";
+        // UTC-5:00の2022-05-27 23:59:59は、UTCでは2022-05-28 04:59:59
+        let offset = UtcOffset::from_hms(-5, 0, 0).unwrap();
+        let clock = MockClock::with_date_time(
+            Date::from_calendar_date(2022, time::Month::May, 28)
+                .unwrap()
+                .with_hms(4, 59, 59)
+                .unwrap()
+                .assume_utc(),
+        );
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(filename_prefix)
+            .time_zone(TimeZone::Fixed(offset))
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+        let log_path = |day| {
+            let date = Date::from_calendar_date(2022, time::Month::May, day).unwrap();
+            let name = create_log_filename(
+                filename_prefix,
+                DEFAULT_SEPARATOR,
+                DEFAULT_EXTENSION,
+                &DateFormat::from(Rotation::Daily),
+                &start_of_day(date),
+                0,
+            );
+            create_daily_log_path(directory.path(), &name)
+        };
+
+        // 真夜中の1秒前は、UTCの日付ではなく、タイムゾーンにおける日付のファイルに記録されるはず
+        write_to_log(&mut appender, "Before midnight");
+        assert!(find_str_in_log_file(
+            Path::new(&log_path(27)),
+            "Before midnight"
+        ));
+
+        // 真夜中になったら、翌日のファイルにローテーションするはず
+        clock.advance(Duration::seconds(1));
+        write_to_log(&mut appender, "At midnight");
+        assert!(find_str_in_log_file(
+            Path::new(&log_path(28)),
+            "At midnight"
+        ));
+        assert!(!find_str_in_log_file(
+            Path::new(&log_path(27)),
+            "At midnight"
+        ));
+
+        // 真夜中の1秒後も、同じファイルに記録されるはず
+        clock.advance(Duration::seconds(1));
+        write_to_log(&mut appender, "After midnight");
+        assert!(find_str_in_log_file(
+            Path::new(&log_path(28)),
+            "At midnightAfter midnight"
+        ));
+        assert_eq!(find_files(directory.path()).len(), 2);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_on_rollover() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let filename_prefix = "foo";
+        let today = today();
+        let closed_paths = Arc::new(Mutex::new(Vec::new()));
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(filename_prefix)
+            .max_bytes(5)
+            .on_rollover({
+                let closed_paths = closed_paths.clone();
+                move |path| closed_paths.lock().unwrap().push(path.to_owned())
+            })
+            .build()
+            .expect("failed to build appender");
+
+        // 最初のファイルを作成したときは呼び出されないはず
+        write_to_log(&mut appender, "Hello");
+        assert!(closed_paths.lock().unwrap().is_empty());
+
+        // ローテーションしたときに、閉じたファイルのパスで呼び出されるはず
+        write_to_log(&mut appender, "World");
+        let first_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            0,
+        );
+        assert_eq!(
+            *closed_paths.lock().unwrap(),
+            vec![directory.path().join(first_name)]
+        );
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_current_link() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let filename_prefix = "foo";
+        let today = today();
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(filename_prefix)
+            .max_bytes(5)
+            .current_link(true)
+            .build()
+            .expect("failed to build appender");
+
+        // 構築したときに、最初のファイルを指すシンボリックリンクが作成されるはず
+        let link_path = directory.path().join("foo-current.log");
+        let first_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            0,
+        );
+        assert_eq!(fs::read_link(&link_path).unwrap(), Path::new(&first_name));
+
+        // ローテーションしたときに、新しいファイルを指すように更新されるはず
+        write_to_log(&mut appender, "Hello");
+        write_to_log(&mut appender, "World");
+        let second_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            1,
+        );
+        assert_eq!(fs::read_link(&link_path).unwrap(), Path::new(&second_name));
+        assert!(find_str_in_log_file(&link_path, "World"));
+
+        // 一時的なシンボリックリンクは残っていないはず
+        assert!(!directory.path().join(".foo-current.log.tmp").exists());
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_current_path() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let filename_prefix = "foo";
+        let today = today();
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(filename_prefix)
+            .max_bytes(5)
+            .build()
+            .expect("failed to build appender");
+
+        let first_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            0,
+        );
+        assert_eq!(appender.current_path(), directory.path().join(first_name));
+
+        // ローテーションした後は、新しいファイルのパスを返却するはず
+        write_to_log(&mut appender, "Hello");
+        write_to_log(&mut appender, "World");
+        let second_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            1,
+        );
+        assert_eq!(appender.current_path(), directory.path().join(second_name));
+        assert!(find_str_in_log_file(&appender.current_path(), "World"));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_on_error() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let log_directory = directory.path().join("logs");
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let appender = DailyRollingFileAppender::builder()
+            .directory(&log_directory)
+            .filename_prefix("foo")
+            .on_error({
+                let errors = errors.clone();
+                move |err| errors.lock().unwrap().push(err.kind())
+            })
+            .build()
+            .expect("failed to build appender");
+
+        // ディレクトリを削除したため、古いファイルを検索できないはず
+        fs::remove_dir_all(&log_directory).unwrap();
+        appender.inner().remove_old_files();
+        assert_eq!(*errors.lock().unwrap(), vec![io::ErrorKind::NotFound]);
+
+        // 直ちに削除する場合は、関数を呼び出さずに呼び出し元にエラーを返却するはず
+        match appender.enforce_retention_now() {
+            Err(AppenderError::ReadDir { path, source }) => {
+                assert_eq!(path, log_directory);
+                assert_eq!(source.kind(), io::ErrorKind::NotFound);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert_eq!(errors.lock().unwrap().len(), 1);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_flush_on_drop() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .buffer_capacity(1024)
+            .build()
+            .expect("failed to build appender");
+        let path = appender.current_path();
+
+        appender.write_all(b"Hello").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        drop(appender);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Hello");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_flush_error_on_drop() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        // 書き込むと常に`ENOSPC`となるデバイスに記録する
+        let name = create_daily_log_filename("foo", DEFAULT_EXTENSION, date, 0);
+        std::os::unix::fs::symlink("/dev/full", directory.path().join(name)).unwrap();
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .buffer_capacity(1024)
+            .clock(MockClock::new(date))
+            .on_error({
+                let errors = errors.clone();
+                move |err| errors.lock().unwrap().push(err.io_error().raw_os_error())
+            })
+            .build()
+            .expect("failed to build appender");
+
+        appender.write_all(b"Hello").unwrap();
+        drop(appender);
+        assert_eq!(*errors.lock().unwrap(), vec![Some(28)]);
+    }
+
+    #[test]
+    fn test_hourly_rotation() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let filename_prefix = "foo";
+        let start = PrimitiveDateTime::new(
+            Date::from_calendar_date(2022, time::Month::May, 27).unwrap(),
+            Time::from_hms(9, 30, 0).unwrap(),
+        )
+        .assume_utc();
+        let clock = MockClock::with_date_time(start);
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(filename_prefix)
+            .rotation(Rotation::Hourly)
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+
+        write_to_log(&mut appender, "Hello");
+        // 同じ時間内であればローテーションしないはず
+        clock.advance(Duration::minutes(20));
+        write_to_log(&mut appender, "World");
+        // 次の時間になったらローテーションするはず
+        clock.advance(Duration::minutes(20));
+        write_to_log(&mut appender, "Again");
+
+        let path = directory.path().join("foo-2022052709.log");
+        assert!(find_str_in_log_file(&path, "HelloWorld"));
+        let path = directory.path().join("foo-2022052710.log");
+        assert!(find_str_in_log_file(&path, "Again"));
+        assert_eq!(find_files(directory.path()).len(), 2);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_buffered_write() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let filename_prefix = "foo";
+        let today = today();
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(filename_prefix)
+            .max_bytes(10)
+            .buffer_capacity(64)
+            .build()
+            .expect("failed to build appender");
+        let first_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            0,
+        );
+        let first_path = directory.path().join(first_name);
+
+        // フラッシュするまでは、ファイルに書き込まれないはず
+        appender.write_all(b"Hello").unwrap();
+        assert!(find_str_in_log_file(&first_path, ""));
+        appender.flush().unwrap();
+        assert!(find_str_in_log_file(&first_path, "Hello"));
+
+        // ローテーションしたときに、バッファの内容が前のファイルに書き込まれるはず
+        appender.write_all(b"World").unwrap();
+        appender.write_all(b"Again").unwrap();
+        assert!(find_str_in_log_file(&first_path, "HelloWorld"));
+        let second_name = create_log_filename(
+            filename_prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            1,
+        );
+        let second_path = directory.path().join(second_name);
+        assert!(find_str_in_log_file(&second_path, ""));
+        appender.flush().unwrap();
+        assert!(find_str_in_log_file(&second_path, "Again"));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_flush_write_stack_on_rollover() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let clock = MockClock::new(Date::from_calendar_date(2022, time::Month::May, 27).unwrap());
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .buffer_capacity(64)
+            .clock(clock.clone())
+            .file_system(
+                // ファイルシステムが返却するライター自体も、バッファリングする
+                HookedFileSystem::new(OsFileSystem::new()).on_open(|inner, path, open_mode| {
+                    Ok(Box::new(BufWriter::with_capacity(
+                        1024,
+                        open_writer(inner, path, open_mode)?,
+                    )))
+                }),
+            )
+            .build()
+            .expect("failed to build appender");
+        let first_path = appender.current_path();
+
+        // アペンダーのバッファを一杯にして、ファイルシステムのライターのバッファにも書き込む
+        for _ in 0..10 {
+            appender.write_all(b"0123456789").unwrap();
+        }
+        assert!(find_str_in_log_file(&first_path, ""));
+
+        // ローテーションしたとき、両方のバッファの内容が前のファイルに書き込まれ、新しいファイルには
+        // 書き込まれないはず
+        clock.advance(Duration::days(1));
+        appender.write_all(b"Hello").unwrap();
+        let second_path = appender.current_path();
+        assert_ne!(first_path, second_path);
+        assert!(find_str_in_log_file(&first_path, &"0123456789".repeat(10)));
+        assert!(find_str_in_log_file(&second_path, ""));
+        appender.flush().unwrap();
+        assert!(find_str_in_log_file(&second_path, "Hello"));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_header() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let build = || {
+            DailyRollingFileAppender::builder()
+                .directory(directory.path())
+                .filename_prefix("foo")
+                .max_bytes(60)
+                .header(|date| format!("# version=1 date={}\n", date.date()).into_bytes())
+                .build()
+                .expect("failed to build appender")
+        };
+        let header = format!("# version=1 date={}\n", today().date());
+
+        // 新しく作成したファイルにヘッダーが書き込まれるはず
+        let mut appender = build();
+        let path = appender.current_path();
+        write_to_log(&mut appender, "Hello\n");
+        assert!(find_str_in_log_file(&path, &format!("{}Hello\n", header)));
+        drop(appender);
+
+        // 再起動して同じファイルに追記する場合は、ヘッダーが書き込まれないはず
+        let mut appender = build();
+        assert_eq!(appender.current_path(), path);
+        write_to_log(&mut appender, "World\n");
+        assert!(find_str_in_log_file(
+            &path,
+            &format!("{}Hello\nWorld\n", header)
+        ));
+
+        // サイズでローテーションした新しいファイルにもヘッダーが書き込まれるはず
+        write_to_log(&mut appender, "0123456789012345678901\n");
+        write_to_log(&mut appender, "Again\n");
+        assert_ne!(appender.current_path(), path);
+        assert!(find_str_in_log_file(
+            &appender.current_path(),
+            &format!("{}Again\n", header)
+        ));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_footer() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let today = today().date();
+        let clock = MockClock::new(today);
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .footer("]\n")
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+        let first_path = appender.current_path();
+        write_to_log(&mut appender, "[\n");
+
+        // ローテーションしたとき、前のファイルの末尾にフッターが書き込まれるはず
+        clock.advance(Duration::days(1));
+        write_to_log(&mut appender, "[\n");
+        let second_path = appender.current_path();
+        assert_ne!(first_path, second_path);
+        assert!(find_str_in_log_file(&first_path, "[\n]\n"));
+        assert!(find_str_in_log_file(&second_path, "[\n"));
+
+        // アペンダーを破棄したとき、ログを記録しているファイルの末尾にフッターが1回だけ
+        // 書き込まれるはず
+        drop(appender);
+        assert!(find_str_in_log_file(&first_path, "[\n]\n"));
+        assert!(find_str_in_log_file(&second_path, "[\n]\n"));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_record_terminator() {
+        use tracing_subscriber::fmt::writer::MakeWriter;
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let build = |prefix: &str, atomic_records| {
+            DailyRollingFileAppender::builder()
+                .directory(directory.path())
+                .filename_prefix(prefix)
+                .record_terminator("\n")
+                .atomic_records(atomic_records)
+                .build()
+                .expect("failed to build appender")
+        };
+
+        // 終端で終わっていないレコードにのみ、終端を1回だけ付けるはず
+        let mut appender = build("foo", false);
+        assert_eq!(appender.write(b"Hello").unwrap(), 5);
+        assert_eq!(appender.write(b"World\n").unwrap(), 6);
+        assert_eq!(appender.write(b"").unwrap(), 0);
+        {
+            let mut writer = appender.make_writer();
+            assert_eq!(writer.write(b"Foo").unwrap(), 3);
+        }
+        appender.flush().unwrap();
+        assert!(find_str_in_log_file(
+            &appender.current_path(),
+            "Hello\nWorld\nFoo\n"
+        ));
+
+        // レコードをまとめて書き込む場合は、まとめたレコードに付けるはず
+        let appender = build("bar", true);
+        {
+            let mut writer = appender.make_writer();
+            writer.write_all(b"Hello, ").unwrap();
+            writer.write_all(b"World").unwrap();
+        }
+        assert!(find_str_in_log_file(
+            &appender.current_path(),
+            "Hello, World\n"
+        ));
+        drop(appender);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_retention_lock() {
+        use crate::file_system::MemoryFileSystem;
+
+        let directory = Path::new("/retention");
+        let file_system = MemoryFileSystem::new();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let build = |prefix: &'static str| {
+            DailyRollingFileAppender::builder()
+                .directory(directory)
+                .filename_prefix(prefix)
+                .file_system(HookedFileSystem::new(file_system.clone()).on_read_dir({
+                    // ディレクトリを読み込んでいる間を記録して、読み込みに時間をかける
+                    let events = Arc::clone(&events);
+                    move |inner, path| {
+                        events.lock().unwrap().push(format!("{} start", prefix));
+                        thread::sleep(StdDuration::from_millis(20));
+                        let result = inner.read_dir(path);
+                        events.lock().unwrap().push(format!("{} end", prefix));
+
+                        result
+                    }
+                }))
+                .build()
+                .expect("failed to build appender")
+        };
+        let appenders = [build("foo"), build("bar")];
+        events.lock().unwrap().clear();
+
+        // 同じディレクトリの古いファイルを同時に削除しても、ディレクトリの読み込みは交互に行われないはず
+        thread::scope(|scope| {
+            for appender in &appenders {
+                scope.spawn(|| {
+                    for _ in 0..3 {
+                        appender.enforce_retention_now().unwrap();
+                    }
+                });
+            }
+        });
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 12);
+        for pair in events.chunks(2) {
+            let prefix = pair[0].strip_suffix(" start").unwrap();
+            assert_eq!(pair[1], format!("{} end", prefix), "{:?}", events);
+        }
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn test_encoding() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let build = || {
+            DailyRollingFileAppender::builder()
+                .directory(directory.path())
+                .filename_prefix("foo")
+                .encoding(encoding_rs::UTF_16LE)
+                .header(|_| b"# header\n".to_vec())
+                .build()
+                .expect("failed to build appender")
+        };
+        let mut appender = build();
+        write_to_log(&mut appender, "Hello, 世界\n");
+        drop(appender);
+        // 同じファイルに追記する場合は、バイト順マークを書き込まないはず
+        let mut appender = build();
+        write_to_log(&mut appender, "こんにちは\n");
+        let path = appender.current_path();
+        drop(appender);
+
+        // バイト順マークに続けてUTF-16LEで記録され、UTF-8に戻すと元のレコードになるはず
+        let bytes = fs::read(path).unwrap();
+        assert!(bytes.starts_with(b"\xff\xfe#\0"));
+        let (decoded, had_errors) = encoding_rs::UTF_16LE.decode_with_bom_removal(&bytes);
+        assert!(!had_errors);
+        assert_eq!(decoded, "# header\nHello, 世界\nこんにちは\n");
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_timestamp_prefix() {
+        use tracing_subscriber::fmt::writer::MakeWriter;
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date_time = Date::from_calendar_date(2022, time::Month::May, 27)
+            .unwrap()
+            .with_hms(15, 30, 0)
+            .unwrap()
+            .assume_utc();
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .timestamp_prefix(true)
+            .record_terminator("\n")
+            .atomic_records(true)
+            .clock(MockClock::with_date_time(date_time))
+            .build()
+            .expect("failed to build appender");
+        {
+            let mut writer = appender.make_writer();
+            writer.write_all(b"Hello, ").unwrap();
+            writer.write_all(b"World").unwrap();
+        }
+        write_to_log(&mut appender, "Foo\n");
+
+        // 各行は解析できる時刻で始まり、まとめたレコードには時刻を1回だけ付けるはず
+        let content = fs::read_to_string(appender.current_path()).unwrap();
+        let records: Vec<_> = content
+            .lines()
+            .map(|line| {
+                let (timestamp, record) = line.split_once(' ').unwrap();
+                assert_eq!(
+                    OffsetDateTime::parse(timestamp, &Rfc3339).unwrap(),
+                    date_time
+                );
+                record
+            })
+            .collect();
+        assert_eq!(records, vec!["Hello, World", "Foo"]);
+        assert!(content.ends_with('\n'));
+        drop(appender);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_directory_lock() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let build_with_extension = |prefix: &str, extension: &str, directory_lock: bool| {
+            DailyRollingFileAppender::builder()
+                .directory(directory.path())
+                .filename_prefix(prefix)
+                .extension(extension)
+                .directory_lock(directory_lock)
+                .build()
+        };
+        let build = |prefix: &str, directory_lock: bool| {
+            build_with_extension(prefix, "log", directory_lock)
+        };
+
+        // 同じディレクトリとファイル名の接頭語のアペンダーは構築できないはず
+        let appender = build("foo", true).expect("failed to build appender");
+        let result = build("foo", true);
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::AlreadyExists);
+        // ファイル名の接頭語が異なる場合と、確認を無効にした場合は構築できるはず
+        build("bar", true).expect("failed to build appender");
+        build("foo", false).expect("failed to build appender");
+        // 拡張子だけが異なる場合は、異なるファイルに記録するため構築できるはず
+        let _jsonl = build_with_extension("foo", "jsonl", true).expect("failed to build appender");
+        let result = build_with_extension("foo", "jsonl", true);
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::AlreadyExists);
+
+        // アペンダーを破棄した後は、同じ組み合わせのアペンダーを構築できるはず
+        drop(appender);
+        build("foo", true).expect("failed to build appender");
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_fsync_on_flush() {
+        use tracing_subscriber::fmt::writer::MakeWriter;
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .buffer_capacity(1024)
+            .fsync_on_flush(true)
+            .build()
+            .expect("failed to build appender");
+
+        appender.write_all(b"Hello").unwrap();
+        appender.flush().unwrap();
+        let mut writer = appender.make_writer();
+        writer.write_all(b"World").unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+
+        assert_eq!(
+            fs::read_to_string(appender.current_path()).unwrap(),
+            "HelloWorld"
+        );
 
-        let path = create_daily_log_filename(filename_prefix, &date);
-        assert_eq!(expected, path);
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
     }
 
-    fn write_to_log(appender: &mut DailyRollingFileAppender, msg: &str) {
-        appender
-            .write_all(msg.as_bytes())
-            .expect("Failed to write to appender");
-        appender.flush().expect("Failed to flush!");
+    #[test]
+    fn test_flush_on_write() {
+        use tracing_subscriber::fmt::writer::MakeWriter;
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .buffer_capacity(64)
+            .flush_on_write(true)
+            .build()
+            .expect("failed to build appender");
+        let path = appender.current_path();
+
+        // バッファリングしていても、書き込むたびにファイルに書き込まれるはず
+        appender.make_writer().write_all(b"Hello").unwrap();
+        assert!(find_str_in_log_file(&path, "Hello"));
+        appender.write_all(b"World").unwrap();
+        assert!(find_str_in_log_file(&path, "HelloWorld"));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
     }
 
-    fn find_str_in_log_files(dir_path: &Path, expected_value: &str) -> bool {
-        let dir_contents = fs::read_dir(dir_path).expect("Failed to read directory");
+    #[test]
+    fn test_flush_interval() {
+        use tracing_subscriber::fmt::writer::MakeWriter;
 
-        for entry in dir_contents {
-            let path = entry.expect("Expected dir entry").path();
-            let file = fs::read_to_string(&path).expect("Failed to read file");
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .buffer_capacity(64)
+            .flush_interval(StdDuration::from_millis(10))
+            .build()
+            .expect("failed to build appender");
+        let path = appender.current_path();
 
-            if file.as_str() == expected_value {
-                return true;
+        // フラッシュしなくても、一定の間隔でファイルに書き込まれるはず
+        appender.make_writer().write_all(b"Hello").unwrap();
+        let mut flushed = false;
+        for _ in 0..500 {
+            if find_str_in_log_file(&path, "Hello") {
+                flushed = true;
+                break;
             }
+            thread::sleep(StdDuration::from_millis(10));
         }
+        assert!(flushed);
 
-        false
+        // アペンダーを破棄したとき、フラッシュするスレッドも終了するはず
+        let writer = Arc::downgrade(&appender.writer);
+        drop(appender);
+        assert!(writer.upgrade().is_none());
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
     }
 
-    fn find_str_in_log_file(path: &Path, expected_value: &str) -> bool {
-        let file = fs::read_to_string(&path).expect("Failed to read file");
+    #[test]
+    fn test_flush_interval_with_error() {
+        use crate::file_system::MemoryFileSystem;
+        use tracing_subscriber::fmt::writer::MakeWriter;
 
-        file.as_str() == expected_value
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let errors_in_hook = Arc::clone(&errors);
+        let appender = DailyRollingFileAppender::builder()
+            .directory("/broken/logs")
+            .filename_prefix("foo")
+            .buffer_capacity(64)
+            .flush_interval(StdDuration::from_millis(10))
+            .file_system(
+                // 作成したファイルに書き込むと、エラーとなる
+                HookedFileSystem::new(MemoryFileSystem::new()).on_open(|inner, path, open_mode| {
+                    open_writer(inner, path, open_mode)?;
+                    Ok(Box::new(FailingWriter))
+                }),
+            )
+            .on_error(move |err| {
+                if let AppenderError::Flush { path, .. } = err {
+                    errors_in_hook.lock().unwrap().push(path.clone());
+                }
+            })
+            .build()
+            .expect("failed to build appender");
+
+        // 一定の間隔でフラッシュできなかった場合は、エラーを報告する関数に渡されるはず
+        appender.make_writer().write_all(b"Hello").unwrap();
+        let mut reported = false;
+        for _ in 0..500 {
+            if !errors.lock().unwrap().is_empty() {
+                reported = true;
+                break;
+            }
+            thread::sleep(StdDuration::from_millis(10));
+        }
+        assert!(reported);
+        assert_eq!(errors.lock().unwrap()[0], appender.current_path());
     }
 
     #[test]
-    fn test_write_log() {
+    fn test_with_max_bytes_and_background_threads() {
         let directory = tempfile::tempdir().expect("failed to create temp dir");
-        let mut appender = DailyRollingFileAppender::new(3, directory.path(), "foo");
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let path = |index| {
+            directory
+                .path()
+                .join(create_daily_log_filename("foo", "log", date, index))
+        };
+        // バックグラウンドのスレッドが状態を共有していても、最大サイズを設定できるはず
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .flush_interval(StdDuration::from_millis(10))
+            .scheduled_rollover(true)
+            .clock(MockClock::new(date))
+            .build()
+            .expect("failed to build appender")
+            .with_max_bytes(5);
 
-        let expected_value = "Hello";
-        write_to_log(&mut appender, expected_value);
-        assert!(find_str_in_log_files(directory.path(), expected_value));
+        write_to_log(&mut appender, "Hello");
+        write_to_log(&mut appender, "World");
+        assert_eq!(appender.current_path(), path(1));
+        assert!(find_str_in_log_file(&path(0), "Hello"));
+        assert!(find_str_in_log_file(&path(1), "World"));
+        assert!(appender._flusher.is_some() && appender._scheduler.is_some());
+        drop(appender);
 
         directory
             .close()
@@ -454,28 +9707,111 @@ mod tests {
     }
 
     #[test]
-    fn test_rolling_file() {
-        // 昨日の日付でアペンダーを作成
+    fn test_scheduled_rollover() {
         let directory = tempfile::tempdir().expect("failed to create temp dir");
-        let filename_prefix = "foo";
-        let today = today();
-        let yesterday = today + Duration::days(-1);
-        let mut appender =
-            DailyRollingFileAppender::new_test(3, directory.path(), filename_prefix, yesterday);
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let path = |days| {
+            directory.path().join(create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                0,
+            ))
+        };
+        let clock = MockClock::new(first_date);
+        let mut appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .footer("Bye")
+            .scheduled_rollover(true)
+            .clock(clock.clone())
+            .build()
+            .expect("failed to build appender");
+        write_to_log(&mut appender, "Hello");
 
-        // ログを出力
-        let expected_value = "Hello";
-        write_to_log(&mut appender, expected_value);
+        // 書き込まなくても、日付が変わった後にローテーションするはず
+        clock.advance(Duration::days(1));
+        let mut rolled = false;
+        for _ in 0..500 {
+            if appender.stats().rollover_count == 1 {
+                rolled = true;
+                break;
+            }
+            thread::sleep(StdDuration::from_millis(10));
+        }
+        assert!(rolled);
+        assert_eq!(appender.current_path(), path(1));
+        assert!(find_str_in_log_file(&path(0), "HelloBye"));
+        assert!(path(1).exists());
 
-        // 昨日のログファイルにはログが記録されていないはず
-        let yesterday_name = create_daily_log_filename(filename_prefix, &yesterday);
-        let yesterday_path = create_daily_log_path(directory.path(), &yesterday_name);
-        assert!(find_str_in_log_file(Path::new(&yesterday_path), ""));
+        // アペンダーを破棄したとき、ローテーションするスレッドも終了するはず
+        let state = Arc::downgrade(&appender.state);
+        drop(appender);
+        assert!(state.upgrade().is_none());
 
-        // 今日のログファイルにはログが記録されているはず
-        let today_name = create_daily_log_filename(filename_prefix, &today);
-        let today_path = create_daily_log_path(directory.path(), &today_name);
-        assert!(find_str_in_log_file(Path::new(&today_path), expected_value));
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_rollover_while_writer_is_held() {
+        use tracing_subscriber::fmt::writer::MakeWriter;
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .max_bytes(5)
+            .build()
+            .expect("failed to build appender");
+        let first_path = appender.current_path();
+
+        // ライターを保持したままでも、他のライターがローテーションできるはず
+        let mut held = appender.make_writer();
+        held.write_all(b"Hello").unwrap();
+        let mut writer = appender.make_writer();
+        let second_path = appender.current_path();
+        assert_ne!(first_path, second_path);
+        writer.write_all(b"World").unwrap();
+
+        // 保持していたライターは、ローテーションした後のファイルに書き込むはず
+        held.write_all(b"Again").unwrap();
+        assert!(find_str_in_log_file(&first_path, "Hello"));
+        assert!(find_str_in_log_file(&second_path, "WorldAgain"));
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_buffered_make_writer() {
+        use tracing_subscriber::fmt::writer::MakeWriter;
+
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .buffer_capacity(64)
+            .build()
+            .expect("failed to build appender");
+
+        // 複数のスレッドから書き込んだ内容が、行ごとに分かれて記録されるはず
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..100 {
+                        appender.make_writer().write_all(b"Hello\n").unwrap();
+                    }
+                });
+            }
+        });
+        appender.make_writer().flush().unwrap();
+
+        let contents = fs::read_to_string(appender.current_path()).unwrap();
+        assert_eq!(contents.lines().count(), 400);
+        assert!(contents.lines().all(|line| line == "Hello"));
 
         directory
             .close()
@@ -496,17 +9832,484 @@ mod tests {
             .collect()
     }
 
+    #[test]
+    fn test_remove_old_files_with_filename_predicate() {
+        let prefix = "foo";
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        // 今日の1日前から5日前までのログファイルを作成
+        let today = today();
+        let log_names: Vec<String> = (1..=5)
+            .map(|days| {
+                let date = today - Duration::days(days);
+                create_log_filename(
+                    prefix,
+                    DEFAULT_SEPARATOR,
+                    DEFAULT_EXTENSION,
+                    &DateFormat::from(Rotation::Daily),
+                    &date,
+                    0,
+                )
+            })
+            .collect();
+        // 既定のパターンに一致するが、他のサービスが出力したファイル
+        let others = ["foo-19990101.log".to_owned(), "foo-19990102.log".to_owned()];
+        for filename in log_names.iter().chain(others.iter()) {
+            File::create(directory.path().join(filename)).unwrap();
+        }
+
+        // 今日のファイルを除いて2つのログファイルを残し、1999年のファイルを管理しないアペンダーを作成
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(prefix)
+            .max_count(2)
+            .filename_predicate(|name| name.starts_with("foo-") && !name.starts_with("foo-1999"))
+            .build()
+            .expect("failed to build appender");
+        appender.inner().remove_old_files();
+
+        // 今日と1日前、2日前のファイル、及び他のサービスのファイルが残っているはず
+        let filenames: Vec<_> = find_files(directory.path())
+            .iter()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(filenames.len(), 3 + others.len());
+        assert!(filenames.contains(&create_log_filename(
+            prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            0
+        )));
+        for filename in log_names[..2].iter().chain(others.iter()) {
+            assert!(filenames.contains(filename));
+        }
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_remove_old_files_deleted_during_scan() {
+        let prefix = "foo";
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        // 今日の1日前から5日前までのログファイルを作成
+        let today = today();
+        let log_names: Vec<String> = (1..=5)
+            .map(|days| {
+                let date = today - Duration::days(days);
+                create_log_filename(
+                    prefix,
+                    DEFAULT_SEPARATOR,
+                    DEFAULT_EXTENSION,
+                    &DateFormat::from(Rotation::Daily),
+                    &date,
+                    0,
+                )
+            })
+            .collect();
+        for filename in &log_names {
+            File::create(directory.path().join(filename)).unwrap();
+        }
+
+        // ファイルを検索している間に、最も古いファイルと3日前のファイルを削除するアペンダーを作成
+        let deleted = [log_names[4].clone(), log_names[2].clone()];
+        let path = directory.path().to_path_buf();
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(prefix)
+            .max_count(1)
+            .filename_predicate(move |name| {
+                if deleted.iter().any(|deleted| deleted == name) {
+                    let _ = fs::remove_file(path.join(name));
+                }
+                name.starts_with("foo-")
+            })
+            .on_error({
+                let errors = Arc::clone(&errors);
+                move |err| errors.lock().unwrap().push(err.kind())
+            })
+            .build()
+            .expect("failed to build appender");
+        appender.inner().remove_old_files();
+
+        // 今日と1日前のファイルのみが残り、削除済みのファイルはエラーにならないはず
+        let filenames: Vec<_> = find_files(directory.path())
+            .iter()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(filenames.len(), 2);
+        assert!(filenames.contains(&log_names[0]));
+        assert!(errors.lock().unwrap().is_empty());
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_remove_old_files_at_startup() {
+        let prefix = "foo";
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        // 今日の1日前から10日前までのログファイルと、明日のログファイルを作成
+        let today = today();
+        let date_format = DateFormat::from(Rotation::Daily);
+        let log_names: Vec<String> = (1..=10)
+            .map(|days| {
+                let date = today - Duration::days(days);
+                create_log_filename(
+                    prefix,
+                    DEFAULT_SEPARATOR,
+                    DEFAULT_EXTENSION,
+                    &date_format,
+                    &date,
+                    0,
+                )
+            })
+            .collect();
+        let tomorrow = create_log_filename(
+            prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &date_format,
+            &(today + Duration::days(1)),
+            0,
+        );
+        for filename in log_names.iter().chain([&tomorrow]) {
+            File::create(directory.path().join(filename)).unwrap();
+        }
+
+        // 今日のファイルを除いて3つのログファイルを残すアペンダーを構築した時点で、古いファイルが
+        // 削除されるはず
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(prefix)
+            .max_count(3)
+            .build()
+            .expect("failed to build appender");
+        let current = appender.current_path();
+        let filenames: Vec<_> = find_files(directory.path())
+            .iter()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(filenames.len(), 4);
+        assert!(current.exists());
+        assert!(filenames.contains(&tomorrow));
+        for filename in &log_names[..2] {
+            assert!(filenames.contains(filename));
+        }
+
+        // 後から作成されたファイルは、明示的に古いファイルを削除したときに削除されるはず
+        File::create(directory.path().join(&log_names[9])).unwrap();
+        appender.enforce_retention_now().unwrap();
+        assert_eq!(find_files(directory.path()).len(), 4);
+        assert!(!directory.path().join(&log_names[9]).exists());
+        assert!(current.exists());
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_retention_order_by_mtime() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let name = |days| {
+            create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                0,
+            )
+        };
+        // ファイル名の日付が新しいファイルほど、更新日時が古いファイルを作成
+        let now = std::time::SystemTime::now();
+        for days in 1..=3 {
+            let file = File::create(directory.path().join(name(-days))).unwrap();
+            file.set_modified(now - StdDuration::from_secs(3600 * (4 - days) as u64))
+                .unwrap();
+        }
+
+        // 更新日時が最も新しい、ファイル名の日付が最も古いファイルが残るはず
+        let appender = DailyRollingFileAppender::builder()
+            .max_count(1)
+            .retention_order(RetentionOrder::ByMtime)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(MockClock::new(first_date))
+            .build()
+            .expect("failed to build appender");
+        assert_eq!(
+            appender.list_files().unwrap(),
+            vec![appender.current_path(), directory.path().join(name(-3))]
+        );
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_retention_keeps_current_file() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let name = |days| {
+            create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                0,
+            )
+        };
+        let appender = DailyRollingFileAppender::builder()
+            .max_count(1)
+            .retention_order(RetentionOrder::ByMtime)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(MockClock::new(first_date))
+            .build()
+            .expect("failed to build appender");
+        // 現在ログを記録しているファイルの更新日時を、他のファイルより古くする
+        let now = std::time::SystemTime::now();
+        for days in 1..=2 {
+            let file = File::create(directory.path().join(name(-days))).unwrap();
+            file.set_modified(now - StdDuration::from_secs(3600 * days as u64))
+                .unwrap();
+        }
+        File::options()
+            .append(true)
+            .open(appender.current_path())
+            .unwrap()
+            .set_modified(now - StdDuration::from_secs(3600 * 24))
+            .unwrap();
+
+        // 更新日時が最も古くても、現在ログを記録しているファイルは削除されないはず
+        assert_eq!(
+            appender.plan_removals().unwrap(),
+            vec![directory.path().join(name(-2))]
+        );
+        appender.enforce_retention_now().unwrap();
+        assert!(appender.current_path().exists());
+        assert_eq!(
+            appender.list_files().unwrap(),
+            vec![appender.current_path(), directory.path().join(name(-1))]
+        );
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let first_date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        let name = |days| {
+            create_daily_log_filename(
+                "foo",
+                DEFAULT_EXTENSION,
+                first_date + Duration::days(days),
+                0,
+            )
+        };
+        for days in 1..=5 {
+            File::create(directory.path().join(name(-days))).unwrap();
+        }
+
+        // 削除するファイルがエラーとは別に報告され、ファイルは削除されないはず
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let cloned_errors = Arc::clone(&errors);
+        let reported = Arc::new(Mutex::new(Vec::new()));
+        let cloned_reported = Arc::clone(&reported);
+        let appender = DailyRollingFileAppender::builder()
+            .max_count(2)
+            .dry_run(true)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(MockClock::new(first_date))
+            .on_planned_removal(move |path| cloned_reported.lock().unwrap().push(path.to_owned()))
+            .on_error(move |err| cloned_errors.lock().unwrap().push(err.to_string()))
+            .build()
+            .expect("failed to build appender");
+        let planned: Vec<_> = (3..=5)
+            .rev()
+            .map(|days| directory.path().join(name(-days)))
+            .collect();
+        assert_eq!(*reported.lock().unwrap(), planned);
+        appender.enforce_retention_now().unwrap();
+        assert_eq!(reported.lock().unwrap().len(), planned.len() * 2);
+        assert!(errors.lock().unwrap().is_empty());
+        assert_eq!(find_files(directory.path()).len(), 6);
+        assert_eq!(appender.stats().files_deleted, 0);
+        assert_eq!(appender.plan_removals().unwrap(), planned);
+
+        // 削除を有効にした場合は、報告されたファイルが削除されるはず
+        drop(appender);
+        let appender = DailyRollingFileAppender::builder()
+            .max_count(2)
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .clock(MockClock::new(first_date))
+            .build()
+            .expect("failed to build appender");
+        assert!(planned.iter().all(|path| !path.exists()));
+        assert_eq!(find_files(directory.path()).len(), 3);
+        assert!(appender.plan_removals().unwrap().is_empty());
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_unlimited_max_count() {
+        let prefix = "foo";
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        // 今日の1日前から40日前までのログファイルを作成
+        let today = today();
+        let date_format = DateFormat::from(Rotation::Daily);
+        let log_names: Vec<String> = (1..=40)
+            .map(|days| {
+                let date = today - Duration::days(days);
+                create_log_filename(
+                    prefix,
+                    DEFAULT_SEPARATOR,
+                    DEFAULT_EXTENSION,
+                    &date_format,
+                    &date,
+                    0,
+                )
+            })
+            .collect();
+        for filename in &log_names {
+            File::create(directory.path().join(filename)).unwrap();
+        }
+
+        // 保存するファイルの最大数が0のアペンダーは、構築時も明示的に削除したときも、
+        // ファイルを削除しないはず
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix(prefix)
+            .max_count(0)
+            .build()
+            .expect("failed to build appender");
+        appender.enforce_retention_now().unwrap();
+        let filenames: Vec<_> = find_files(directory.path())
+            .iter()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(filenames.len(), log_names.len() + 1);
+        assert!(appender.current_path().exists());
+        for filename in &log_names {
+            assert!(filenames.contains(filename));
+        }
+        assert_eq!(appender.stats().files_deleted, 0);
+
+        // 公開されている関数も、0の場合は何も削除しないはず
+        let removed = prune_log_files(directory.path(), prefix, DEFAULT_EXTENSION, 0).unwrap();
+        assert!(removed.is_empty());
+        assert_eq!(find_files(directory.path()).len(), log_names.len() + 1);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_public_helpers() {
+        let prefix = "foo";
+        let date = Date::from_calendar_date(2022, time::Month::May, 27).unwrap();
+        assert_eq!(
+            create_daily_log_filename(prefix, DEFAULT_EXTENSION, date, 0),
+            "foo-20220527.log"
+        );
+        assert_eq!(
+            create_daily_log_filename(prefix, DEFAULT_EXTENSION, date, 2),
+            "foo-20220527.2.log"
+        );
+        assert!(is_log_file("foo-20220527.log", prefix, DEFAULT_EXTENSION));
+        assert!(is_log_file(
+            "foo-20220527.2.log.gz",
+            prefix,
+            DEFAULT_EXTENSION
+        ));
+        assert!(is_log_file(
+            "foo-20220527T153000.log",
+            prefix,
+            DEFAULT_EXTENSION
+        ));
+        assert!(!is_log_file(
+            "foo-20220527T1530.log",
+            prefix,
+            DEFAULT_EXTENSION
+        ));
+        assert!(!is_log_file("bar-20220527.log", prefix, DEFAULT_EXTENSION));
+        assert!(!is_log_file("foo-20220527.txt", prefix, DEFAULT_EXTENSION));
+    }
+
+    #[test]
+    fn test_prune_log_files() {
+        let prefix = "foo";
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        // 2022年5月20日から5月27日までのログファイルと、ログファイルでないファイルを作成
+        let log_names: Vec<String> = (20..=27)
+            .map(|day| {
+                let date = Date::from_calendar_date(2022, time::Month::May, day).unwrap();
+                create_daily_log_filename(prefix, DEFAULT_EXTENSION, date, 0)
+            })
+            .collect();
+        let others = ["bar-20220501.log", "foo-20220501.txt"];
+        for filename in log_names.iter().map(String::as_str).chain(others) {
+            File::create(directory.path().join(filename)).unwrap();
+        }
+
+        // 新しい順に3つのファイルを残して、削除したファイルを返却するはず
+        let mut removed = prune_log_files(directory.path(), prefix, DEFAULT_EXTENSION, 3).unwrap();
+        removed.sort();
+        let expected: Vec<_> = log_names[..5]
+            .iter()
+            .map(|name| directory.path().join(name))
+            .collect();
+        assert_eq!(removed, expected);
+        let filenames: Vec<_> = find_files(directory.path())
+            .iter()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(filenames.len(), 3 + others.len());
+        for filename in &log_names[5..] {
+            assert!(filenames.contains(filename));
+        }
+
+        // 存在しないディレクトリはエラー
+        let result = prune_log_files(directory.path().join("missing"), prefix, "log", 3);
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::NotFound);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
     #[test]
     fn test_remove_old_files() {
         let prefix = "foo";
         // 今日の10日前までのログファイルの名前を生成
         // 今日のマイナス1日から、マイナス10日までのログファイルの名前を生成
         let today = today();
-        let mut date = today.clone();
+        let mut date = today;
         let log_names: Vec<String> = (0..10)
             .map(|_| {
-                date = date + Duration::days(-1);
-                create_daily_log_filename(&prefix, &date)
+                date += Duration::days(-1);
+                create_log_filename(
+                    prefix,
+                    DEFAULT_SEPARATOR,
+                    DEFAULT_EXTENSION,
+                    &DateFormat::from(Rotation::Daily),
+                    &date,
+                    0,
+                )
             })
             .collect();
 
@@ -543,7 +10346,14 @@ mod tests {
             .collect();
 
         // 今日と今日から2日前までのログファイルが存在することを確認
-        assert!(filenames.contains(&create_daily_log_filename(&prefix, &today)));
+        assert!(filenames.contains(&create_log_filename(
+            prefix,
+            DEFAULT_SEPARATOR,
+            DEFAULT_EXTENSION,
+            &DateFormat::from(Rotation::Daily),
+            &today,
+            0
+        )));
         for filename in &log_names[0..2] {
             assert!(filenames.contains(filename));
         }
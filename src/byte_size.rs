@@ -0,0 +1,331 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+/// `ByteSize`
+///
+/// ファイルのサイズなど、バイト数を表現する。
+///
+/// `KiB`、`MiB`、`GiB`及び`TiB`は1024を基数とする2進接頭辞、`KB`、`MB`、`GB`及び`TB`は
+/// 1000を基数とする10進接頭辞として扱う。設定ファイルなどの文字列は、`"10MiB"`や`"512KB"`のように、
+/// 0以上の整数と単位で指定する。単位の大文字と小文字は区別せず、数値と単位の間の空白は無視する。
+/// 単位を省略した場合と`B`の場合は、バイト数として扱う。小数や負の数は指定できない。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct ByteSize(u64);
+
+/// `ParseByteSizeError`
+///
+/// 文字列を`ByteSize`に変換できなかったときのエラー。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseByteSizeError(ParseByteSizeErrorKind);
+
+/// 文字列を`ByteSize`に変換できなかった理由。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParseByteSizeErrorKind {
+    /// 文字列が空、または空白のみ。
+    Empty,
+    /// 数値を指定していないか、整数でない。
+    InvalidNumber,
+    /// 単位が不明。
+    InvalidUnit,
+    /// バイト数が`u64`の範囲を超えている。
+    Overflow,
+}
+
+/// 単位と、単位が表現するバイト数。
+const UNITS: [(&str, u64); 9] = [
+    ("b", 1),
+    ("kb", 1_000),
+    ("mb", 1_000_000),
+    ("gb", 1_000_000_000),
+    ("tb", 1_000_000_000_000),
+    ("kib", 1 << 10),
+    ("mib", 1 << 20),
+    ("gib", 1 << 30),
+    ("tib", 1 << 40),
+];
+
+impl ByteSize {
+    /// バイト数から`ByteSize`を作成する。
+    ///
+    /// # Arguments
+    ///
+    /// * bytes: バイト数。
+    ///
+    /// # Returns
+    ///
+    /// `ByteSize`インスタンス。
+    pub const fn b(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    /// キビバイト(1024バイト)単位の数から`ByteSize`を作成する。
+    ///
+    /// `u64`の範囲を超える場合は、`u64::MAX`バイトとなる。
+    ///
+    /// # Arguments
+    ///
+    /// * kib: キビバイト単位の数。
+    ///
+    /// # Returns
+    ///
+    /// `ByteSize`インスタンス。
+    pub const fn kib(kib: u64) -> Self {
+        Self(kib.saturating_mul(1 << 10))
+    }
+
+    /// メビバイト(1024 * 1024バイト)単位の数から`ByteSize`を作成する。
+    ///
+    /// `u64`の範囲を超える場合は、`u64::MAX`バイトとなる。
+    ///
+    /// # Arguments
+    ///
+    /// * mib: メビバイト単位の数。
+    ///
+    /// # Returns
+    ///
+    /// `ByteSize`インスタンス。
+    pub const fn mib(mib: u64) -> Self {
+        Self(mib.saturating_mul(1 << 20))
+    }
+
+    /// ギビバイト(1024 * 1024 * 1024バイト)単位の数から`ByteSize`を作成する。
+    ///
+    /// `u64`の範囲を超える場合は、`u64::MAX`バイトとなる。
+    ///
+    /// # Arguments
+    ///
+    /// * gib: ギビバイト単位の数。
+    ///
+    /// # Returns
+    ///
+    /// `ByteSize`インスタンス。
+    pub const fn gib(gib: u64) -> Self {
+        Self(gib.saturating_mul(1 << 30))
+    }
+
+    /// キロバイト(1000バイト)単位の数から`ByteSize`を作成する。
+    ///
+    /// `u64`の範囲を超える場合は、`u64::MAX`バイトとなる。
+    ///
+    /// # Arguments
+    ///
+    /// * kb: キロバイト単位の数。
+    ///
+    /// # Returns
+    ///
+    /// `ByteSize`インスタンス。
+    pub const fn kb(kb: u64) -> Self {
+        Self(kb.saturating_mul(1_000))
+    }
+
+    /// メガバイト(1000 * 1000バイト)単位の数から`ByteSize`を作成する。
+    ///
+    /// `u64`の範囲を超える場合は、`u64::MAX`バイトとなる。
+    ///
+    /// # Arguments
+    ///
+    /// * mb: メガバイト単位の数。
+    ///
+    /// # Returns
+    ///
+    /// `ByteSize`インスタンス。
+    pub const fn mb(mb: u64) -> Self {
+        Self(mb.saturating_mul(1_000_000))
+    }
+
+    /// ギガバイト(1000 * 1000 * 1000バイト)単位の数から`ByteSize`を作成する。
+    ///
+    /// `u64`の範囲を超える場合は、`u64::MAX`バイトとなる。
+    ///
+    /// # Arguments
+    ///
+    /// * gb: ギガバイト単位の数。
+    ///
+    /// # Returns
+    ///
+    /// `ByteSize`インスタンス。
+    pub const fn gb(gb: u64) -> Self {
+        Self(gb.saturating_mul(1_000_000_000))
+    }
+
+    /// バイト数を返却する。
+    ///
+    /// # Returns
+    ///
+    /// バイト数。
+    pub const fn as_u64(&self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for ByteSize {
+    fn from(bytes: u64) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+impl Display for ByteSize {
+    /// バイト数を`"1024B"`の形式で出力する。
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}B", self.0)
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = ParseByteSizeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseByteSizeError(ParseByteSizeErrorKind::Empty));
+        }
+        let (number, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+        if number.is_empty() {
+            return Err(ParseByteSizeError(ParseByteSizeErrorKind::InvalidNumber));
+        }
+        let unit = unit.trim_start().to_ascii_lowercase();
+        let multiplier = match unit.as_str() {
+            "" => 1,
+            // 小数点などの数値の続きは、単位と区別して報告する
+            unit if unit.starts_with(['.', ',']) => {
+                return Err(ParseByteSizeError(ParseByteSizeErrorKind::InvalidNumber))
+            }
+            unit => UNITS
+                .iter()
+                .find(|(name, _)| *name == unit)
+                .map(|(_, multiplier)| *multiplier)
+                .ok_or(ParseByteSizeError(ParseByteSizeErrorKind::InvalidUnit))?,
+        };
+        number
+            .parse::<u64>()
+            .ok()
+            .and_then(|number| number.checked_mul(multiplier))
+            .map(Self)
+            .ok_or(ParseByteSizeError(ParseByteSizeErrorKind::Overflow))
+    }
+}
+
+impl Display for ParseByteSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self.0 {
+            ParseByteSizeErrorKind::Empty => "cannot parse byte size from empty string",
+            ParseByteSizeErrorKind::InvalidNumber => "byte size must be a non-negative integer",
+            ParseByteSizeErrorKind::InvalidUnit => {
+                "unknown byte size unit; expected B, KB, MB, GB, TB, KiB, MiB, GiB or TiB"
+            }
+            ParseByteSizeErrorKind::Overflow => "byte size is too large",
+        };
+
+        f.write_str(message)
+    }
+}
+
+impl Error for ParseByteSizeError {}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ByteSize {
+    /// バイト数を整数としてシリアル化する。
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ByteSize {
+    /// バイト数を表現する整数、または`"10MiB"`のような文字列から逆シリアル化する。
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ByteSizeVisitor;
+
+        impl serde::de::Visitor<'_> for ByteSizeVisitor {
+            type Value = ByteSize;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a number of bytes or a string such as \"10MiB\"")
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(ByteSize(v))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                u64::try_from(v)
+                    .map(ByteSize)
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Signed(v), &self))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(ByteSizeVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_byte_size() {
+        let cases = [
+            ("0", 0),
+            ("1B", 1),
+            ("512", 512),
+            (" 10 MB ", 10_000_000),
+            ("512KiB", 512 * 1024),
+            ("10mib", 10 * 1024 * 1024),
+            ("1GB", 1_000_000_000),
+            ("1GiB", 1 << 30),
+            ("2TiB", 2 << 40),
+            ("0KiB", 0),
+            ("18446744073709551615", u64::MAX),
+        ];
+        for (s, expected) in cases {
+            assert_eq!(s.parse::<ByteSize>(), Ok(ByteSize::b(expected)), "{}", s);
+        }
+
+        let errors = [
+            ("", ParseByteSizeErrorKind::Empty),
+            ("   ", ParseByteSizeErrorKind::Empty),
+            ("MB", ParseByteSizeErrorKind::InvalidNumber),
+            ("-1MB", ParseByteSizeErrorKind::InvalidNumber),
+            ("1.5MB", ParseByteSizeErrorKind::InvalidNumber),
+            ("10M", ParseByteSizeErrorKind::InvalidUnit),
+            ("10 MB B", ParseByteSizeErrorKind::InvalidUnit),
+            ("10 bytes", ParseByteSizeErrorKind::InvalidUnit),
+            ("18446744073709551616", ParseByteSizeErrorKind::Overflow),
+            ("16777216TiB", ParseByteSizeErrorKind::Overflow),
+        ];
+        for (s, kind) in errors {
+            assert_eq!(
+                s.parse::<ByteSize>(),
+                Err(ParseByteSizeError(kind)),
+                "{}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn test_byte_size_constructors() {
+        assert_eq!(ByteSize::kib(512).as_u64(), 512 * 1024);
+        assert_eq!(ByteSize::mib(10).as_u64(), 10 * 1024 * 1024);
+        assert_eq!(ByteSize::gib(1).as_u64(), 1 << 30);
+        assert_eq!(ByteSize::kb(512).as_u64(), 512_000);
+        assert_eq!(ByteSize::mb(10).as_u64(), 10_000_000);
+        assert_eq!(ByteSize::gb(1).as_u64(), 1_000_000_000);
+        assert_eq!(ByteSize::gib(u64::MAX).as_u64(), u64::MAX);
+        assert_eq!(u64::from(ByteSize::from(5)), 5);
+        assert_eq!(ByteSize::kib(1).to_string(), "1024B");
+    }
+}
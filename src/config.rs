@@ -0,0 +1,139 @@
+use std::{io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    appenders::{DailyRollingFileAppender, Rotation, TimeZone},
+    byte_size::ByteSize,
+};
+
+/// `AppenderConfig`
+///
+/// 設定ファイルから読み込む`DailyRollingFileAppender`の設定。
+///
+/// `serde`フィーチャーを有効にした場合に使用できる。TOMLやYAMLなどの設定ファイルから直接
+/// 逆シリアル化して、`DailyRollingFileAppender::from_config`に渡す。省略した項目は、
+/// `DailyRollingFileAppenderBuilder`の既定値となる。不明な項目はエラーとなるため、項目名の
+/// 誤りに気付ける。
+///
+/// ```toml
+/// directory = "/var/log/app"
+/// filename_prefix = "app"
+/// max_count = 14
+/// rotation = "hourly"
+/// max_bytes = "10MiB"
+/// timezone = "local"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppenderConfig {
+    /// ログファイルを保存するディレクトリ。
+    pub directory: PathBuf,
+    /// ログファイル名の接頭語。
+    pub filename_prefix: String,
+    /// 現在ログを出力しているファイルを除いて、保存するファイルの最大数。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_count: Option<usize>,
+    /// ログファイルの拡張子。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extension: Option<String>,
+    /// ファイルをローテーションする間隔。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<Rotation>,
+    /// ファイルの最大サイズ。バイト数、または`"10MiB"`のような文字列で指定する。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<ByteSize>,
+    /// 日付の境界を決めるタイムゾーン。
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<TimeZone>,
+}
+
+impl DailyRollingFileAppender {
+    /// 設定ファイルから読み込んだ設定で、`DailyRollingFileAppender`を構築する。
+    ///
+    /// `serde`フィーチャーを有効にした場合に使用できる。
+    ///
+    /// # Arguments
+    ///
+    /// * config: アペンダーの設定。
+    ///
+    /// # Returns
+    ///
+    /// `DailyRollingFileAppender`インスタンス。構築できなかった場合は、
+    /// `DailyRollingFileAppenderBuilder::build`と同じエラー。
+    pub fn from_config(config: AppenderConfig) -> io::Result<Self> {
+        let mut builder = Self::builder()
+            .directory(config.directory)
+            .filename_prefix(config.filename_prefix);
+        if let Some(max_count) = config.max_count {
+            builder = builder.max_count(max_count);
+        }
+        if let Some(extension) = config.extension {
+            builder = builder.extension(extension);
+        }
+        if let Some(rotation) = config.rotation {
+            builder = builder.rotation(rotation);
+        }
+        if let Some(max_bytes) = config.max_bytes {
+            builder = builder.max_size(max_bytes);
+        }
+        if let Some(timezone) = config.timezone {
+            builder = builder.time_zone(timezone);
+        }
+
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_appender_config() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let config = AppenderConfig {
+            directory: directory.path().to_owned(),
+            filename_prefix: "foo".to_owned(),
+            max_count: Some(3),
+            extension: Some("txt".to_owned()),
+            rotation: Some(Rotation::Hourly),
+            max_bytes: Some(ByteSize::kib(512)),
+            timezone: Some(TimeZone::Fixed(time::UtcOffset::from_hms(9, 0, 0).unwrap())),
+        };
+
+        // シリアル化した設定を逆シリアル化すると、元の設定に戻るはず
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            serde_json::from_str::<AppenderConfig>(&json).unwrap(),
+            config
+        );
+
+        // 省略した項目は既定値となり、サイズは文字列でも指定できるはず
+        let json = format!(
+            r#"{{"directory": {:?}, "filename_prefix": "bar", "max_bytes": "10MiB"}}"#,
+            directory.path()
+        );
+        let parsed = serde_json::from_str::<AppenderConfig>(&json).unwrap();
+        assert_eq!(parsed.max_bytes, Some(ByteSize::mib(10)));
+        assert_eq!(parsed.rotation, None);
+        let unknown = r#"{"directory": "/logs", "filename_prefix": "bar", "max_files": 3}"#;
+        assert!(serde_json::from_str::<AppenderConfig>(unknown).is_err());
+        let invalid = r#"{"directory": "/logs", "filename_prefix": "bar", "max_bytes": "10M"}"#;
+        assert!(serde_json::from_str::<AppenderConfig>(invalid).is_err());
+
+        let appender = DailyRollingFileAppender::from_config(config).unwrap();
+        assert!(appender
+            .current_path()
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .ends_with(".txt"));
+        drop(appender);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+}
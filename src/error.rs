@@ -0,0 +1,191 @@
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// `AppenderError`
+///
+/// `DailyRollingFileAppender`で発生したエラー。
+///
+/// エラーが発生した操作と対象のパスを保持するため、`DailyRollingFileAppenderBuilder::on_error`に
+/// 設定した関数や、`DailyRollingFileAppender`の失敗する可能性のあるメソッドの呼び出し元で、
+/// エラーの種類に応じて処理できる。
+#[derive(Debug)]
+pub enum AppenderError {
+    /// ログを記録するファイルを作成、または開けなかった。
+    CreateWriter {
+        /// 作成しようとしたファイルのパス。
+        path: PathBuf,
+        /// 発生したエラー。
+        source: io::Error,
+    },
+    /// バッファに残っている内容をファイルに書き込めなかった。
+    Flush {
+        /// 書き込もうとしたファイルのパス。
+        path: PathBuf,
+        /// 発生したエラー。
+        source: io::Error,
+    },
+    /// 古いファイルを検索するために、ディレクトリを読み込めなかった。
+    ReadDir {
+        /// 読み込もうとしたディレクトリのパス。
+        path: PathBuf,
+        /// 発生したエラー。
+        source: io::Error,
+    },
+    /// 古いファイルを削除できなかった。
+    RemoveFile {
+        /// 削除しようとしたファイルのパス。
+        path: PathBuf,
+        /// 発生したエラー。
+        source: io::Error,
+    },
+    /// その他の操作で発生したエラー。
+    Other {
+        /// エラーの内容を説明するメッセージ。
+        message: &'static str,
+        /// 発生したエラー。
+        source: io::Error,
+    },
+}
+
+impl AppenderError {
+    /// エラーの原因となった`io::Error`を返却する。
+    ///
+    /// # Returns
+    ///
+    /// エラーの原因となった`io::Error`。
+    pub fn io_error(&self) -> &io::Error {
+        match self {
+            Self::CreateWriter { source, .. }
+            | Self::Flush { source, .. }
+            | Self::ReadDir { source, .. }
+            | Self::RemoveFile { source, .. }
+            | Self::Other { source, .. } => source,
+        }
+    }
+
+    /// エラーの原因となった`io::Error`の種類を返却する。
+    ///
+    /// # Returns
+    ///
+    /// `io::ErrorKind`。
+    pub fn kind(&self) -> io::ErrorKind {
+        self.io_error().kind()
+    }
+
+    /// エラーが発生した操作の対象のパスを返却する。
+    ///
+    /// # Returns
+    ///
+    /// 対象のファイルまたはディレクトリのパス。`Other`の場合はNone。
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::CreateWriter { path, .. }
+            | Self::Flush { path, .. }
+            | Self::ReadDir { path, .. }
+            | Self::RemoveFile { path, .. } => Some(path),
+            Self::Other { .. } => None,
+        }
+    }
+
+    /// `io::Error`を`AppenderError`に変換する。
+    ///
+    /// `io::Error`が`AppenderError`を保持している場合は、その`AppenderError`を取り出す。
+    ///
+    /// # 引数
+    ///
+    /// - message: `AppenderError`を保持していない場合に、エラーの内容を説明するメッセージ。
+    /// - err: 変換する`io::Error`。
+    ///
+    /// # 戻り値
+    ///
+    /// `AppenderError`インスタンス。
+    pub(crate) fn from_io(message: &'static str, err: io::Error) -> Self {
+        if !err.get_ref().is_some_and(|inner| inner.is::<Self>()) {
+            return Self::Other {
+                message,
+                source: err,
+            };
+        }
+
+        match err.into_inner().map(|inner| inner.downcast::<Self>()) {
+            Some(Ok(err)) => *err,
+            _ => unreachable!("inner error was checked to be AppenderError"),
+        }
+    }
+}
+
+impl Display for AppenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CreateWriter { path, source } => {
+                write!(
+                    f,
+                    "Couldn't create writer for {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            Self::Flush { path, source } => {
+                write!(f, "Couldn't flush {}: {}", path.display(), source)
+            }
+            Self::ReadDir { path, source } => {
+                write!(
+                    f,
+                    "Couldn't find log files in {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            Self::RemoveFile { path, source } => {
+                write!(f, "Couldn't remove log file {}: {}", path.display(), source)
+            }
+            Self::Other { message, source } => write!(f, "{}: {}", message, source),
+        }
+    }
+}
+
+impl Error for AppenderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.io_error())
+    }
+}
+
+impl From<AppenderError> for io::Error {
+    /// 原因となった`io::Error`の種類を引き継いで、`AppenderError`を保持する`io::Error`に変換する。
+    fn from(err: AppenderError) -> Self {
+        io::Error::new(err.kind(), err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_appender_error() {
+        let err = AppenderError::RemoveFile {
+            path: PathBuf::from("/logs/foo-20220527.log"),
+            source: io::Error::from(io::ErrorKind::PermissionDenied),
+        };
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        assert_eq!(err.path(), Some(Path::new("/logs/foo-20220527.log")));
+        assert!(err
+            .to_string()
+            .starts_with("Couldn't remove log file /logs/foo-20220527.log"));
+        assert!(err.source().is_some());
+
+        // `io::Error`に変換した後も、元の`AppenderError`を取り出せるはず
+        let err = AppenderError::from_io("Couldn't build appender", io::Error::from(err));
+        assert!(matches!(err, AppenderError::RemoveFile { .. }));
+        let err = AppenderError::from_io(
+            "Couldn't build appender",
+            io::Error::from(io::ErrorKind::InvalidInput),
+        );
+        assert!(matches!(err, AppenderError::Other { .. }));
+        assert_eq!(err.path(), None);
+    }
+}
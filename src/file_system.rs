@@ -0,0 +1,816 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::{self, OpenOptions},
+    io::{self, Cursor, Read, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::SystemTime,
+};
+
+/// `FileSystem`
+///
+/// `DailyRollingFileAppender`がログファイルを作成、検索及び削除するために使用するファイルシステム。
+/// 単体テストなどでディスクに書き込まずにログを確認したい場合は、`MemoryFileSystem`、または
+/// このトレイトを実装したファイルシステムを`DailyRollingFileAppenderBuilder::file_system`に設定する。
+pub trait FileSystem: Send + Sync {
+    /// ディレクトリを、存在しない親ディレクトリとともに作成する。
+    ///
+    /// # Arguments
+    ///
+    /// * path: 作成するディレクトリのパス。
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// 追記するためにファイルを開く。ファイルが存在しない場合は作成する。
+    ///
+    /// # Arguments
+    ///
+    /// * path: 開くファイルのパス。
+    ///
+    /// # Returns
+    ///
+    /// ファイルの末尾に書き込むライター。
+    fn append(&self, path: &Path) -> io::Result<Box<dyn Write + Send>>;
+
+    /// 書き込むためにファイルを開く。ファイルが存在する場合は空にし、存在しない場合は作成する。
+    ///
+    /// # Arguments
+    ///
+    /// * path: 開くファイルのパス。
+    ///
+    /// # Returns
+    ///
+    /// ファイルの先頭から書き込むライター。
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write + Send>>;
+
+    /// 読み込むためにファイルを開く。
+    ///
+    /// # Arguments
+    ///
+    /// * path: 開くファイルのパス。
+    ///
+    /// # Returns
+    ///
+    /// ファイルの先頭から読み込むリーダー。
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>>;
+
+    /// ファイルのサイズを返却する。
+    ///
+    /// # Arguments
+    ///
+    /// * path: ファイルのパス。
+    ///
+    /// # Returns
+    ///
+    /// ファイルのサイズ(バイト)。
+    fn file_len(&self, path: &Path) -> io::Result<u64>;
+
+    /// ファイルの更新日時を返却する。
+    ///
+    /// 既定の実装は、更新日時を記録しないため常にエラーを返却する。
+    ///
+    /// # Arguments
+    ///
+    /// * path: ファイルのパス。
+    ///
+    /// # Returns
+    ///
+    /// ファイルの更新日時。
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        let _ = path;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "modified time is not supported by this file system",
+        ))
+    }
+
+    /// ディレクトリに存在するファイルの名前を返却する。
+    ///
+    /// # Arguments
+    ///
+    /// * path: ディレクトリのパス。
+    ///
+    /// # Returns
+    ///
+    /// ファイルの名前。順序は問わない。
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<String>>;
+
+    /// ファイルを削除する。
+    ///
+    /// # Arguments
+    ///
+    /// * path: 削除するファイルのパス。ファイルが存在しない場合は`NotFound`エラーを返却する。
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// ファイルの名前を変更する。移動先にファイルが存在する場合は置き換える。
+    ///
+    /// 既定の実装は、名前の変更に対応していないため常にエラーを返却する。その場合、
+    /// `DailyRollingFileAppender`はファイルを複製した後に削除することで、ファイルを移動する。
+    ///
+    /// # Arguments
+    ///
+    /// * from: 名前を変更するファイルのパス。
+    /// * to: 変更後のファイルのパス。異なるファイルシステムのパスを指定した場合は
+    ///   `CrossesDevices`エラーを返却する。
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let _ = (from, to);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "rename is not supported by this file system",
+        ))
+    }
+
+    /// ファイルの内容とメタデータを、ストレージに書き込むまで待機する。
+    ///
+    /// 既定の実装は、ストレージへの同期に対応していないため常にエラーを返却する。
+    ///
+    /// # Arguments
+    ///
+    /// * path: 同期するファイルのパス。
+    fn sync_all(&self, path: &Path) -> io::Result<()> {
+        let _ = path;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "sync is not supported by this file system",
+        ))
+    }
+
+    /// ディレクトリが存在するファイルシステムの空き容量を返却する。
+    ///
+    /// 既定の実装は、空き容量を取得できないため常にエラーを返却する。
+    ///
+    /// # Arguments
+    ///
+    /// * path: ディレクトリのパス。
+    ///
+    /// # Returns
+    ///
+    /// 特権を持たないユーザーが使用できる空き容量(バイト)。
+    fn available_space(&self, path: &Path) -> io::Result<u64> {
+        let _ = path;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "available space is not supported by this file system",
+        ))
+    }
+
+    /// シンボリックリンクを作成、または置き換える。
+    ///
+    /// 既定の実装は、シンボリックリンクに対応していないため常にエラーを返却する。
+    ///
+    /// # Arguments
+    ///
+    /// * directory: シンボリックリンクを作成するディレクトリ。
+    /// * link_name: シンボリックリンクの名前。
+    /// * target: シンボリックリンクが指すファイルの名前(ディレクトリからの相対パス)。
+    fn replace_symlink(&self, directory: &Path, link_name: &str, target: &str) -> io::Result<()> {
+        let _ = (directory, link_name, target);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "symbolic links are not supported by this file system",
+        ))
+    }
+}
+
+/// `OsFileSystem`
+///
+/// オペレーティングシステムのファイルシステム。`DailyRollingFileAppender`の既定のファイルシステム。
+///
+/// Windowsでは、開いているハンドルがあるファイルを削除すると`PermissionDenied`エラーとなるため、
+/// 次の点がUnix系のプラットフォームと異なる。
+///
+/// * ログファイルは`FILE_SHARE_DELETE`を含む共有モードで開き、書き込んでいる間も他のプロセスが
+///   削除や名前の変更をできるようにする。
+/// * ファイルの削除が`PermissionDenied`エラーとなった場合は、他のプロセスがハンドルを閉じるのを
+///   待つために、間隔を延ばしながら`REMOVE_RETRIES`回まで再試行する。ログを読み込む他のプロセスが
+///   `FILE_SHARE_DELETE`を指定せずにファイルを開いている場合は、再試行しても削除できない。
+///
+/// Unix系のプラットフォームでは、作成するファイルとディレクトリの許可モードを設定できる。
+/// 設定しなかった場合は、プロセスのumaskに従う。
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsFileSystem {
+    #[cfg(unix)]
+    file_mode: Option<u32>,
+    #[cfg(unix)]
+    directory_mode: Option<u32>,
+}
+
+/// `MemoryFileSystem`
+///
+/// ファイルの内容をメモリに保持するファイルシステム。
+///
+/// 複製したインスタンスは同じファイルを共有するため、アペンダーに設定したインスタンスの複製から、
+/// 書き込まれたログやローテーションしたファイルを確認できる。ディレクトリは、ファイルのパスの
+/// 親として暗黙的に存在するものとして扱う。
+#[derive(Debug, Default, Clone)]
+pub struct MemoryFileSystem {
+    files: Arc<Mutex<BTreeMap<PathBuf, Vec<u8>>>>,
+    capacity: Option<u64>,
+}
+
+/// `SinkFactory`
+///
+/// ローテーションするたびに、ログを書き込む出力先を作成する。
+///
+/// パイプやソケットなど、ファイル以外の出力先にログを書き込む場合に実装して、
+/// `DailyRollingFileAppenderBuilder::sink_factory`に設定する。日時やサイズによるローテーションと
+/// ファイル名の規則はファイルに記録する場合と同じで、出力先を作成するときに、ログファイルの
+/// パスを引数に呼び出す。`Fn(&Path) -> io::Result<Box<dyn Write + Send>>`を満たす関数も、
+/// このトレイトを実装する。
+pub trait SinkFactory: Send + Sync {
+    /// ログを書き込む出力先を作成する。
+    ///
+    /// # Arguments
+    ///
+    /// * path: 出力先に対応するログファイルのパス。ファイル名に日時と連番を含む。
+    ///
+    /// # Returns
+    ///
+    /// ログを書き込むライター。
+    fn open_sink(&self, path: &Path) -> io::Result<Box<dyn Write + Send>>;
+}
+
+impl<F> SinkFactory for F
+where
+    F: Fn(&Path) -> io::Result<Box<dyn Write + Send>> + Send + Sync,
+{
+    fn open_sink(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        self(path)
+    }
+}
+
+/// `SinkFileSystem`
+///
+/// `SinkFactory`が作成する出力先を、ファイルとして扱うファイルシステム。
+///
+/// 出力先は読み込みや削除ができないため、ディレクトリは常に空として扱い、古いファイルの削除や
+/// 圧縮はしない。作成した出力先のサイズは常に0となるため、ヘッダーは出力先を作成するたびに
+/// 書き込む。ファイルのサイズによるローテーションは、書き込んだバイト数で判定する。
+pub struct SinkFileSystem<S> {
+    factory: S,
+    opened: Mutex<HashSet<PathBuf>>,
+}
+
+impl<S: SinkFactory> SinkFileSystem<S> {
+    /// `SinkFileSystem`を作成する。
+    ///
+    /// # Arguments
+    ///
+    /// * factory: 出力先を作成する`SinkFactory`。
+    ///
+    /// # Returns
+    ///
+    /// `SinkFileSystem`インスタンス。
+    pub fn new(factory: S) -> Self {
+        Self {
+            factory,
+            opened: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn open_sink(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        let sink = self.factory.open_sink(path)?;
+        self.opened
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(path.to_owned());
+
+        Ok(sink)
+    }
+}
+
+impl<S> std::fmt::Debug for SinkFileSystem<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SinkFileSystem").finish_non_exhaustive()
+    }
+}
+
+impl<S: SinkFactory> FileSystem for SinkFileSystem<S> {
+    fn create_dir_all(&self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn append(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        self.open_sink(path)
+    }
+
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        self.open_sink(path)
+    }
+
+    fn open(&self, _path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "reading is not supported by this file system",
+        ))
+    }
+
+    fn file_len(&self, path: &Path) -> io::Result<u64> {
+        // 作成した出力先は、削除されたものとして作成し直さないように、存在するものとして扱う
+        match self
+            .opened
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(path)
+        {
+            true => Ok(0),
+            false => Err(MemoryFileSystem::not_found(path)),
+        }
+    }
+
+    fn read_dir(&self, _path: &Path) -> io::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        Err(MemoryFileSystem::not_found(path))
+    }
+}
+
+/// Windowsで、ファイルの削除を再試行する回数。
+#[cfg(windows)]
+const REMOVE_RETRIES: u32 = 3;
+
+/// Windowsで、ファイルの削除を最初に再試行するまでの間隔。再試行するたびに2倍にする。
+#[cfg(windows)]
+const REMOVE_BACKOFF: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// `MemoryFileSystem`のファイルの末尾に書き込むライター。
+///
+/// 開いた後にファイルが削除された場合、書き込んだ内容は破棄される。
+struct MemoryFile {
+    files: Arc<Mutex<BTreeMap<PathBuf, Vec<u8>>>>,
+    path: PathBuf,
+}
+
+impl OsFileSystem {
+    /// `OsFileSystem`を作成する。
+    ///
+    /// # Returns
+    ///
+    /// `OsFileSystem`インスタンス。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 作成するファイルの許可モード(例: `0o640`)を設定する。
+    ///
+    /// 許可モードは、ファイルを作成するときにのみ適用され、既に存在するファイルの許可モードは
+    /// 変更しない。また、プロセスのumaskで除かれたビットは設定されない。
+    #[cfg(unix)]
+    pub fn file_mode(mut self, file_mode: u32) -> Self {
+        self.file_mode = Some(file_mode);
+
+        self
+    }
+
+    /// 作成するディレクトリの許可モード(例: `0o750`)を設定する。
+    ///
+    /// 許可モードは、ログファイルを作成するディレクトリと、存在しない親ディレクトリを作成するときに
+    /// 適用される。また、プロセスのumaskで除かれたビットは設定されない。
+    #[cfg(unix)]
+    pub fn directory_mode(mut self, directory_mode: u32) -> Self {
+        self.directory_mode = Some(directory_mode);
+
+        self
+    }
+
+    /// ファイルを開くときの、プラットフォームに固有の設定をする。
+    ///
+    /// Windowsでは、開いているファイルを他のプロセスが削除できるように共有モードを、Unix系の
+    /// プラットフォームでは、許可モードが設定されている場合はその許可モードを設定する。
+    ///
+    /// # 引数
+    ///
+    /// - open_options: ファイルを開くときの設定。
+    ///
+    /// # 戻り値
+    ///
+    /// ファイルを開くときの設定。
+    fn platform_options<'a>(&self, open_options: &'a mut OpenOptions) -> &'a mut OpenOptions {
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::OpenOptionsExt;
+
+            // FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE
+            open_options.share_mode(0x1 | 0x2 | 0x4);
+        }
+        #[cfg(unix)]
+        if let Some(file_mode) = self.file_mode {
+            use std::os::unix::fs::OpenOptionsExt;
+
+            open_options.mode(file_mode);
+        }
+
+        open_options
+    }
+}
+
+impl FileSystem for OsFileSystem {
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        if let Some(directory_mode) = self.directory_mode {
+            use std::os::unix::fs::DirBuilderExt;
+
+            builder.mode(directory_mode);
+        }
+
+        builder.create(path)
+    }
+
+    fn append(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        let mut open_options = OpenOptions::new();
+        open_options.append(true).create(true);
+
+        Ok(Box::new(
+            self.platform_options(&mut open_options).open(path)?,
+        ))
+    }
+
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        let mut open_options = OpenOptions::new();
+        open_options.write(true).truncate(true).create(true);
+
+        Ok(Box::new(
+            self.platform_options(&mut open_options).open(path)?,
+        ))
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        Ok(Box::new(fs::File::open(path)?))
+    }
+
+    fn file_len(&self, path: &Path) -> io::Result<u64> {
+        Ok(fs::metadata(path)?.len())
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        fs::metadata(path)?.modified()
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<String>> {
+        Ok(fs::read_dir(path)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect())
+    }
+
+    #[cfg(not(windows))]
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    /// 書き込めるようにファイルを開き直して同期する。同期はファイルごとに行われるため、
+    /// ログを書き込んでいるハンドルでバッファに書き込んだ内容も同期される。
+    fn sync_all(&self, path: &Path) -> io::Result<()> {
+        let mut open_options = OpenOptions::new();
+        open_options.write(true);
+
+        self.platform_options(&mut open_options)
+            .open(path)?
+            .sync_all()
+    }
+
+    /// `statvfs`で、特権を持たないユーザーが使用できるブロック数から空き容量を計算する。
+    #[cfg(unix)]
+    fn available_space(&self, path: &Path) -> io::Result<u64> {
+        use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+
+        let path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+        // SAFETY: `path`はNUL終端された文字列で、`stat`は`statvfs`構造体を書き込める領域を指す
+        if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `statvfs`が成功した場合、`stat`は初期化されている
+        let stat = unsafe { stat.assume_init() };
+
+        #[allow(clippy::unnecessary_cast)] // プラットフォームによって型が異なる
+        Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+    }
+
+    /// `GetDiskFreeSpaceExW`で、呼び出したユーザーが使用できる空き容量を取得する。
+    #[cfg(windows)]
+    fn available_space(&self, path: &Path) -> io::Result<u64> {
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+        let path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let mut available = 0;
+        // SAFETY: `path`はNUL終端されたワイド文字列で、`available`は書き込める`u64`を指す
+        let succeeded = unsafe {
+            GetDiskFreeSpaceExW(
+                path.as_ptr(),
+                &mut available,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        if succeeded == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(available)
+    }
+
+    /// 他のプロセスがファイルを開いている場合に備えて、`PermissionDenied`エラーとなった場合は、
+    /// 間隔を延ばしながら再試行する。
+    #[cfg(windows)]
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let mut backoff = REMOVE_BACKOFF;
+        let mut retries = 0;
+        loop {
+            match fs::remove_file(path) {
+                Err(err)
+                    if err.kind() == io::ErrorKind::PermissionDenied
+                        && retries < REMOVE_RETRIES =>
+                {
+                    std::thread::sleep(backoff);
+                    backoff *= 2;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// 一時的な名前でシンボリックリンクを作成した後、その名前を変更することで、
+    /// シンボリックリンクをアトミックに置き換える。
+    #[cfg(unix)]
+    fn replace_symlink(&self, directory: &Path, link_name: &str, target: &str) -> io::Result<()> {
+        let temp_path = directory.join(format!(".{}.tmp", link_name));
+        // 前回作成に失敗した一時的なシンボリックリンクが残っている場合は削除
+        if let Err(err) = fs::remove_file(&temp_path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                return Err(err);
+            }
+        }
+        std::os::unix::fs::symlink(target, &temp_path)?;
+
+        fs::rename(temp_path, directory.join(link_name))
+    }
+}
+
+impl MemoryFileSystem {
+    /// 空の`MemoryFileSystem`を作成する。
+    ///
+    /// # Returns
+    ///
+    /// `MemoryFileSystem`インスタンス。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 容量を制限した`MemoryFileSystem`を作成する。
+    ///
+    /// 空き容量は、容量からすべてのファイルのサイズの合計を引いた値となる。ただし、容量を超えて
+    /// 書き込んでもエラーにはならない。
+    ///
+    /// # Arguments
+    ///
+    /// * capacity: 容量(バイト)。
+    ///
+    /// # Returns
+    ///
+    /// `MemoryFileSystem`インスタンス。
+    pub fn with_capacity(capacity: u64) -> Self {
+        Self {
+            capacity: Some(capacity),
+            ..Self::default()
+        }
+    }
+
+    /// ファイルの内容を返却する。
+    ///
+    /// # Arguments
+    ///
+    /// * path: ファイルのパス。
+    ///
+    /// # Returns
+    ///
+    /// ファイルの内容。ファイルが存在しない場合はNone。
+    pub fn read(&self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+        self.lock().get(path.as_ref()).cloned()
+    }
+
+    /// 存在するすべてのファイルのパスを返却する。
+    ///
+    /// # Returns
+    ///
+    /// パスの昇順に並べたファイルのパス。
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.lock().keys().cloned().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<PathBuf, Vec<u8>>> {
+        self.files.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn not_found(path: &Path) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} does not exist", path.display()),
+        )
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn create_dir_all(&self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn append(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        self.lock().entry(path.to_owned()).or_default();
+
+        Ok(Box::new(MemoryFile {
+            files: Arc::clone(&self.files),
+            path: path.to_owned(),
+        }))
+    }
+
+    fn create(&self, path: &Path) -> io::Result<Box<dyn Write + Send>> {
+        self.lock().insert(path.to_owned(), Vec::new());
+
+        Ok(Box::new(MemoryFile {
+            files: Arc::clone(&self.files),
+            path: path.to_owned(),
+        }))
+    }
+
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + Send>> {
+        let contents = self.read(path).ok_or_else(|| Self::not_found(path))?;
+
+        Ok(Box::new(Cursor::new(contents)))
+    }
+
+    fn file_len(&self, path: &Path) -> io::Result<u64> {
+        self.lock()
+            .get(path)
+            .map(|contents| contents.len() as u64)
+            .ok_or_else(|| Self::not_found(path))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<String>> {
+        Ok(self
+            .lock()
+            .keys()
+            .filter(|file| file.parent() == Some(path))
+            .filter_map(|file| file.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect())
+    }
+
+    /// 内容はメモリにのみ保持するため、何もしない。
+    fn sync_all(&self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// 容量が制限されていない場合は`u64::MAX`を返却する。
+    fn available_space(&self, _path: &Path) -> io::Result<u64> {
+        let capacity = match self.capacity {
+            Some(capacity) => capacity,
+            None => return Ok(u64::MAX),
+        };
+        let used: u64 = self
+            .lock()
+            .values()
+            .map(|contents| contents.len() as u64)
+            .sum();
+
+        Ok(capacity.saturating_sub(used))
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        self.lock()
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| Self::not_found(path))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut files = self.lock();
+        let contents = files.remove(from).ok_or_else(|| Self::not_found(from))?;
+        files.insert(to.to_owned(), contents);
+
+        Ok(())
+    }
+}
+
+impl Write for MemoryFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut files = self.files.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(contents) = files.get_mut(&self.path) {
+            contents.extend_from_slice(buf);
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_available_space() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        assert!(
+            0 < OsFileSystem::new()
+                .available_space(directory.path())
+                .unwrap()
+        );
+        assert!(OsFileSystem::new()
+            .available_space(&directory.path().join("missing"))
+            .is_err());
+
+        let file_system = MemoryFileSystem::with_capacity(10);
+        let path = Path::new("/logs/foo.log");
+        file_system
+            .append(path)
+            .unwrap()
+            .write_all(b"0123")
+            .unwrap();
+        assert_eq!(file_system.available_space(Path::new("/logs")).unwrap(), 6);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_remove_file_while_reader_is_attached() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let path = directory.path().join("foo-20220527.log");
+        OsFileSystem::new()
+            .append(&path)
+            .unwrap()
+            .write_all(b"Hello")
+            .unwrap();
+
+        // ログを読み込むプロセスと同様に、削除を許可してファイルを開いたまま削除する
+        let reader = OsFileSystem::new().open(&path).unwrap();
+        OsFileSystem::new().remove_file(&path).unwrap();
+        drop(reader);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_memory_file_system() {
+        let file_system = MemoryFileSystem::new();
+        let directory = Path::new("/logs");
+        let path = directory.join("foo.log");
+
+        let mut writer = file_system.append(&path).unwrap();
+        writer.write_all(b"hello ").unwrap();
+        file_system
+            .append(&path)
+            .unwrap()
+            .write_all(b"world")
+            .unwrap();
+        assert_eq!(file_system.read(&path).unwrap(), b"hello world");
+        assert_eq!(file_system.file_len(&path).unwrap(), 11);
+
+        let mut contents = String::new();
+        file_system
+            .open(&path)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "hello world");
+
+        file_system
+            .append(&directory.join("nested").join("bar.log"))
+            .unwrap();
+        assert_eq!(file_system.read_dir(directory).unwrap(), vec!["foo.log"]);
+
+        // 名前を変更したファイルは、内容を引き継ぐ
+        let renamed = directory.join("baz.log");
+        file_system.rename(&path, &renamed).unwrap();
+        assert_eq!(file_system.read(&renamed).unwrap(), b"hello world");
+        file_system.rename(&renamed, &path).unwrap();
+        assert_eq!(
+            file_system.rename(&renamed, &path).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        // 削除したファイルへの書き込みは破棄される
+        file_system.remove_file(&path).unwrap();
+        writer.write_all(b"!").unwrap();
+        assert!(file_system.read(&path).is_none());
+        assert_eq!(
+            file_system.remove_file(&path).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            file_system.paths(),
+            vec![directory.join("nested").join("bar.log")]
+        );
+    }
+}
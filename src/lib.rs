@@ -1,2 +1,11 @@
 pub mod appenders;
+pub mod byte_size;
+#[cfg(feature = "serde")]
+pub mod config;
+pub mod error;
+pub mod file_system;
+pub mod multi_stream;
+pub mod non_blocking;
+pub mod shared;
 mod sync;
+pub mod tee;
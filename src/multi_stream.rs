@@ -0,0 +1,172 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use tracing_subscriber::fmt::writer::MakeWriter;
+
+use crate::appenders::{DailyRollingFileAppender, RollingWriter};
+
+/// `MultiStreamAppender`
+///
+/// `MultiStreamAppender`は、ラベルを付けた複数の`DailyRollingFileAppender`を保持して、ラベルごとに
+/// ログを記録するファイルを分ける。例えば、エラーを`foo-error-20220527.log`に、それ以外のログを
+/// `foo-20220527.log`に記録する場合は、接頭語が`foo-error`と`foo`のアペンダーを、それぞれ
+/// `error`と`all`などのラベルで追加する。
+///
+/// 各ストリームは独立した`DailyRollingFileAppender`であるため、ローテーションや古いファイルの削除も
+/// ストリームごとに、そのアペンダーに設定した基準で行われる。ログのレベルによる振り分けは
+/// `tracing`の役割であるため、ストリームごとのライターを、レベルで絞り込んだレイヤーに設定する。
+#[derive(Debug, Default)]
+pub struct MultiStreamAppender {
+    streams: BTreeMap<String, Arc<DailyRollingFileAppender>>,
+}
+
+/// `StreamWriter`
+///
+/// `MultiStreamAppender`の1つのストリームにログを記録する`MakeWriter`。複製したインスタンスは、
+/// 同じアペンダーに記録する。
+#[derive(Debug, Clone)]
+pub struct StreamWriter(Arc<DailyRollingFileAppender>);
+
+impl MultiStreamAppender {
+    /// ストリームを持たない`MultiStreamAppender`を作成する。
+    ///
+    /// # Returns
+    ///
+    /// `MultiStreamAppender`インスタンス。
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// ラベルを付けてストリームを追加する。
+    ///
+    /// 同じラベルのストリームが既に存在する場合は、そのストリームを置き換える。同じディレクトリに
+    /// 記録するストリームは、それぞれ異なる接頭語のアペンダーにする。
+    ///
+    /// # Arguments
+    ///
+    /// * label: ストリームのラベル。
+    /// * appender: ストリームのログを記録するアペンダー。
+    pub fn stream(mut self, label: impl Into<String>, appender: DailyRollingFileAppender) -> Self {
+        self.streams.insert(label.into(), Arc::new(appender));
+
+        self
+    }
+
+    /// ストリームにログを記録する`MakeWriter`を返却する。
+    ///
+    /// # Arguments
+    ///
+    /// * label: ストリームのラベル。
+    ///
+    /// # Returns
+    ///
+    /// `StreamWriter`インスタンス。ラベルのストリームが存在しない場合はNone。
+    pub fn make_writer_for(&self, label: &str) -> Option<StreamWriter> {
+        self.streams.get(label).cloned().map(StreamWriter)
+    }
+
+    /// ストリームのログを記録しているアペンダーを返却する。
+    ///
+    /// # Arguments
+    ///
+    /// * label: ストリームのラベル。
+    ///
+    /// # Returns
+    ///
+    /// ストリームのアペンダー。ラベルのストリームが存在しない場合はNone。
+    pub fn appender(&self, label: &str) -> Option<&DailyRollingFileAppender> {
+        self.streams.get(label).map(Arc::as_ref)
+    }
+
+    /// ストリームのラベルを、辞書順に返却する。
+    ///
+    /// # Returns
+    ///
+    /// ストリームのラベルのイテレーター。
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.streams.keys().map(String::as_str)
+    }
+}
+
+impl<'a> MakeWriter<'a> for StreamWriter {
+    type Writer = RollingWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.0.make_writer()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    use time::{Date, Duration};
+    use tracing_subscriber::{filter::LevelFilter, layer::SubscriberExt, Layer};
+
+    use crate::appenders::create_daily_log_filename;
+
+    #[test]
+    fn test_multi_stream_appender() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let date = time::OffsetDateTime::now_utc().date();
+        let name = |prefix, date: Date| create_daily_log_filename(prefix, "log", date, 0);
+        // ストリームごとに、保存するファイルの最大数を超える古いファイルを作成
+        for days in 1..=2 {
+            for prefix in ["foo", "foo-error"] {
+                let path = directory
+                    .path()
+                    .join(name(prefix, date - Duration::days(days)));
+                fs::File::create(path).unwrap();
+            }
+        }
+        let build = |prefix: &str, max_count| {
+            DailyRollingFileAppender::builder()
+                .max_count(max_count)
+                .directory(directory.path())
+                .filename_prefix(prefix)
+                .build()
+                .expect("failed to build appender")
+        };
+        let appender = MultiStreamAppender::new()
+            .stream("all", build("foo", 1))
+            .stream("error", build("foo-error", 2));
+        assert_eq!(appender.labels().collect::<Vec<_>>(), vec!["all", "error"]);
+        assert!(appender.make_writer_for("warn").is_none());
+
+        let subscriber = tracing_subscriber::registry()
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(appender.make_writer_for("all").unwrap())
+                    .with_ansi(false),
+            )
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(appender.make_writer_for("error").unwrap())
+                    .with_ansi(false)
+                    .with_filter(LevelFilter::ERROR),
+            );
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("Hello");
+            tracing::error!("World");
+        });
+
+        // エラーのみがエラーのストリームに記録されるはず
+        let all = fs::read_to_string(appender.appender("all").unwrap().current_path()).unwrap();
+        let error = fs::read_to_string(appender.appender("error").unwrap().current_path()).unwrap();
+        assert!(all.contains("Hello") && all.contains("World"));
+        assert!(!error.contains("Hello") && error.contains("World"));
+
+        // 古いファイルは、ストリームごとの基準で削除されるはず
+        let path = |prefix, days| {
+            directory
+                .path()
+                .join(name(prefix, date - Duration::days(days)))
+        };
+        assert!(path("foo", 1).exists() && !path("foo", 2).exists());
+        assert!(path("foo-error", 1).exists() && path("foo-error", 2).exists());
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+}
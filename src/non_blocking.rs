@@ -0,0 +1,373 @@
+use std::{
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+/// `NonBlocking`
+///
+/// `NonBlocking`は、書き込まれたバイト列を容量に制限のあるチャネルに送信して、バックグラウンドの
+/// スレッドに書き込ませるライターである。ログを出力するスレッドは、ファイルへの書き込みを待たない。
+/// バックグラウンドのスレッドは書き込み先(例えば`DailyRollingFileAppender`)を所有して、
+/// チャネルに送信されたバイト列を順番に書き込む。
+///
+/// チャネルが一杯になったときの動作は`OverflowPolicy`で設定する。
+/// `NonBlocking`と同時に作成される`WorkerGuard`を破棄したとき、チャネルに残っているバイト列を
+/// 書き込んでから、バックグラウンドのスレッドを終了する。
+#[derive(Clone, Debug)]
+pub struct NonBlocking {
+    sender: SyncSender<Message>,
+    overflow_policy: OverflowPolicy,
+    dropped_lines: Arc<AtomicUsize>,
+}
+
+/// `WorkerGuard`
+///
+/// `WorkerGuard`は、`NonBlocking`のバックグラウンドのスレッドを管理する。
+/// 破棄したとき、チャネルに残っているバイト列を書き込み、書き込み先をフラッシュしてから、
+/// バックグラウンドのスレッドの終了を待つ。
+/// 破棄するとログが書き込まれなくなるため、アプリケーションが終了するまで保持しなければならない。
+#[must_use]
+#[derive(Debug)]
+pub struct WorkerGuard {
+    sender: SyncSender<Message>,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// `OverflowPolicy`
+///
+/// チャネルが一杯になったときの動作。
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// チャネルに空きができるまで、ログを出力するスレッドを待たせる。
+    ///
+    /// ログは失われないが、書き込みが追いつかない場合、ログを出力するスレッドが遅くなる。
+    #[default]
+    Block,
+    /// チャネルに送信できなかったバイト列を破棄する。
+    ///
+    /// ログを出力するスレッドは待たないが、ログが失われる。破棄した数は
+    /// `NonBlocking::dropped_lines`で取得できる。
+    Drop,
+}
+
+/// `NonBlockingBuilder`
+///
+/// `NonBlocking`を構築するビルダー。
+#[derive(Debug)]
+pub struct NonBlockingBuilder {
+    buffered_lines_limit: usize,
+    overflow_policy: OverflowPolicy,
+    thread_name: String,
+}
+
+/// チャネルに送信できるバイト列の数の既定値。
+const DEFAULT_BUFFERED_LINES_LIMIT: usize = 128_000;
+
+/// バックグラウンドのスレッドの名前の既定値。
+const DEFAULT_THREAD_NAME: &str = "restricted-rolling-file-appender";
+
+/// バックグラウンドのスレッドに送信するメッセージ。
+#[derive(Debug)]
+enum Message {
+    /// 書き込むバイト列。
+    Line(Vec<u8>),
+    /// チャネルに残っているバイト列を書き込んで、スレッドを終了する。
+    Shutdown,
+}
+
+impl NonBlocking {
+    /// 既定の設定で`NonBlocking`を作成する。
+    ///
+    /// # Arguments
+    ///
+    /// * writer: バックグラウンドのスレッドが所有する書き込み先。
+    ///
+    /// # Returns
+    ///
+    /// `NonBlocking`インスタンスと、バックグラウンドのスレッドを管理する`WorkerGuard`。
+    pub fn new<T: Write + Send + 'static>(writer: T) -> (NonBlocking, WorkerGuard) {
+        NonBlockingBuilder::default().finish(writer)
+    }
+
+    /// `NonBlocking`を構築するビルダーを返却する。
+    pub fn builder() -> NonBlockingBuilder {
+        NonBlockingBuilder::default()
+    }
+
+    /// チャネルが一杯だったために破棄したバイト列の数を返却する。
+    ///
+    /// `OverflowPolicy::Block`の場合は、常に0を返却する。
+    pub fn dropped_lines(&self) -> usize {
+        self.dropped_lines.load(Ordering::Acquire)
+    }
+}
+
+impl Write for NonBlocking {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let message = Message::Line(buf.to_vec());
+        match self.overflow_policy {
+            OverflowPolicy::Block => self.sender.send(message).map_err(|_| worker_exited())?,
+            OverflowPolicy::Drop => match self.sender.try_send(message) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    self.dropped_lines.fetch_add(1, Ordering::Release);
+                }
+                Err(TrySendError::Disconnected(_)) => return Err(worker_exited()),
+            },
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // バイト列はバックグラウンドのスレッドが書き込むため、ここではフラッシュしない
+        Ok(())
+    }
+}
+
+impl<'a> tracing_subscriber::fmt::writer::MakeWriter<'a> for NonBlocking {
+    type Writer = NonBlocking;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+impl Default for NonBlockingBuilder {
+    fn default() -> Self {
+        Self {
+            buffered_lines_limit: DEFAULT_BUFFERED_LINES_LIMIT,
+            overflow_policy: OverflowPolicy::default(),
+            thread_name: DEFAULT_THREAD_NAME.to_owned(),
+        }
+    }
+}
+
+impl NonBlockingBuilder {
+    /// チャネルに送信できるバイト列の数を設定する。
+    ///
+    /// 設定しなかった場合は128,000となる。
+    pub fn buffered_lines_limit(mut self, buffered_lines_limit: usize) -> Self {
+        self.buffered_lines_limit = buffered_lines_limit;
+
+        self
+    }
+
+    /// チャネルが一杯になったときの動作を設定する。
+    ///
+    /// 設定しなかった場合は`OverflowPolicy::Block`となる。
+    pub fn overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.overflow_policy = overflow_policy;
+
+        self
+    }
+
+    /// バックグラウンドのスレッドの名前を設定する。
+    pub fn thread_name(mut self, thread_name: impl Into<String>) -> Self {
+        self.thread_name = thread_name.into();
+
+        self
+    }
+
+    /// バックグラウンドのスレッドを開始して、`NonBlocking`を構築する。
+    ///
+    /// # Arguments
+    ///
+    /// * writer: バックグラウンドのスレッドが所有する書き込み先。
+    ///
+    /// # Returns
+    ///
+    /// `NonBlocking`インスタンスと、バックグラウンドのスレッドを管理する`WorkerGuard`。
+    pub fn finish<T: Write + Send + 'static>(self, writer: T) -> (NonBlocking, WorkerGuard) {
+        let (sender, receiver) = mpsc::sync_channel(self.buffered_lines_limit);
+        let handle = thread::Builder::new()
+            .name(self.thread_name)
+            .spawn(move || work(writer, receiver))
+            .expect("failed to spawn the non-blocking writer thread");
+
+        let non_blocking = NonBlocking {
+            sender: sender.clone(),
+            overflow_policy: self.overflow_policy,
+            dropped_lines: Arc::new(AtomicUsize::new(0)),
+        };
+        let guard = WorkerGuard {
+            sender,
+            handle: Some(handle),
+        };
+
+        (non_blocking, guard)
+    }
+}
+
+impl Drop for WorkerGuard {
+    fn drop(&mut self) {
+        // スレッドが終了している場合は送信に失敗するが、そのまま終了を待つ
+        let _ = self.sender.send(Message::Shutdown);
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                eprintln!("The non-blocking writer thread panicked");
+            }
+        }
+    }
+}
+
+/// バックグラウンドのスレッドで、チャネルに送信されたバイト列を書き込む。
+///
+/// チャネルが空になるたびに書き込み先をフラッシュする。`Message::Shutdown`を受信したとき、
+/// またはすべての送信側が破棄されたとき、書き込み先をフラッシュして終了する。
+///
+/// # 引数
+///
+/// - writer: 書き込み先。
+/// - receiver: バイト列を受信するチャネル。
+fn work<T: Write>(mut writer: T, receiver: Receiver<Message>) {
+    while let Ok(Message::Line(line)) = receiver.recv() {
+        if let Err(err) = writer.write_all(&line) {
+            eprintln!("Couldn't write to the log file: {}", err);
+        }
+        // チャネルに残っているバイト列をまとめて書き込んでから、フラッシュする
+        let mut shutdown = false;
+        for message in receiver.try_iter() {
+            match message {
+                Message::Line(line) => {
+                    if let Err(err) = writer.write_all(&line) {
+                        eprintln!("Couldn't write to the log file: {}", err);
+                    }
+                }
+                Message::Shutdown => {
+                    shutdown = true;
+                    break;
+                }
+            }
+        }
+        if let Err(err) = writer.flush() {
+            eprintln!("Couldn't flush the log file: {}", err);
+        }
+        if shutdown {
+            return;
+        }
+    }
+
+    if let Err(err) = writer.flush() {
+        eprintln!("Couldn't flush the log file: {}", err);
+    }
+}
+
+/// バックグラウンドのスレッドが終了しているときに返却するエラーを作成する。
+fn worker_exited() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::BrokenPipe,
+        "the non-blocking writer thread has exited",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        sync::{Barrier, Mutex},
+    };
+
+    use tracing_subscriber::fmt::writer::MakeWriter;
+
+    use super::*;
+    use crate::appenders::DailyRollingFileAppender;
+
+    /// 書き込まれたバイト列を保持するライター。
+    ///
+    /// 最初の書き込みでは、書き込みを開始したことを通知して、`Barrier`で待ち合わせてから書き込む。
+    struct BlockingWriter {
+        written: Arc<Mutex<Vec<u8>>>,
+        started: mpsc::Sender<()>,
+        barrier: Arc<Barrier>,
+    }
+
+    impl Write for BlockingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut written = self.written.lock().unwrap();
+            if written.is_empty() {
+                self.started.send(()).unwrap();
+                self.barrier.wait();
+            }
+            written.extend_from_slice(buf);
+
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_non_blocking_write() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let appender = DailyRollingFileAppender::builder()
+            .directory(directory.path())
+            .filename_prefix("foo")
+            .buffer_capacity(8 * 1024)
+            .build()
+            .expect("failed to build appender");
+        let path = appender.current_path();
+        let (non_blocking, guard) = NonBlocking::new(appender);
+
+        for i in 0..100 {
+            non_blocking
+                .make_writer()
+                .write_all(format!("line {}\n", i).as_bytes())
+                .expect("failed to write to non-blocking writer");
+        }
+        // ガードを破棄すると、すべてのバイト列が書き込まれるはず
+        drop(guard);
+
+        let expected: String = (0..100).map(|i| format!("line {}\n", i)).collect();
+        assert_eq!(fs::read_to_string(path).unwrap(), expected);
+        assert_eq!(non_blocking.dropped_lines(), 0);
+
+        // スレッドが終了した後の書き込みはエラー
+        let result = non_blocking.make_writer().write_all(b"after shutdown");
+        assert_eq!(
+            result.err().map(|err| err.kind()),
+            Some(io::ErrorKind::BrokenPipe)
+        );
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+
+    #[test]
+    fn test_drop_overflow_policy() {
+        let written = Arc::new(Mutex::new(Vec::new()));
+        let barrier = Arc::new(Barrier::new(2));
+        let (started, wait_started) = mpsc::channel();
+        let writer = BlockingWriter {
+            written: Arc::clone(&written),
+            started,
+            barrier: Arc::clone(&barrier),
+        };
+        let (mut non_blocking, guard) = NonBlocking::builder()
+            .buffered_lines_limit(1)
+            .overflow_policy(OverflowPolicy::Drop)
+            .finish(writer);
+
+        // バックグラウンドのスレッドが最初のバイト列の書き込みで待っている間に、チャネルを一杯にする
+        non_blocking.write_all(b"a").unwrap();
+        wait_started.recv().unwrap();
+        non_blocking.write_all(b"b").unwrap();
+        // チャネルが一杯であるため、以降のバイト列は破棄されるが、エラーにはならない
+        non_blocking.write_all(b"c").unwrap();
+        non_blocking.write_all(b"d").unwrap();
+        assert_eq!(non_blocking.dropped_lines(), 2);
+        barrier.wait();
+        drop(guard);
+
+        // 破棄しなかったバイト列のみが書き込まれているはず
+        assert_eq!(*written.lock().unwrap(), b"ab");
+    }
+}
@@ -0,0 +1,122 @@
+use std::{ops::Deref, sync::Arc};
+
+use tracing_subscriber::fmt::writer::MakeWriter;
+
+use crate::appenders::{DailyRollingFileAppender, RollingWriter};
+
+/// `SharedAppender`
+///
+/// `SharedAppender`は、`DailyRollingFileAppender`を複数のスレッドや`tracing`のレイヤーで共有する
+/// `MakeWriter`。`DailyRollingFileAppender`の`make_writer`は`&self`を必要とするため、レイヤーに
+/// 所有させると他のレイヤーから使用できない。`SharedAppender`は内部で`Arc`を保持するため、複製は
+/// 同じアペンダーを共有し、複製したインスタンスをそれぞれのレイヤーやスレッドに渡せる。
+///
+/// `Deref`を実装しているため、`current_path`などの`DailyRollingFileAppender`のメソッドを、そのまま
+/// 呼び出せる。
+#[derive(Debug, Clone)]
+pub struct SharedAppender(Arc<DailyRollingFileAppender>);
+
+impl SharedAppender {
+    /// アペンダーを共有する`SharedAppender`を作成する。
+    ///
+    /// # Arguments
+    ///
+    /// * appender: 共有するアペンダー。
+    ///
+    /// # Returns
+    ///
+    /// `SharedAppender`インスタンス。
+    pub fn new(appender: DailyRollingFileAppender) -> Self {
+        Self(Arc::new(appender))
+    }
+}
+
+impl From<DailyRollingFileAppender> for SharedAppender {
+    fn from(appender: DailyRollingFileAppender) -> Self {
+        Self::new(appender)
+    }
+}
+
+impl Deref for SharedAppender {
+    type Target = DailyRollingFileAppender;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a> MakeWriter<'a> for SharedAppender {
+    type Writer = RollingWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.0.make_writer()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, io::Write, thread};
+
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_shared_appender() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let appender = SharedAppender::new(
+            DailyRollingFileAppender::builder()
+                .directory(directory.path())
+                .filename_prefix("foo")
+                .build()
+                .expect("failed to build appender"),
+        );
+
+        // 複製したインスタンスを、2つのスレッドから同時に使用する
+        let handles: Vec<_> = (0..2)
+            .map(|thread| {
+                let appender = appender.clone();
+                thread::spawn(move || {
+                    for line in 0..100 {
+                        let record = format!("thread {} line {}\n", thread, line);
+                        appender.make_writer().write_all(record.as_bytes()).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        // 2つのレイヤーに、同じアペンダーを共有させる
+        let subscriber = tracing_subscriber::registry()
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(appender.clone())
+                    .with_ansi(false),
+            )
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .with_writer(appender.clone())
+                    .with_ansi(false),
+            );
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("Hello");
+        });
+
+        // 全ての行が、同じファイルに行単位で記録されるはず
+        appender.make_writer().flush().unwrap();
+        let content = fs::read_to_string(appender.current_path()).unwrap();
+        for thread in 0..2 {
+            for line in 0..100 {
+                let expected = format!("thread {} line {}", thread, line);
+                assert!(content.lines().any(|l| l == expected), "{}", expected);
+            }
+        }
+        assert_eq!(content.lines().count(), 202);
+        assert_eq!(content.matches("Hello").count(), 2);
+        drop(appender);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+}
@@ -35,6 +35,7 @@ mod std_impl {
         }
 
         #[inline]
+        #[allow(dead_code)] // may be used later;
         pub(crate) fn get_mut(&mut self) -> &mut T {
             self.inner.get_mut().unwrap_or_else(PoisonError::into_inner)
         }
@@ -72,3 +73,35 @@ mod std_impl {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::Arc, thread};
+
+    #[test]
+    fn test_rw_lock() {
+        let mut lock = RwLock::new(0);
+        *lock.get_mut() = 1;
+
+        // 読み込みロックを獲得している間は、書き込みロックを獲得できないはず
+        let reader = lock.read();
+        assert_eq!(*lock.try_read().unwrap(), 1);
+        assert!(lock.try_write().is_none());
+        drop(reader);
+        *lock.try_write().unwrap() = 2;
+
+        // ロックを獲得したスレッドがパニックしても、どちらの実装でも引き続きロックを獲得できるはず
+        let lock = Arc::new(lock);
+        let cloned = lock.clone();
+        let result = thread::spawn(move || {
+            let mut writer = cloned.write();
+            *writer = 3;
+            panic!("panic while holding the lock");
+        })
+        .join();
+        assert!(result.is_err());
+        assert_eq!(*lock.read(), 3);
+        assert_eq!(*lock.write(), 3);
+    }
+}
@@ -0,0 +1,204 @@
+use std::io::{self, Write};
+
+use tracing_subscriber::fmt::writer::MakeWriter;
+
+use crate::appenders::{DailyRollingFileAppender, RollingWriter};
+
+/// `TeeAppender`
+///
+/// `TeeAppender`は、2つの`DailyRollingFileAppender`に同じログを記録する。例えば、ログを記録する
+/// ディレクトリを移行する間に、移行前と移行後の両方のディレクトリに記録する場合に使用する。
+///
+/// 各アペンダーは独立しているため、ローテーションや古いファイルの削除は、アペンダーごとに、
+/// そのアペンダーに設定した基準で行われる。書き込みとフラッシュは、一方のアペンダーでエラーが
+/// 発生しても、もう一方のアペンダーに続けて行い、最初に発生したエラーを返却する。
+#[derive(Debug)]
+pub struct TeeAppender {
+    primary: DailyRollingFileAppender,
+    secondary: DailyRollingFileAppender,
+}
+
+/// `TeeWriter`
+///
+/// `TeeAppender`の`MakeWriter`が返却するライター。書き込まれた内容を、両方のアペンダーの
+/// ライターに書き込む。
+#[derive(Debug)]
+pub struct TeeWriter<'a>(RollingWriter<'a>, RollingWriter<'a>);
+
+impl TeeAppender {
+    /// 2つのアペンダーに同じログを記録する`TeeAppender`を作成する。
+    ///
+    /// # Arguments
+    ///
+    /// * primary: ログを記録するアペンダー。
+    /// * secondary: 同じログを記録するもう1つのアペンダー。
+    ///
+    /// # Returns
+    ///
+    /// `TeeAppender`インスタンス。
+    pub fn new(primary: DailyRollingFileAppender, secondary: DailyRollingFileAppender) -> Self {
+        Self { primary, secondary }
+    }
+
+    /// ログを記録するアペンダーを返却する。
+    ///
+    /// # Returns
+    ///
+    /// `new`の`primary`に指定したアペンダー。
+    pub fn primary(&self) -> &DailyRollingFileAppender {
+        &self.primary
+    }
+
+    /// 同じログを記録するもう1つのアペンダーを返却する。
+    ///
+    /// # Returns
+    ///
+    /// `new`の`secondary`に指定したアペンダー。
+    pub fn secondary(&self) -> &DailyRollingFileAppender {
+        &self.secondary
+    }
+
+    /// `TeeAppender`を、2つのアペンダーに分解する。
+    ///
+    /// # Returns
+    ///
+    /// `primary`と`secondary`のアペンダーのタプル。
+    pub fn into_inner(self) -> (DailyRollingFileAppender, DailyRollingFileAppender) {
+        (self.primary, self.secondary)
+    }
+}
+
+impl Write for TeeAppender {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        write_both(&mut self.primary, &mut self.secondary, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        flush_both(&mut self.primary, &mut self.secondary)
+    }
+}
+
+impl<'a> MakeWriter<'a> for TeeAppender {
+    type Writer = TeeWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        TeeWriter(self.primary.make_writer(), self.secondary.make_writer())
+    }
+}
+
+impl Write for TeeWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        write_both(&mut self.0, &mut self.1, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        flush_both(&mut self.0, &mut self.1)
+    }
+}
+
+/// 2つのライターに、同じ内容を全て書き込む。
+///
+/// 一方のライターにしか書き込まれない部分が残らないように、それぞれのライターに全て書き込む。
+///
+/// # 引数
+///
+/// - primary: 書き込むライター。
+/// - secondary: 同じ内容を書き込むもう1つのライター。
+/// - buf: 書き込む内容。
+///
+/// # 戻り値
+///
+/// 両方のライターに書き込めた場合は、書き込んだバイト数。そうでない場合は、最初に発生したエラー。
+fn write_both(
+    primary: &mut impl Write,
+    secondary: &mut impl Write,
+    buf: &[u8],
+) -> io::Result<usize> {
+    let primary = primary.write_all(buf);
+    let secondary = secondary.write_all(buf);
+
+    primary.and(secondary).map(|_| buf.len())
+}
+
+/// 2つのライターをフラッシュする。
+///
+/// # 引数
+///
+/// - primary: フラッシュするライター。
+/// - secondary: フラッシュするもう1つのライター。
+///
+/// # 戻り値
+///
+/// 両方のライターをフラッシュできた場合は`()`。そうでない場合は、最初に発生したエラー。
+fn flush_both(primary: &mut impl Write, secondary: &mut impl Write) -> io::Result<()> {
+    let primary = primary.flush();
+    let secondary = secondary.flush();
+
+    primary.and(secondary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    use time::Duration;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_tee_appender() {
+        let directory = tempfile::tempdir().expect("failed to create temp dir");
+        let old = directory.path().join("old");
+        let new = directory.path().join("new");
+        let build = |directory: &std::path::Path, max_count| {
+            DailyRollingFileAppender::builder()
+                .max_count(max_count)
+                .directory(directory)
+                .filename_prefix("foo")
+                .build()
+                .expect("failed to build appender")
+        };
+        // 古いファイルは、アペンダーごとの基準で削除されるはず
+        let date = time::OffsetDateTime::now_utc().date();
+        let stale = |days| {
+            crate::appenders::create_daily_log_filename(
+                "foo",
+                "log",
+                date - Duration::days(days),
+                0,
+            )
+        };
+        for directory in [&old, &new] {
+            fs::create_dir_all(directory).unwrap();
+            for days in 1..=2 {
+                fs::write(directory.join(stale(days)), "Old").unwrap();
+            }
+        }
+        let mut appender = TeeAppender::new(build(&old, 1), build(&new, 2));
+        assert!(!old.join(stale(2)).exists() && new.join(stale(2)).exists());
+
+        appender.write_all(b"Hello\n").unwrap();
+        appender.flush().unwrap();
+        let primary = appender.primary().current_path();
+        let secondary = appender.secondary().current_path();
+        assert!(primary.starts_with(&old) && secondary.starts_with(&new));
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(appender)
+                .with_ansi(false),
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("World");
+        });
+
+        // 両方のディレクトリに、同じ内容が記録されるはず
+        let primary = fs::read_to_string(primary).unwrap();
+        let secondary = fs::read_to_string(secondary).unwrap();
+        assert!(primary.starts_with("Hello\n") && primary.contains("World"));
+        assert_eq!(primary, secondary);
+
+        directory
+            .close()
+            .expect("Failed to explicitly close TempDir. TempDir should delete once out of scope.")
+    }
+}